@@ -1,5 +1,6 @@
 use std::env;
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 
 use gl_generator::Api;
@@ -17,4 +18,34 @@ fn main() {
     Registry::new(Api::Gl, (4, 5), Profile::Core, Fallbacks::All, [])
         .write_bindings(GlobalGenerator, &mut file)
         .unwrap();
+
+    let mut file = File::create(Path::new(&dest).join("swizzles.rs")).unwrap();
+    write_swizzles(&mut file).unwrap();
+}
+
+// every 2, 3 and 4 component combination of the components of Vector<T, 2..=4>
+fn write_swizzles(out: &mut impl Write) -> std::io::Result<()> {
+    const NAMES: [char; 4] = ['x', 'y', 'z', 'w'];
+
+    for n in 2..=4usize {
+        writeln!(out, "impl<T: Copy> Vector<T, {n}> {{")?;
+        for len in 2..=4 {
+            for i in 0..n.pow(len) {
+                // the components, as the base-n digits of i
+                let idx: Vec<usize> = (0..len).rev().map(|d| i / n.pow(d) % n).collect();
+                let name: String = idx.iter().map(|&c| NAMES[c]).collect();
+                let body: Vec<String> = idx.iter().map(|c| format!("self.0[{c}]")).collect();
+
+                writeln!(out, "    #[inline]")?;
+                writeln!(
+                    out,
+                    "    pub fn {name}(self) -> Vector<T, {len}> {{ Vector([{}]) }}",
+                    body.join(", ")
+                )?;
+            }
+        }
+        writeln!(out, "}}")?;
+    }
+
+    Ok(())
 }
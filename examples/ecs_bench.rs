@@ -0,0 +1,145 @@
+// runs a small simulation through the ecs, checking the results against plain Vecs
+// build with `--release`, the time per run of each system is printed
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use engine_2d::ecs::Changed;
+use engine_2d::ecs::Entity;
+use engine_2d::ecs::Schedule;
+use engine_2d::ecs::Stage;
+use engine_2d::ecs::Time;
+use engine_2d::ecs::With;
+use engine_2d::ecs::Without;
+use engine_2d::ecs::World;
+use engine_2d::math::Rng;
+use engine_2d::math::Transform2D;
+use engine_2d::math::Vec2;
+
+const ENTITIES: usize = 100_000;
+const FRAMES: u32 = 100;
+const DT: Duration = Duration::from_millis(16);
+const WORLD: f32 = 4096.0;
+
+struct Velocity(Vec2);
+struct Lifetime(f32);
+struct Frozen;
+
+// how many entities moved on the previous frame, counted with change detection
+struct Moved(usize);
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_op = start.elapsed() / iterations;
+    println!("{name:<32} {per_op:?}");
+}
+
+fn movement(world: &mut World) {
+    let dt = world.resource::<Time>().dt();
+    world
+        .query_filtered::<(&mut Transform2D, &Velocity), Without<Frozen>>()
+        .for_each(|(mut transform, velocity)| transform.translate(velocity.0 * dt));
+}
+
+fn aging(world: &mut World) {
+    let dt = world.resource::<Time>().dt();
+    world
+        .query::<(Entity, &mut Lifetime)>()
+        .for_each(|(entity, mut lifetime)| {
+            lifetime.0 -= dt;
+            if lifetime.0 <= 0.0 {
+                world.commands().despawn(entity);
+            }
+        });
+}
+
+fn count_moved(world: &mut World) {
+    let moved = world
+        .query_filtered::<Entity, Changed<Transform2D>>()
+        .count();
+    world.insert_resource(Moved(moved));
+}
+
+fn main() {
+    let mut rng = Rng::new(7);
+    let mut world = World::new();
+    let mut expected = Vec::new();
+
+    for i in 0..ENTITIES {
+        let position = Vec2::new(rng.range(0.0..WORLD), rng.range(0.0..WORLD));
+        let velocity = rng.unit_vec2() * rng.range(10.0..100.0);
+        let lifetime = rng.range(0.5..2.0);
+        let frozen = i % 10 == 0;
+
+        let entity = world.spawn((
+            Transform2D::from_position(position),
+            Velocity(velocity),
+            Lifetime(lifetime),
+        ));
+        if frozen {
+            world.insert(entity, Frozen);
+        }
+        expected.push((entity, position, velocity, lifetime, frozen));
+    }
+    assert_eq!(
+        world.query_filtered::<Entity, With<Frozen>>().count(),
+        ENTITIES / 10
+    );
+
+    let mut schedule = Schedule::new()
+        .with_system(Stage::Tick, movement)
+        .with_system(Stage::Tick, aging)
+        .with_system(Stage::PostTick, count_moved);
+
+    // the same simulation by hand, every frame
+    let dt = DT.as_secs_f32();
+    for frame in 0..FRAMES {
+        schedule.tick(&mut world, DT);
+
+        expected.retain_mut(|(_, position, velocity, lifetime, frozen)| {
+            if !*frozen {
+                *position += *velocity * dt;
+            }
+            *lifetime -= dt;
+            *lifetime > 0.0
+        });
+        // on the first frame, every entity was just spawned
+        let moved = expected.iter().filter(|e| frame == 0 || !e.4).count();
+        assert_eq!(world.len(), expected.len());
+        assert_eq!(world.resource::<Moved>().0, moved);
+    }
+
+    for (entity, position, ..) in &expected {
+        let transform = world.get::<Transform2D>(*entity).unwrap();
+        assert!((transform.position() - *position).len() < 1e-3);
+    }
+    println!("{} entities left after {FRAMES} frames", world.len());
+
+    // timings on a fresh world that doesn't shrink
+    let mut world = World::new();
+    for _ in 0..ENTITIES {
+        let position = Vec2::new(rng.range(0.0..WORLD), rng.range(0.0..WORLD));
+        world.spawn((
+            Transform2D::from_position(position),
+            Velocity(rng.unit_vec2()),
+        ));
+    }
+    world.insert_resource(Time::default());
+
+    bench("movement system", FRAMES, || movement(&mut world));
+    bench("changed query", FRAMES, || {
+        black_box(
+            world
+                .query_filtered::<Entity, Changed<Transform2D>>()
+                .count(),
+        );
+        world.clear_trackers();
+    });
+    bench("spawn and despawn", ENTITIES as u32, || {
+        let entity = world.spawn((Transform2D::default(), Velocity(Vec2::zero())));
+        world.despawn(black_box(entity));
+    });
+}
@@ -0,0 +1,155 @@
+// compares drawing many copies of a sprite one by one against a single instanced draw
+// run with `--instanced` to use the instanced path, the average frame time is printed every second
+use std::path::Path;
+use std::time::Duration;
+
+use engine_2d::math::Mat3;
+use engine_2d::math::Vec2;
+use engine_2d::render;
+use engine_2d::render::shader::IShaderBuilder;
+use engine_2d::render::shader::PartType;
+use engine_2d::render::shader::Shader;
+use engine_2d::render::shader::ShaderBuilder;
+use engine_2d::render::shader::ShaderPart;
+use engine_2d::render::sprite::ISprite;
+use engine_2d::render::sprite::Instance;
+use engine_2d::render::sprite::Sprite;
+use engine_2d::render::sprite::INSTANCED_FRAGMENT_SHADER;
+use engine_2d::render::sprite::INSTANCED_VERTEX_SHADER;
+use engine_2d::render::texture::ITexture;
+use engine_2d::render::texture::Texture;
+use engine_2d::time::Timer;
+use engine_2d::window::DrawContext;
+use engine_2d::window::Engine;
+use engine_2d::window::GameLoop;
+use engine_2d::window::WindowManager;
+
+const COUNT: usize = 10_000;
+
+const VERTEX_SHADER: &str = r#"
+#version 450 core
+
+uniform mat3 uSprite;
+
+layout(location = 0)
+in vec2 aPos;
+
+layout(location = 1)
+in vec2 aUV;
+
+out vec2 texUV;
+
+void main() {
+    texUV = aUV;
+    gl_Position = vec4(uSprite * vec3(aPos, 1.0), 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 450 core
+
+uniform sampler2D uTexture;
+
+in vec2 texUV;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = texture(uTexture, texUV);
+}
+"#;
+
+fn make_shader<'c>(ctx: &'c DrawContext, vert: &str, frag: &str) -> Shader<'c> {
+    ShaderBuilder::new(ctx)
+        .add_part(ShaderPart {
+            type_: PartType::Vertex,
+            source_code: vert,
+        })
+        .unwrap()
+        .add_part(ShaderPart {
+            type_: PartType::Fragment,
+            source_code: frag,
+        })
+        .unwrap()
+        .verify()
+        .unwrap()
+}
+
+struct Bench<'a> {
+    sprite: Sprite<'a>,
+    shader: Shader<'a>,
+    instanced_shader: Shader<'a>,
+    instances: Vec<Instance>,
+    instanced: bool,
+    report: Timer,
+    frames: u32,
+    elapsed: Duration,
+}
+
+impl<'a> GameLoop<'a> for Bench<'a> {
+    fn setup(ctx: &'a DrawContext, _wm: &mut WindowManager) -> Self {
+        let texture = Texture::from_file(ctx, Path::new("deer.png")).unwrap();
+        let sprite = Sprite::new(ctx, texture);
+
+        // lay the sprites out in a grid covering the screen
+        let side = (COUNT as f32).sqrt().ceil() as usize;
+        let step = 2.0 / side as f32;
+        let instances = (0..COUNT)
+            .map(|i| {
+                let pos = Vec2::new(
+                    -1.0 + step * (0.5 + (i % side) as f32),
+                    -1.0 + step * (0.5 + (i / side) as f32),
+                );
+                let transform = Mat3::translate(pos) * Mat3::scale(0.5 * Vec2::new(step, step));
+                Instance::new(transform)
+            })
+            .collect();
+
+        Self {
+            sprite,
+            shader: make_shader(ctx, VERTEX_SHADER, FRAGMENT_SHADER),
+            instanced_shader: make_shader(ctx, INSTANCED_VERTEX_SHADER, INSTANCED_FRAGMENT_SHADER),
+            instances,
+            instanced: std::env::args().any(|a| a == "--instanced"),
+            report: Timer::new(Duration::from_secs(1)),
+            frames: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn tick(&mut self, dt: Duration, _wm: &mut WindowManager) {
+        self.frames += 1;
+        self.elapsed += dt;
+        if self.report.tick(dt) {
+            let mode = if self.instanced {
+                "instanced"
+            } else {
+                "per-sprite"
+            };
+            println!(
+                "{mode}: {:?} per frame ({COUNT} sprites)",
+                self.elapsed / self.frames
+            );
+            self.frames = 0;
+            self.elapsed = Duration::ZERO;
+        }
+    }
+
+    fn draw(&mut self, _ctx: &'a DrawContext, _wm: &mut WindowManager) {
+        render::clear();
+        if self.instanced {
+            self.sprite
+                .draw_instanced(&self.instanced_shader, &self.instances);
+        } else {
+            for i in &self.instances {
+                self.sprite.draw(&self.shader, i.transform);
+            }
+        }
+    }
+}
+
+fn main() {
+    let wm = WindowManager::new(800, 800, "instancing benchmark");
+    let mut engine = Engine::new(wm);
+    engine.run::<Bench>()
+}
@@ -0,0 +1,74 @@
+// compares the SSE2 matrix math against the portable implementation
+// build with `--release`, the time per operation of each version is printed
+// the results are checked to be identical by the tests of `math::simd`
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use engine_2d::math::simd;
+use engine_2d::math::Mat3;
+use engine_2d::math::Mat4;
+use engine_2d::math::Vec2;
+use engine_2d::math::Vec3;
+use engine_2d::math::Vec4;
+
+const ITERATIONS: u32 = 10_000_000;
+const BATCHES: u32 = 1_000;
+const POINTS: usize = 10_000;
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_op: Duration = start.elapsed() / iterations;
+    println!("{name:<32} {per_op:?}");
+}
+
+fn main() {
+    let a = Mat3::from_trs(Vec2::new(3.0, -2.0), 30.0, Vec2::new(2.0, 0.5));
+    let b = Mat3::from_trs(Vec2::new(-1.0, 4.0), -75.0, Vec2::new(0.25, 3.0));
+    let v = Vec3::new(1.5, -0.5, 1.0);
+
+    bench("mat3 * mat3 (scalar)", ITERATIONS, || {
+        black_box(simd::scalar::mat3_mul(black_box(a), black_box(b)));
+    });
+    bench("mat3 * mat3 (simd)", ITERATIONS, || {
+        black_box(simd::mat3_mul(black_box(a), black_box(b)));
+    });
+    bench("mat3 * vec3 (scalar)", ITERATIONS, || {
+        black_box(simd::scalar::mat3_mul_vec(black_box(a), black_box(v)));
+    });
+    bench("mat3 * vec3 (simd)", ITERATIONS, || {
+        black_box(simd::mat3_mul_vec(black_box(a), black_box(v)));
+    });
+
+    let a4 = Mat4::from_array(std::array::from_fn(|i| i as f32 * 0.25 - 1.0));
+    let b4 = a4.transpose();
+    let v4 = Vec4::new(1.5, -0.5, 2.0, 1.0);
+    bench("mat4 * mat4 (scalar)", ITERATIONS, || {
+        black_box(simd::scalar::mat4_mul(black_box(a4), black_box(b4)));
+    });
+    bench("mat4 * mat4 (simd)", ITERATIONS, || {
+        black_box(simd::mat4_mul(black_box(a4), black_box(b4)));
+    });
+    bench("mat4 * vec4 (scalar)", ITERATIONS, || {
+        black_box(simd::scalar::mat4_mul_vec(black_box(a4), black_box(v4)));
+    });
+    bench("mat4 * vec4 (simd)", ITERATIONS, || {
+        black_box(simd::mat4_mul_vec(black_box(a4), black_box(v4)));
+    });
+
+    // the points are transformed over and over, without scaling they stay in range
+    let m = Mat3::from_trs(Vec2::new(3.0, -2.0), 30.0, Vec2::new(1.0, 1.0));
+    let mut points: Vec<Vec2> = (0..POINTS)
+        .map(|i| Vec2::new(i as f32, (POINTS - i) as f32 * 0.5))
+        .collect();
+
+    bench("transform 10k points (scalar)", BATCHES, || {
+        simd::scalar::transform_points(&m, black_box(&mut points));
+    });
+    bench("transform 10k points (simd)", BATCHES, || {
+        simd::transform_points(&m, black_box(&mut points));
+    });
+}
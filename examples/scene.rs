@@ -0,0 +1,102 @@
+// loads a level made of prefab instances, edits it, and checks that saving and loading it again
+// gives the same scene, the saved level is printed
+use engine_2d::math::Vec2;
+use engine_2d::math::Vec3;
+use engine_2d::scene::Node;
+use engine_2d::scene::Prefabs;
+use engine_2d::scene::Scene;
+use engine_2d::scene::TextAttachment;
+
+const ENEMY: &str = r##"{
+  "name": "enemy",
+  "sprite": {"name": "goblin", "layer": 1},
+  "children": [
+    {
+      "name": "weapon",
+      "position": [8, 0],
+      "sprite": {"name": "sword", "z": 0.5},
+      "children": [{"name": "flash", "shape": {"circle": [0, 0, 4], "color": "#ffff00"}}]
+    },
+    {"name": "health_bar", "position": [0, -12], "shape": {"rect": [-8, -1, 8, 1]}}
+  ]
+}"##;
+
+// a prefab can be an instance of another one
+const BOSS: &str = r##"{
+  "name": "boss",
+  "prefab": "enemy",
+  "scale": [2, 2],
+  "overrides": {"weapon": {"sprite": {"name": "axe"}}}
+}"##;
+
+const LEVEL: &str = r##"{
+  "name": "level_1",
+  "children": [
+    {
+      "name": "player",
+      "position": [64, 32],
+      "rotation": 90,
+      "sprite": {"name": "hero", "blend": "opaque"},
+      "children": [{"name": "weapon", "position": [8, 0], "sprite": {"name": "sword"}}]
+    },
+    {"name": "title", "text": {"text": "Level 1", "size": 32, "color": "#ffcc00"}},
+    {"name": "floor", "shape": {"polygon": [[0, 0], [640, 0], [640, 16], [0, 16]]}},
+    {
+      "name": "goblin",
+      "prefab": "enemy",
+      "position": [300, 32],
+      "overrides": {"weapon/flash": null},
+      "children": [{"name": "aura", "shape": {"circle": [0, 0, 64], "color": "#ff000080"}}]
+    },
+    {"name": "king", "prefab": "boss", "position": [500, 32]}
+  ]
+}"##;
+
+fn main() {
+    let mut prefabs = Prefabs::new();
+    prefabs.insert("enemy", ENEMY).unwrap();
+    prefabs.insert("boss", BOSS).unwrap();
+    let mut scene = Scene::from_json(LEVEL, &prefabs).unwrap();
+
+    // the instances are expanded
+    assert_eq!(scene.len(), 13);
+    assert!(scene.find("goblin/weapon/flash").is_none());
+    assert!(scene.find("goblin/aura").is_some());
+    let axe = scene.find("king/weapon").unwrap();
+    assert_eq!(scene[axe].sprite.as_ref().unwrap().sprite, "axe");
+    assert_eq!(scene.path_of(axe), "king/weapon");
+
+    // the weapon is 8 units in front of the player, turned 90 degrees clockwise
+    let weapon = scene.find("player/weapon").unwrap();
+    let p = scene.world_transform(weapon) * Vec3::new(0.0, 0.0, 1.0);
+    assert!((Vec2::new(p.x, p.y) - Vec2::new(64.0, 24.0)).len() < 1e-4);
+    let goblin = scene.find_from(weapon, "../../goblin").unwrap();
+
+    // edits to an instance are saved as overrides of its prefab
+    let bar = scene.find_from(goblin, "health_bar").unwrap();
+    scene[bar].transform.set_position(Vec2::new(0.0, -16.0));
+    scene.remove(scene.find("king/health_bar").unwrap());
+    let grunt = scene.instantiate(&prefabs, "enemy", scene.root()).unwrap();
+    scene[grunt].name = "grunt".to_string();
+    scene.add(
+        grunt,
+        Node::new("label").with_text(TextAttachment::new("zzz", 12.0)),
+    );
+
+    let saved = scene.to_json(&prefabs);
+    println!("{saved}");
+    let loaded = Scene::from_json(&saved, &prefabs).unwrap();
+    assert_eq!(loaded.to_json(&prefabs), saved);
+    assert_eq!(loaded.len(), scene.len());
+    for ((_, a), (_, b)) in scene.iter().zip(loaded.iter()) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.transform, b.transform);
+        assert_eq!(a.prefab, b.prefab);
+    }
+
+    // without the prefabs, the instances are saved in full
+    let full = scene.to_json(&Prefabs::new());
+    let loaded = Scene::from_json(&full, &Prefabs::new()).unwrap();
+    assert_eq!(loaded.to_json(&Prefabs::new()), full);
+    assert_eq!(loaded.len(), scene.len());
+}
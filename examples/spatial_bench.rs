@@ -0,0 +1,152 @@
+// compares the spatial indices against a brute force scan, on random boxes and queries
+// build with `--release`, the time per query of each version is printed
+// the results are checked against the scan by the tests of `data::spatial`
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use engine_2d::data::Bvh;
+use engine_2d::data::ItemId;
+use engine_2d::data::QuadTree;
+use engine_2d::data::SpatialHash;
+use engine_2d::data::SpatialIndex;
+use engine_2d::math::Aabb;
+use engine_2d::math::Circle;
+use engine_2d::math::Ray;
+use engine_2d::math::Rng;
+use engine_2d::math::Shape;
+use engine_2d::math::Vec2;
+
+const ITEMS: usize = 10_000;
+const QUERIES: usize = 1_000;
+const WORLD: f32 = 4096.0;
+
+fn bench(name: &str, iterations: usize, mut f: impl FnMut(usize)) {
+    let start = Instant::now();
+    for i in 0..iterations {
+        f(i);
+    }
+    let per_op: Duration = start.elapsed() / iterations as u32;
+    println!("{name:<32} {per_op:?}");
+}
+
+fn random_point(rng: &mut Rng) -> Vec2 {
+    Vec2::new(rng.range(0.0..WORLD), rng.range(0.0..WORLD))
+}
+
+fn random_box(rng: &mut Rng, max_size: f32) -> Aabb {
+    let min = random_point(rng);
+    let size = Vec2::new(rng.range(1.0..max_size), rng.range(1.0..max_size));
+    Aabb::new(min, min + size)
+}
+
+// the same items and queries for every index
+struct Scene {
+    boxes: Vec<Aabb>,
+    moved: Vec<Aabb>,
+    rects: Vec<Aabb>,
+    circles: Vec<Circle>,
+    rays: Vec<Ray>,
+}
+
+impl Scene {
+    fn new(rng: &mut Rng) -> Self {
+        Self {
+            boxes: (0..ITEMS).map(|_| random_box(rng, 64.0)).collect(),
+            moved: (0..ITEMS).map(|_| random_box(rng, 64.0)).collect(),
+            rects: (0..QUERIES).map(|_| random_box(rng, 512.0)).collect(),
+            circles: (0..QUERIES)
+                .map(|_| Circle::new(random_point(rng), rng.range(1.0..256.0)))
+                .collect(),
+            rays: (0..QUERIES)
+                .map(|_| Ray::new(random_point(rng), rng.unit_vec2()))
+                .collect(),
+        }
+    }
+}
+
+// times every query, on the items left after moving and removing some
+fn run(name: &str, index: &mut impl SpatialIndex<usize>, scene: &Scene) {
+    let Scene {
+        rects,
+        circles,
+        rays,
+        ..
+    } = scene;
+    let mut boxes = scene.boxes.clone();
+    let ids: Vec<ItemId> = (0..ITEMS).map(|i| index.insert(boxes[i], i)).collect();
+
+    // move and remove some, like a game would
+    for i in (0..ITEMS).step_by(3) {
+        index.update(ids[i], scene.moved[i]);
+        boxes[i] = scene.moved[i];
+    }
+    for i in (0..ITEMS).step_by(7) {
+        index.remove(ids[i]);
+    }
+
+    bench(&format!("{name}: rect query"), QUERIES, |q| {
+        let mut count = 0;
+        index.query_rect(black_box(&rects[q]), |_, _| count += 1);
+        black_box(count);
+    });
+    bench(&format!("{name}: circle query"), QUERIES, |q| {
+        let mut count = 0;
+        index.query_circle(black_box(&circles[q]), |_, _| count += 1);
+        black_box(count);
+    });
+    bench(&format!("{name}: raycast"), QUERIES, |q| {
+        black_box(index.raycast(black_box(&rays[q]), 1024.0));
+    });
+    bench(&format!("{name}: 5 nearest"), QUERIES, |q| {
+        black_box(index.nearest(black_box(circles[q].center), 5));
+    });
+    bench(&format!("{name}: update"), ITEMS, |i| {
+        let b = boxes[i];
+        let offset = Vec2::new(1.0, -1.0);
+        index.update(ids[i], Aabb::new(b.min + offset, b.max + offset));
+    });
+}
+
+fn main() {
+    let scene = Scene::new(&mut Rng::new(7));
+    let Scene {
+        boxes, rects, rays, ..
+    } = &scene;
+
+    bench("brute force: rect query", QUERIES, |q| {
+        let rect = black_box(&rects[q]);
+        black_box(boxes.iter().filter(|b| b.overlaps(rect)).count());
+    });
+    bench("brute force: raycast", QUERIES, |q| {
+        let ray = black_box(&rays[q]);
+        let hits = boxes.iter().filter_map(|b| b.raycast(ray, 1024.0));
+        black_box(hits.count());
+    });
+
+    let region = Aabb::new(Vec2::zero(), Vec2::splat(WORLD + 64.0));
+    run("quadtree", &mut QuadTree::new(region), &scene);
+    run("bvh", &mut Bvh::new(), &scene);
+    let mut hash = SpatialHash::new(64.0);
+    run("spatial hash", &mut hash, &scene);
+
+    bench("spatial hash: pairs", 10, |_| {
+        let mut count = 0;
+        hash.pairs(|_, _| count += 1);
+        black_box(count);
+    });
+
+    // a tree built at once, from the same items
+    let items = hash.iter().map(|(_, b, &i)| (b, i));
+    let bvh = Bvh::from_items(items);
+
+    bench("bvh: pairs", 10, |_| {
+        let mut count = 0;
+        bvh.pairs(|_, _| count += 1);
+        black_box(count);
+    });
+    bench("bvh: first hit", QUERIES, |q| {
+        black_box(bvh.raycast_first(black_box(&rays[q]), 1024.0));
+    });
+    println!("bvh height: {}", bvh.height());
+}
@@ -0,0 +1,695 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::spatial::Distance;
+use super::spatial::Entry;
+use super::spatial::Items;
+use super::ItemId;
+use super::SpatialIndex;
+use crate::math::Aabb;
+use crate::math::Ray;
+use crate::math::RayHit;
+use crate::math::Shape;
+use crate::math::Vec2;
+
+const NONE: u32 = u32::MAX;
+// the bins tried by the surface area heuristic on each split
+const BINS: usize = 16;
+
+/// A bounding volume hierarchy: a binary tree of boxes, with one item per leaf
+///
+/// leaves are fattened by a margin, so that small moves don't touch the tree,
+/// and the tree is rebalanced with rotations as items come and go
+/// unlike the quadtree it has no region, and items of any size are found quickly
+///
+/// ex: level geometry that rarely moves, built once with `from_items`
+/// let walls = Bvh::from_items(level.walls.iter().map(|w| (w.aabb(), w.id)));
+/// let hit = walls.raycast_first(&ray, 500.0);
+pub struct Bvh<T> {
+    nodes: Vec<Node>,
+    free_nodes: Vec<u32>,
+    root: u32,
+    // located by their leaf
+    items: Items<T, u32>,
+    margin: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    // the fattened bounds of the item for leaves
+    bounds: Aabb,
+    parent: u32,
+    // 0 for leaves
+    height: u32,
+    content: Content,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Content {
+    Leaf(ItemId),
+    Branch([u32; 2]),
+}
+
+impl<T> Default for Bvh<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Bvh<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            root: NONE,
+            items: Items::new(),
+            margin: 4.0,
+        }
+    }
+
+    /// builds a tree for items that won't move, with the surface area heuristic
+    /// queries are faster than after inserting the items one by one
+    /// the ids are given in order, see `iter`
+    pub fn from_items(items: impl IntoIterator<Item = (Aabb, T)>) -> Self {
+        let mut ret = Self::new().margin(0.0);
+        for (bounds, value) in items {
+            ret.items.insert(Entry {
+                bounds,
+                value,
+                location: NONE,
+            });
+        }
+        ret.rebuild();
+        ret
+    }
+
+    /// how much the bounds of the items are grown in the tree, 4 by default
+    /// a larger margin makes updates cheaper but queries slower
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, Aabb, &T)> {
+        self.items.iter().map(|(id, e)| (id, e.bounds, &e.value))
+    }
+
+    /// the bounds of the item as stored in the tree, with the margin
+    pub fn fat_bounds(&self, id: ItemId) -> Option<Aabb> {
+        let entry = self.items.get(id)?;
+        Some(self.node(entry.location).bounds)
+    }
+
+    /// the length of the longest branch, 0 for a single item
+    pub fn height(&self) -> u32 {
+        if self.root == NONE {
+            0
+        } else {
+            self.node(self.root).height
+        }
+    }
+
+    /// the bounds of every node, ex: to draw the tree when debugging
+    pub fn nodes(&self) -> impl Iterator<Item = Aabb> + '_ {
+        let mut stack = vec![self.root];
+        std::iter::from_fn(move || {
+            let n = stack.pop().filter(|&n| n != NONE)?;
+            if let Content::Branch(c) = self.node(n).content {
+                stack.extend(c);
+            }
+            Some(self.node(n).bounds)
+        })
+    }
+
+    /// rebuilds the whole tree with the surface area heuristic, keeping the ids
+    /// ex: after loading a level, or after many items moved
+    pub fn rebuild(&mut self) {
+        let mut leaves: Vec<(ItemId, Aabb)> = self
+            .items
+            .iter()
+            .map(|(id, e)| match e.location {
+                NONE => (id, e.bounds.expand(self.margin)),
+                n => (id, self.node(n).bounds),
+            })
+            .collect();
+
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.root = NONE;
+        if !leaves.is_empty() {
+            self.root = self.build(&mut leaves, NONE);
+        }
+    }
+
+    /// the closest item hit by the ray, faster than `raycast` as farther nodes are skipped
+    pub fn raycast_first(&self, ray: &Ray, max_distance: f32) -> Option<(ItemId, RayHit)> {
+        let mut best: Option<(ItemId, RayHit)> = None;
+        let mut max_distance = max_distance;
+        let mut stack = vec![self.root];
+
+        while let Some(n) = stack.pop().filter(|&n| n != NONE) {
+            match self.node(n).content {
+                Content::Leaf(id) => {
+                    let hit = self.items.entry(id).bounds.raycast(ray, max_distance);
+                    if let Some(hit) = hit {
+                        max_distance = hit.distance;
+                        best = Some((id, hit));
+                    }
+                }
+                Content::Branch(c) => {
+                    let hits = c.map(|c| self.node(c).bounds.raycast(ray, max_distance));
+                    // the closest child is visited first
+                    let order = match hits {
+                        [Some(a), Some(b)] if b.distance < a.distance => [0, 1],
+                        _ => [1, 0],
+                    };
+                    for i in order {
+                        if hits[i].is_some() {
+                            stack.push(c[i]);
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// calls `f` once with every pair of items whose bounds overlap
+    /// ex: the broad phase of collision detection
+    pub fn pairs(&self, mut f: impl FnMut(ItemId, ItemId)) {
+        // the pairs within a subtree are the pairs within each child, and those between them
+        let mut stack = Vec::new();
+        let mut branches = vec![self.root];
+        while let Some(n) = branches.pop().filter(|&n| n != NONE) {
+            if let Content::Branch([a, b]) = self.node(n).content {
+                stack.push((a, b));
+                branches.extend([a, b]);
+            }
+        }
+        overlapping(self, self, stack, &mut f);
+    }
+
+    /// calls `f` with every item of this tree and item of `other` whose bounds overlap
+    /// ex: bullets against walls, each in their own tree
+    pub fn pairs_with<U>(&self, other: &Bvh<U>, mut f: impl FnMut(ItemId, ItemId)) {
+        if self.root != NONE && other.root != NONE {
+            overlapping(self, other, vec![(self.root, other.root)], &mut f);
+        }
+    }
+
+    // the top down build, splitting where the sum of the children's area times their items is lowest
+    fn build(&mut self, leaves: &mut [(ItemId, Aabb)], parent: u32) -> u32 {
+        if let [(id, bounds)] = *leaves {
+            let n = self.alloc(Node {
+                bounds,
+                parent,
+                height: 0,
+                content: Content::Leaf(id),
+            });
+            self.items.get_mut(id).unwrap().location = n;
+            return n;
+        }
+
+        let centers = Aabb::from_points(&leaves.iter().map(|l| l.1.center()).collect::<Vec<_>>());
+        let centers = centers.unwrap();
+        let size = centers.size();
+        let axis = if size.x >= size.y { 0 } else { 1 };
+        let bin_of = |bounds: &Aabb| {
+            let t = (bounds.center()[axis] - centers.min[axis]) / size[axis];
+            ((t * BINS as f32) as usize).min(BINS - 1)
+        };
+
+        let mut split = None;
+        if size[axis] > 0.0 {
+            let mut bins: [(usize, Option<Aabb>); BINS] = [(0, None); BINS];
+            for (_, bounds) in leaves.iter() {
+                let bin = &mut bins[bin_of(bounds)];
+                bin.0 += 1;
+                bin.1 = Some(bin.1.map_or(*bounds, |b| b.union(bounds)));
+            }
+            let cost = |bins: &[(usize, Option<Aabb>)]| {
+                let count: usize = bins.iter().map(|b| b.0).sum();
+                let bounds = bins.iter().filter_map(|b| b.1).reduce(|a, b| a.union(&b));
+                bounds.map_or(0.0, |b| b.perimeter() * count as f32)
+            };
+            split = (1..BINS)
+                .filter(|&k| bins[..k].iter().any(|b| b.0 > 0) && bins[k..].iter().any(|b| b.0 > 0))
+                .map(|k| (k, cost(&bins[..k]) + cost(&bins[k..])))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(k, _)| k);
+        }
+
+        // every center in the same spot, split in half
+        let mid = match split {
+            Some(k) => {
+                let mut mid = 0;
+                for i in 0..leaves.len() {
+                    if bin_of(&leaves[i].1) < k {
+                        leaves.swap(i, mid);
+                        mid += 1;
+                    }
+                }
+                mid
+            }
+            None => leaves.len() / 2,
+        };
+
+        let n = self.alloc(Node {
+            bounds: Aabb::default(),
+            parent,
+            height: 0,
+            content: Content::Branch([NONE, NONE]),
+        });
+        let (left, right) = leaves.split_at_mut(mid);
+        let children = [self.build(left, n), self.build(right, n)];
+        self.node_mut(n).content = Content::Branch(children);
+        self.refit(n);
+        n
+    }
+
+    fn insert_leaf(&mut self, leaf: u32) {
+        if self.root == NONE {
+            self.root = leaf;
+            self.node_mut(leaf).parent = NONE;
+            return;
+        }
+
+        // walks down to the sibling that makes the tree grow the least (Box2D)
+        let bounds = self.node(leaf).bounds;
+        let mut sibling = self.root;
+        while let Content::Branch(c) = self.node(sibling).content {
+            let area = self.node(sibling).bounds.perimeter();
+            let combined = self.node(sibling).bounds.union(&bounds).perimeter();
+            // a new parent here, or the cost pushed down on the children
+            let cost = 2.0 * combined;
+            let inherited = 2.0 * (combined - area);
+            let child_cost = |c: u32| {
+                let node = self.node(c);
+                let grown = node.bounds.union(&bounds).perimeter();
+                match node.content {
+                    Content::Leaf(_) => grown + inherited,
+                    Content::Branch(_) => grown - node.bounds.perimeter() + inherited,
+                }
+            };
+            let costs = c.map(child_cost);
+            if cost < costs[0] && cost < costs[1] {
+                break;
+            }
+            sibling = if costs[0] < costs[1] { c[0] } else { c[1] };
+        }
+
+        let old_parent = self.node(sibling).parent;
+        let parent = self.alloc(Node {
+            bounds: bounds.union(&self.node(sibling).bounds),
+            parent: old_parent,
+            height: self.node(sibling).height + 1,
+            content: Content::Branch([sibling, leaf]),
+        });
+        self.replace_child(old_parent, sibling, parent);
+        self.node_mut(sibling).parent = parent;
+        self.node_mut(leaf).parent = parent;
+        // from the new parent up, as in Box2D
+        self.fix_upwards(parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: u32) {
+        if leaf == self.root {
+            self.root = NONE;
+            return;
+        }
+
+        let parent = self.node(leaf).parent;
+        let Content::Branch(c) = self.node(parent).content else {
+            unreachable!()
+        };
+        let sibling = if c[0] == leaf { c[1] } else { c[0] };
+        let grandparent = self.node(parent).parent;
+        self.replace_child(grandparent, parent, sibling);
+        self.node_mut(sibling).parent = grandparent;
+        self.free_nodes.push(parent);
+        self.fix_upwards(grandparent);
+    }
+
+    // refits the bounds up to the root, rebalancing on the way
+    fn fix_upwards(&mut self, mut n: u32) {
+        while n != NONE {
+            n = self.balance(n);
+            self.refit(n);
+            n = self.node(n).parent;
+        }
+    }
+
+    // rotates the taller child up when the children's heights differ by more than 1
+    // returns the node now in the place of `a`
+    fn balance(&mut self, a: u32) -> u32 {
+        let Content::Branch(c) = self.node(a).content else {
+            return a;
+        };
+        let heights = c.map(|c| self.node(c).height);
+        let up_idx = if heights[1] > heights[0] + 1 {
+            1
+        } else if heights[0] > heights[1] + 1 {
+            0
+        } else {
+            return a;
+        };
+
+        let up = c[up_idx];
+        let Content::Branch([f, g]) = self.node(up).content else {
+            unreachable!()
+        };
+
+        // `up` takes the place of `a`, and keeps its taller child
+        let parent = self.node(a).parent;
+        self.replace_child(parent, a, up);
+        self.node_mut(up).parent = parent;
+        self.node_mut(a).parent = up;
+
+        let (keep, give) = if self.node(f).height > self.node(g).height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.node_mut(up).content = Content::Branch([a, keep]);
+        let mut children = c;
+        children[up_idx] = give;
+        self.node_mut(a).content = Content::Branch(children);
+        self.node_mut(give).parent = a;
+
+        self.refit(a);
+        self.refit(up);
+        up
+    }
+
+    fn refit(&mut self, n: u32) {
+        let Content::Branch([a, b]) = self.node(n).content else {
+            return;
+        };
+        let (a, b) = (self.node(a), self.node(b));
+        let bounds = a.bounds.union(&b.bounds);
+        let height = 1 + a.height.max(b.height);
+        let node = self.node_mut(n);
+        node.bounds = bounds;
+        node.height = height;
+    }
+
+    fn replace_child(&mut self, parent: u32, old: u32, new: u32) {
+        if parent == NONE {
+            self.root = new;
+            return;
+        }
+        if let Content::Branch(c) = &mut self.node_mut(parent).content {
+            for child in c.iter_mut().filter(|c| **c == old) {
+                *child = new;
+            }
+        }
+    }
+
+    fn alloc(&mut self, node: Node) -> u32 {
+        match self.free_nodes.pop() {
+            Some(n) => {
+                self.nodes[n as usize] = node;
+                n
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() as u32 - 1
+            }
+        }
+    }
+
+    fn node(&self, n: u32) -> &Node {
+        &self.nodes[n as usize]
+    }
+
+    fn node_mut(&mut self, n: u32) -> &mut Node {
+        &mut self.nodes[n as usize]
+    }
+}
+
+// the overlapping items under each pair of nodes on the stack, one from each tree
+fn overlapping<A, B>(
+    a: &Bvh<A>,
+    b: &Bvh<B>,
+    mut stack: Vec<(u32, u32)>,
+    f: &mut impl FnMut(ItemId, ItemId),
+) {
+    while let Some((na, nb)) = stack.pop() {
+        let (node_a, node_b) = (a.node(na), b.node(nb));
+        if !node_a.bounds.overlaps(&node_b.bounds) {
+            continue;
+        }
+
+        match (node_a.content, node_b.content) {
+            (Content::Leaf(ia), Content::Leaf(ib)) => {
+                if a.items.entry(ia).bounds.overlaps(&b.items.entry(ib).bounds) {
+                    f(ia, ib);
+                }
+            }
+            // descends into the larger node
+            (Content::Branch(c), Content::Leaf(_)) => stack.extend(c.map(|c| (c, nb))),
+            (Content::Leaf(_), Content::Branch(c)) => stack.extend(c.map(|c| (na, c))),
+            (Content::Branch(ca), Content::Branch(cb)) => {
+                if node_a.bounds.perimeter() >= node_b.bounds.perimeter() {
+                    stack.extend(ca.map(|c| (c, nb)));
+                } else {
+                    stack.extend(cb.map(|c| (na, c)));
+                }
+            }
+        }
+    }
+}
+
+impl<T> SpatialIndex<T> for Bvh<T> {
+    fn insert(&mut self, bounds: Aabb, value: T) -> ItemId {
+        let id = self.items.insert(Entry {
+            bounds,
+            value,
+            location: NONE,
+        });
+        let leaf = self.alloc(Node {
+            bounds: bounds.expand(self.margin),
+            parent: NONE,
+            height: 0,
+            content: Content::Leaf(id),
+        });
+        self.items.get_mut(id).unwrap().location = leaf;
+        self.insert_leaf(leaf);
+        id
+    }
+
+    fn remove(&mut self, id: ItemId) -> Option<T> {
+        let entry = self.items.remove(id)?;
+        self.remove_leaf(entry.location);
+        self.free_nodes.push(entry.location);
+        Some(entry.value)
+    }
+
+    fn update(&mut self, id: ItemId, bounds: Aabb) -> bool {
+        let Some(entry) = self.items.get_mut(id) else {
+            return false;
+        };
+        let old = std::mem::replace(&mut entry.bounds, bounds);
+        let leaf = entry.location;
+
+        // small moves stay within the margin
+        let old_fat = self.node(leaf).bounds;
+        if old_fat.contains(&bounds) {
+            return true;
+        }
+
+        // stretched along the move, expecting the next one to go the same way
+        // unless the item was teleported
+        let mut fat = bounds.expand(self.margin);
+        let displacement = 2.0 * (bounds.center() - old.center());
+        for axis in (0..2).filter(|_| old_fat.overlaps(&bounds)) {
+            if displacement[axis] < 0.0 {
+                fat.min[axis] += displacement[axis];
+            } else {
+                fat.max[axis] += displacement[axis];
+            }
+        }
+
+        self.remove_leaf(leaf);
+        self.node_mut(leaf).bounds = fat;
+        self.insert_leaf(leaf);
+        true
+    }
+
+    fn get(&self, id: ItemId) -> Option<&T> {
+        self.items.get(id).map(|e| &e.value)
+    }
+
+    fn get_mut(&mut self, id: ItemId) -> Option<&mut T> {
+        self.items.get_mut(id).map(|e| &mut e.value)
+    }
+
+    fn bounds(&self, id: ItemId) -> Option<Aabb> {
+        self.items.get(id).map(|e| e.bounds)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.root = NONE;
+        self.items.clear();
+    }
+
+    fn query_rect(&self, rect: &Aabb, mut f: impl FnMut(ItemId, &T)) {
+        let mut stack = vec![self.root];
+        while let Some(n) = stack.pop().filter(|&n| n != NONE) {
+            let node = self.node(n);
+            if !node.bounds.overlaps(rect) {
+                continue;
+            }
+            match node.content {
+                Content::Leaf(id) => {
+                    let entry = self.items.entry(id);
+                    if entry.bounds.overlaps(rect) {
+                        f(id, &entry.value);
+                    }
+                }
+                Content::Branch(c) => stack.extend(c),
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(ItemId, RayHit)> {
+        let mut ret = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(n) = stack.pop().filter(|&n| n != NONE) {
+            let node = self.node(n);
+            if node.bounds.raycast(ray, max_distance).is_none() {
+                continue;
+            }
+            match node.content {
+                Content::Leaf(id) => {
+                    if let Some(hit) = self.items.entry(id).bounds.raycast(ray, max_distance) {
+                        ret.push((id, hit));
+                    }
+                }
+                Content::Branch(c) => stack.extend(c),
+            }
+        }
+        ret.sort_by(|a, b| a.1.distance.total_cmp(&b.1.distance));
+        ret
+    }
+
+    fn nearest(&self, point: Vec2, k: usize) -> Vec<(ItemId, f32)> {
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        enum Candidate {
+            Item(ItemId),
+            Node(u32),
+        }
+
+        // best first: a node is never closer than its bounds
+        let mut ret = Vec::with_capacity(k);
+        let mut heap = BinaryHeap::new();
+        if self.root != NONE {
+            heap.push(Reverse((Distance(0.0), Candidate::Node(self.root))));
+        }
+
+        while let Some(Reverse((Distance(distance), candidate))) = heap.pop() {
+            if ret.len() == k {
+                break;
+            }
+            match candidate {
+                Candidate::Item(id) => ret.push((id, distance)),
+                Candidate::Node(n) => match self.node(n).content {
+                    Content::Leaf(id) => {
+                        let d = self.items.entry(id).bounds.distance_to_point(point);
+                        heap.push(Reverse((Distance(d), Candidate::Item(id))));
+                    }
+                    Content::Branch(c) => {
+                        for child in c {
+                            let d = self.node(child).bounds.distance_to_point(point);
+                            heap.push(Reverse((Distance(d), Candidate::Node(child))));
+                        }
+                    }
+                },
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    // the links, heights and bounds of every node below `n`, returns the number of leaves
+    fn check_node<T>(bvh: &Bvh<T>, n: u32, parent: u32) -> usize {
+        let node = bvh.node(n);
+        assert_eq!(node.parent, parent);
+        match node.content {
+            Content::Leaf(id) => {
+                assert_eq!(node.height, 0);
+                assert_eq!(bvh.items.entry(id).location, n);
+                1
+            }
+            Content::Branch(c) => {
+                let heights = c.map(|c| bvh.node(c).height);
+                assert_eq!(node.height, 1 + heights[0].max(heights[1]));
+                for c in c {
+                    assert!(node.bounds.contains(&bvh.node(c).bounds));
+                }
+                check_node(bvh, c[0], n) + check_node(bvh, c[1], n)
+            }
+        }
+    }
+
+    fn check_tree<T>(bvh: &Bvh<T>) {
+        if bvh.root != NONE {
+            assert_eq!(check_node(bvh, bvh.root, NONE), bvh.len());
+        }
+    }
+
+    #[test]
+    fn inserts_and_updates() {
+        // in a row, the worst order for a tree that isn't rebalanced
+        let mut bvh = Bvh::new().margin(0.0);
+        let mut ids = Vec::new();
+        for i in 0..1000 {
+            let min = Vec2::new(10.0 * i as f32, 0.0);
+            ids.push(bvh.insert(Aabb::new(min, min + Vec2::splat(5.0)), i));
+            check_tree(&bvh);
+        }
+        assert!(bvh.height() <= 15, "height {}", bvh.height());
+
+        let mut rng = Rng::new(46);
+        for (i, &id) in ids.iter().enumerate() {
+            if i % 3 == 0 {
+                assert_eq!(bvh.remove(id), Some(i));
+            } else {
+                let min = Vec2::new(rng.range(0.0..1000.0), rng.range(0.0..1000.0));
+                bvh.update(id, Aabb::new(min, min + Vec2::splat(5.0)));
+            }
+            check_tree(&bvh);
+        }
+    }
+
+    #[test]
+    fn insert_balances_the_new_parent() {
+        let mut bvh = Bvh::new().margin(0.0);
+        for i in 0..8 {
+            let min = Vec2::new(10.0 * i as f32, 0.0);
+            bvh.insert(Aabb::new(min, min + Vec2::splat(5.0)), i);
+        }
+        assert_eq!(bvh.height(), 3);
+
+        // far away, so paired with the root: a new root with children of heights 3 and 0
+        let min = Vec2::new(1e5, 0.0);
+        bvh.insert(Aabb::new(min, min + Vec2::splat(5.0)), 8);
+        check_tree(&bvh);
+        let Content::Branch(c) = bvh.node(bvh.root).content else {
+            unreachable!()
+        };
+        let heights = c.map(|c| bvh.node(c).height);
+        assert!(heights[0].abs_diff(heights[1]) <= 1, "{heights:?}");
+    }
+}
@@ -0,0 +1,491 @@
+use std::fmt;
+use std::fmt::Write;
+
+/// A JSON value, ex: to read and write files meant to be edited by hand
+///
+/// objects keep their keys in order, so that a file read and written back keeps its layout
+/// numbers are f64, which holds any f32 or i32 exactly
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Json {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// ex: Json::parse(r#"{"name": "player", "position": [10, 20]}"#)
+    pub fn parse(src: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            src,
+            pos: 0,
+            depth: 0,
+        };
+        let ret = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < src.len() {
+            return Err(parser.error("expected the end of the file"));
+        }
+        Ok(ret)
+    }
+
+    /// indented with 2 spaces, arrays of numbers and other plain values on a single line
+    pub fn to_pretty_string(&self) -> String {
+        let mut ret = String::new();
+        self.write_pretty(&mut ret, 0);
+        ret
+    }
+
+    /// the value of `key`, None if it isn't there or this isn't an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|f| f.0 == key).map(|f| &f.1),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        match self {
+            Json::Object(fields) => fields.iter_mut().find(|f| f.0 == key).map(|f| &mut f.1),
+            _ => None,
+        }
+    }
+
+    /// sets `key`, keeping its place if it was already there
+    /// panics if this isn't an object
+    pub fn insert(&mut self, key: &str, value: Json) {
+        let Json::Object(fields) = self else {
+            panic!("Bad json: not an object");
+        };
+        match fields.iter_mut().find(|f| f.0 == key) {
+            Some(field) => field.1 = value,
+            None => fields.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Json> {
+        let Json::Object(fields) = self else {
+            return None;
+        };
+        let idx = fields.iter().position(|f| f.0 == key)?;
+        Some(fields.remove(idx).1)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        !matches!(self, Json::Array(_) | Json::Object(_))
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let pad = |out: &mut String, indent: usize| out.extend((0..indent).map(|_| "  "));
+        match self {
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Object(fields) if fields.is_empty() => out.push_str("{}"),
+            Json::Array(items) if items.iter().all(Json::is_plain) => {
+                write!(out, "{self}").unwrap();
+            }
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    pad(out, indent + 1);
+                    item.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    pad(out, indent + 1);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push('}');
+            }
+            plain => write!(out, "{plain}").unwrap(),
+        }
+    }
+}
+
+/// on a single line, see `to_pretty_string` for files
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            // json has no infinities or NaN
+            Json::Number(n) if !n.is_finite() => write!(f, "null"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => {
+                let mut out = String::new();
+                write_string(&mut out, s);
+                f.write_str(&out)
+            }
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{sep}{item}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{sep}{}: {value}", Json::String(key.clone()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Number(n)
+    }
+}
+
+/// written with the fewest digits that read back as the same f32, ex: 0.1 and not 0.10000000149011612
+impl From<f32> for Json {
+    fn from(n: f32) -> Self {
+        Json::Number(n.to_string().parse().unwrap_or(n as f64))
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(items: Vec<Json>) -> Self {
+        Json::Array(items)
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// deeper arrays and objects are an error, rather than overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+    // how many arrays and objects the parser is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        // by bytes, `pos` may be inside a character
+        let newlines = self.src.as_bytes()[..self.pos]
+            .iter()
+            .filter(|&&c| c == b'\n');
+        let line = newlines.count() + 1;
+        format!("Bad json at line {line}: {msg}")
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected '{}'", c as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // whether the next thing is `c`, skipping it if so
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        let ret = self.peek() == Some(c);
+        self.pos += ret as usize;
+        ret
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for (word, value) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                ] {
+                    if self.src[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn nested(&mut self, f: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.depth += 1;
+        let ret = f(self);
+        self.depth -= 1;
+        ret
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields: Vec<(String, Json)> = Vec::new();
+        if self.eat(b'}') {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            if fields.iter().any(|f| f.0 == key) {
+                return Err(self.error(&format!("duplicate key \"{key}\"")));
+            }
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            if self.eat(b'}') {
+                return Ok(Json::Object(fields));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.eat(b']') {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.eat(b']') {
+                return Ok(Json::Array(items));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+
+        let mut ret = String::new();
+        loop {
+            let rest = &self.src[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(ret),
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    ret.push(match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("bad escape")),
+                    });
+                }
+                c => ret.push(c),
+            }
+        }
+    }
+
+    // the 4 hex digits after \u, and the second half of a surrogate pair
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let mut code = self.hex4()?;
+        if (0xd800..0xdc00).contains(&code) && self.src[self.pos..].starts_with("\\u") {
+            self.pos += 2;
+            let low = self.hex4()?;
+            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+        }
+        char::from_u32(code).ok_or_else(|| self.error("bad unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        // `from_str_radix` would take a sign
+        let digits = self.src.as_bytes().get(self.pos..self.pos + 4);
+        let digits = digits.filter(|d| d.iter().all(u8::is_ascii_hexdigit));
+        let digits = digits.ok_or_else(|| self.error("bad unicode escape"))?;
+        let code = digits
+            .iter()
+            .fold(0, |code, &d| code * 16 + (d as char).to_digit(16).unwrap());
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        self.src[start..self.pos]
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("bad number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let src = r#"{"name": "player", "position": [10, -20.5], "tags": [], "sprite": {"name": "h\u00e9ro\n", "visible": true, "z": null}}"#;
+        let json = Json::parse(src).unwrap();
+        assert_eq!(json.get("name").and_then(Json::as_str), Some("player"));
+        let sprite = json.get("sprite").unwrap();
+        assert_eq!(sprite.get("name").and_then(Json::as_str), Some("héro\n"));
+        assert_eq!(sprite.get("z"), Some(&Json::Null));
+
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+        assert_eq!(Json::parse(&json.to_pretty_string()).unwrap(), json);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |src: &str| Json::parse(src).unwrap_err();
+        assert_eq!(
+            error("{\n\"a\": 1,\n}"),
+            "Bad json at line 3: expected a string"
+        );
+        assert_eq!(error("[1, 2"), "Bad json at line 1: expected ','");
+        assert!(error(r#"{"a": 1, "a": 2}"#).contains("duplicate key"));
+        assert!(error("[1] 2").contains("expected the end of the file"));
+        assert!(error("").contains("unexpected end of file"));
+        assert!(error(r#""\ud800""#).starts_with("Bad json"));
+        assert_eq!(error("\"\\é\""), "Bad json at line 1: bad escape");
+        assert_eq!(
+            error(r#""\u+041""#),
+            "Bad json at line 1: bad unicode escape"
+        );
+        assert_eq!(
+            error(r#""\u-041""#),
+            "Bad json at line 1: bad unicode escape"
+        );
+        assert_eq!(Json::parse(r#""\u0041\u00e9""#).unwrap(), Json::from("Aé"));
+    }
+
+    #[test]
+    fn nested_too_deep() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            Json::parse(&nested(MAX_DEPTH + 1)).unwrap_err(),
+            "Bad json at line 1: nested too deep"
+        );
+
+        // fails early instead of overflowing the stack
+        let src = "{\"a\": ".repeat(100_000);
+        assert!(Json::parse(&src).unwrap_err().ends_with("nested too deep"));
+    }
+}
@@ -0,0 +1,20 @@
+mod bvh;
+pub use bvh::*;
+
+mod json;
+pub use json::*;
+
+mod quadtree;
+pub use quadtree::*;
+
+mod region_quadtree;
+pub use region_quadtree::*;
+
+mod slot_map;
+pub use slot_map::*;
+
+mod spatial;
+pub use spatial::*;
+
+mod spatial_hash;
+pub use spatial_hash::*;
@@ -0,0 +1,376 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::spatial::Distance;
+use super::spatial::Entry;
+use super::spatial::Items;
+use super::ItemId;
+use super::SpatialIndex;
+use crate::math::Aabb;
+use crate::math::Ray;
+use crate::math::RayHit;
+use crate::math::Shape;
+use crate::math::Vec2;
+
+const ROOT: u32 = 0;
+
+/// A spatial index splitting a fixed region into quarters, where they hold many items
+///
+/// items are kept in the smallest quarter that fully contains their bounds,
+/// so items straddling a split stay in the larger region above it
+/// items outside of the region are kept at the root, they work but make every query slower
+///
+/// ex: QuadTree::new(Aabb::new(Vec2::zero(), Vec2::new(4096.0, 4096.0))).max_depth(6)
+pub struct QuadTree<T> {
+    nodes: Vec<Node>,
+    // the first of each free block of 4 nodes
+    free_nodes: Vec<u32>,
+    // located by the node holding them
+    items: Items<T, u32>,
+    max_depth: u32,
+    bucket_size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Aabb,
+    depth: u32,
+    parent: u32,
+    // the first of 4 consecutive nodes: bottom left, bottom right, top left, top right
+    children: Option<u32>,
+    items: Vec<ItemId>,
+}
+
+impl<T> QuadTree<T> {
+    pub fn new(region: Aabb) -> Self {
+        Self {
+            nodes: vec![Node {
+                bounds: region,
+                depth: 0,
+                parent: ROOT,
+                children: None,
+                items: Vec::new(),
+            }],
+            free_nodes: Vec::new(),
+            items: Items::new(),
+            max_depth: 8,
+            bucket_size: 8,
+        }
+    }
+
+    /// how many times the region can be split, 8 by default
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// how many items a region holds before being split, 8 by default
+    pub fn bucket_size(mut self, bucket_size: usize) -> Self {
+        self.bucket_size = bucket_size.max(1);
+        self
+    }
+
+    pub fn region(&self) -> Aabb {
+        self.nodes[ROOT as usize].bounds
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, Aabb, &T)> {
+        self.items.iter().map(|(id, e)| (id, e.bounds, &e.value))
+    }
+
+    /// the bounds of every region that isn't split, ex: to draw the tree when debugging
+    pub fn leaves(&self) -> impl Iterator<Item = Aabb> + '_ {
+        let free: Vec<u32> = self.free_nodes.iter().flat_map(|&c| c..c + 4).collect();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(i, n)| n.children.is_none() && !free.contains(&(*i as u32)))
+            .map(|(_, n)| n.bounds)
+    }
+
+    // walks down from `start` to the smallest node containing the bounds
+    fn insert_at(&mut self, start: u32, id: ItemId, bounds: &Aabb) {
+        let mut n = start;
+        while let Some(child) = self.child_containing(n, bounds) {
+            n = child;
+        }
+
+        self.node_mut(n).items.push(id);
+        self.items.get_mut(id).unwrap().location = n;
+
+        let node = self.node(n);
+        if node.children.is_none()
+            && node.items.len() > self.bucket_size
+            && node.depth < self.max_depth
+        {
+            self.split(n);
+        }
+    }
+
+    fn child_containing(&self, n: u32, bounds: &Aabb) -> Option<u32> {
+        let c = self.node(n).children?;
+        (c..c + 4).find(|&i| self.node(i).bounds.contains(bounds))
+    }
+
+    fn split(&mut self, n: u32) {
+        let Node { bounds, depth, .. } = *self.node(n);
+        let center = bounds.center();
+        let quarters = [
+            Aabb::new(bounds.min, center),
+            Aabb::new(
+                Vec2::new(center.x, bounds.min.y),
+                Vec2::new(bounds.max.x, center.y),
+            ),
+            Aabb::new(
+                Vec2::new(bounds.min.x, center.y),
+                Vec2::new(center.x, bounds.max.y),
+            ),
+            Aabb::new(center, bounds.max),
+        ];
+
+        let children = quarters.map(|bounds| Node {
+            bounds,
+            depth: depth + 1,
+            parent: n,
+            children: None,
+            items: Vec::new(),
+        });
+        let c = match self.free_nodes.pop() {
+            Some(c) => {
+                for (i, child) in children.into_iter().enumerate() {
+                    self.nodes[c as usize + i] = child;
+                }
+                c
+            }
+            None => {
+                self.nodes.extend(children);
+                self.nodes.len() as u32 - 4
+            }
+        };
+        self.node_mut(n).children = Some(c);
+
+        // move down what fits in a quarter
+        let ids = std::mem::take(&mut self.node_mut(n).items);
+        for id in ids {
+            let bounds = self.items.entry(id).bounds;
+            match self.child_containing(n, &bounds) {
+                Some(child) => {
+                    self.node_mut(child).items.push(id);
+                    self.items.get_mut(id).unwrap().location = child;
+                }
+                None => self.node_mut(n).items.push(id),
+            }
+        }
+
+        for child in c..c + 4 {
+            if self.node(child).items.len() > self.bucket_size && depth + 1 < self.max_depth {
+                self.split(child);
+            }
+        }
+    }
+
+    // merges the quarters around `n` back up while they hold few enough items
+    fn collapse(&mut self, n: u32) {
+        let mut p = if self.node(n).children.is_some() {
+            n
+        } else {
+            self.node(n).parent
+        };
+
+        loop {
+            let Some(c) = self.node(p).children else {
+                return;
+            };
+            let leaves = (c..c + 4).all(|i| self.node(i).children.is_none());
+            let count: usize = self.node(p).items.len()
+                + (c..c + 4).map(|i| self.node(i).items.len()).sum::<usize>();
+            if !leaves || count > self.bucket_size {
+                return;
+            }
+
+            for i in c..c + 4 {
+                let ids = std::mem::take(&mut self.node_mut(i).items);
+                for &id in &ids {
+                    self.items.get_mut(id).unwrap().location = p;
+                }
+                self.node_mut(p).items.extend(ids);
+            }
+            self.node_mut(p).children = None;
+            self.free_nodes.push(c);
+
+            if p == ROOT {
+                return;
+            }
+            p = self.node(p).parent;
+        }
+    }
+
+    fn detach(&mut self, id: ItemId, n: u32) {
+        let items = &mut self.node_mut(n).items;
+        let idx = items.iter().position(|&i| i == id).unwrap();
+        items.swap_remove(idx);
+    }
+
+    fn visit(&self, n: u32, rect: &Aabb, f: &mut impl FnMut(ItemId, &T)) {
+        let node = self.node(n);
+        for &id in &node.items {
+            let entry = self.items.entry(id);
+            if entry.bounds.overlaps(rect) {
+                f(id, &entry.value);
+            }
+        }
+
+        if let Some(c) = node.children {
+            for child in c..c + 4 {
+                if self.node(child).bounds.overlaps(rect) {
+                    self.visit(child, rect, f);
+                }
+            }
+        }
+    }
+
+    fn visit_ray(&self, n: u32, ray: &Ray, max_distance: f32, out: &mut Vec<(ItemId, RayHit)>) {
+        let node = self.node(n);
+        for &id in &node.items {
+            if let Some(hit) = self.items.entry(id).bounds.raycast(ray, max_distance) {
+                out.push((id, hit));
+            }
+        }
+
+        if let Some(c) = node.children {
+            for child in c..c + 4 {
+                if self.node(child).bounds.raycast(ray, max_distance).is_some() {
+                    self.visit_ray(child, ray, max_distance, out);
+                }
+            }
+        }
+    }
+
+    fn node(&self, n: u32) -> &Node {
+        &self.nodes[n as usize]
+    }
+
+    fn node_mut(&mut self, n: u32) -> &mut Node {
+        &mut self.nodes[n as usize]
+    }
+}
+
+impl<T> SpatialIndex<T> for QuadTree<T> {
+    fn insert(&mut self, bounds: Aabb, value: T) -> ItemId {
+        let id = self.items.insert(Entry {
+            bounds,
+            value,
+            location: ROOT,
+        });
+        self.insert_at(ROOT, id, &bounds);
+        id
+    }
+
+    fn remove(&mut self, id: ItemId) -> Option<T> {
+        let entry = self.items.remove(id)?;
+        self.detach(id, entry.location);
+        self.collapse(entry.location);
+        Some(entry.value)
+    }
+
+    fn update(&mut self, id: ItemId, bounds: Aabb) -> bool {
+        let Some(entry) = self.items.get_mut(id) else {
+            return false;
+        };
+        entry.bounds = bounds;
+        let n = entry.location;
+
+        // small moves usually stay in the same region
+        let fits = n == ROOT || self.node(n).bounds.contains(&bounds);
+        if fits && self.child_containing(n, &bounds).is_none() {
+            return true;
+        }
+
+        self.detach(id, n);
+        let mut start = n;
+        while start != ROOT && !self.node(start).bounds.contains(&bounds) {
+            start = self.node(start).parent;
+        }
+        self.insert_at(start, id, &bounds);
+        self.collapse(n);
+        true
+    }
+
+    fn get(&self, id: ItemId) -> Option<&T> {
+        self.items.get(id).map(|e| &e.value)
+    }
+
+    fn get_mut(&mut self, id: ItemId) -> Option<&mut T> {
+        self.items.get_mut(id).map(|e| &mut e.value)
+    }
+
+    fn bounds(&self, id: ItemId) -> Option<Aabb> {
+        self.items.get(id).map(|e| e.bounds)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn clear(&mut self) {
+        let region = self.region();
+        self.nodes.truncate(1);
+        self.nodes[0] = Node {
+            bounds: region,
+            depth: 0,
+            parent: ROOT,
+            children: None,
+            items: Vec::new(),
+        };
+        self.free_nodes.clear();
+        self.items.clear();
+    }
+
+    fn query_rect(&self, rect: &Aabb, mut f: impl FnMut(ItemId, &T)) {
+        // the root is always visited, for the items outside of the region
+        self.visit(ROOT, rect, &mut f);
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(ItemId, RayHit)> {
+        let mut ret = Vec::new();
+        self.visit_ray(ROOT, ray, max_distance, &mut ret);
+        ret.sort_by(|a, b| a.1.distance.total_cmp(&b.1.distance));
+        ret
+    }
+
+    fn nearest(&self, point: Vec2, k: usize) -> Vec<(ItemId, f32)> {
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        enum Candidate {
+            Item(ItemId),
+            Node(u32),
+        }
+
+        // best first: a node is never closer than its bounds, apart from the root
+        let mut ret = Vec::with_capacity(k);
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((Distance(0.0), Candidate::Node(ROOT))));
+
+        while let Some(Reverse((Distance(distance), candidate))) = heap.pop() {
+            if ret.len() == k {
+                break;
+            }
+            match candidate {
+                Candidate::Item(id) => ret.push((id, distance)),
+                Candidate::Node(n) => {
+                    let node = self.node(n);
+                    for &id in &node.items {
+                        let d = self.items.entry(id).bounds.distance_to_point(point);
+                        heap.push(Reverse((Distance(d), Candidate::Item(id))));
+                    }
+                    if let Some(c) = node.children {
+                        for child in c..c + 4 {
+                            let d = self.node(child).bounds.distance_to_point(point);
+                            heap.push(Reverse((Distance(d), Candidate::Node(child))));
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+}
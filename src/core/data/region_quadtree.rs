@@ -0,0 +1,461 @@
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::math::IVec2;
+use crate::math::Vec2;
+
+/// the largest side of a `RegionQuadTree`, so that every cell fits in an `IVec2`
+pub const MAX_REGION_SIZE: u32 = 1 << 30;
+
+/// A square grid of cells stored as a tree of quarters, where quarters with the same value are merged
+///
+/// a map of mostly uniform areas takes a fraction of the memory of the full grid,
+/// and is kept merged as it is painted
+/// the side is a power of two, cell (0, 0) is the bottom left one
+///
+/// ex: fog of war, only the explored areas split the tree
+/// let mut fog = RegionQuadTree::new(4096, false);
+/// fog.fill_circle(player_cell, 12.0, true);
+/// if fog.get(enemy_cell) == Some(&true) { draw(enemy) }
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionQuadTree<T> {
+    size: u32,
+    root: QuadTreeNode<T>,
+}
+
+/// A quarter of a region quadtree, either a single value or split in 4
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuadTreeNode<T> {
+    Leaf(T),
+    /// bottom left, bottom right, top left, top right
+    Branch(Box<[QuadTreeNode<T>; 4]>),
+}
+
+// how much of a quarter a painted area covers
+enum Coverage {
+    None,
+    Partial,
+    Full,
+}
+
+impl<T: Clone + PartialEq> RegionQuadTree<T> {
+    /// every cell set to `value`, the size is rounded up to a power of two
+    /// panics if the size is above `MAX_REGION_SIZE`
+    pub fn new(size: u32, value: T) -> Self {
+        Self {
+            size: round_size(size),
+            root: QuadTreeNode::Leaf(value),
+        }
+    }
+
+    /// the value of every cell given by `f`, the size is rounded up to a power of two
+    /// panics if the size is above `MAX_REGION_SIZE`
+    pub fn from_fn(size: u32, f: impl Fn(IVec2) -> T) -> Self {
+        let size = round_size(size);
+        Self {
+            size,
+            root: build(IVec2::zero(), size, &f),
+        }
+    }
+
+    /// a cell per pixel, with the bottom row of the image as row 0
+    /// the cells past the image, when it isn't a square power of two, are set to `outside`
+    /// ex: RegionQuadTree::from_image(&mask, false, |p| p[3] > 127) for a terrain mask
+    pub fn from_image(image: &RgbaImage, outside: T, f: impl Fn(&Rgba<u8>) -> T) -> Self {
+        let (width, height) = image.dimensions();
+        Self::from_fn(width.max(height), |cell| {
+            let (x, y) = (cell.x as u32, cell.y as u32);
+            if x < width && y < height {
+                f(image.get_pixel(x, height - 1 - y))
+            } else {
+                outside.clone()
+            }
+        })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn root(&self) -> &QuadTreeNode<T> {
+        &self.root
+    }
+
+    /// the value of the cell, None outside of the tree
+    pub fn get(&self, cell: IVec2) -> Option<&T> {
+        if !self.in_bounds(cell) {
+            return None;
+        }
+
+        let (mut node, mut min, mut size) = (&self.root, IVec2::zero(), self.size as i32);
+        loop {
+            match node {
+                QuadTreeNode::Leaf(value) => return Some(value),
+                QuadTreeNode::Branch(children) => {
+                    size /= 2;
+                    let right = cell.x >= min.x + size;
+                    let top = cell.y >= min.y + size;
+                    node = &children[right as usize + 2 * top as usize];
+                    min += IVec2::new(right as i32, top as i32) * size;
+                }
+            }
+        }
+    }
+
+    pub fn set(&mut self, cell: IVec2, value: T) {
+        self.fill_rect(cell, cell + IVec2::one(), value);
+    }
+
+    /// sets the cells from `min` up to, but not including, `max`
+    pub fn fill_rect(&mut self, min: IVec2, max: IVec2, value: T) {
+        let coverage = |node_min: IVec2, size: i32| {
+            let node_max = node_min + IVec2::splat(size);
+            if node_min.x >= max.x
+                || node_min.y >= max.y
+                || node_max.x <= min.x
+                || node_max.y <= min.y
+            {
+                Coverage::None
+            } else if min.x <= node_min.x
+                && min.y <= node_min.y
+                && node_max.x <= max.x
+                && node_max.y <= max.y
+            {
+                Coverage::Full
+            } else {
+                Coverage::Partial
+            }
+        };
+        paint(
+            &mut self.root,
+            IVec2::zero(),
+            self.size as i32,
+            &coverage,
+            &value,
+        );
+    }
+
+    /// sets the cells whose center is within `radius` of `center`, in cells
+    /// ex: the crater of an explosion in a terrain mask
+    pub fn fill_circle(&mut self, center: Vec2, radius: f32, value: T) {
+        let coverage = |node_min: IVec2, size: i32| {
+            // the centers of the cells of the quarter
+            let lo = node_min.cast::<f32>() + Vec2::splat(0.5);
+            let hi = lo + Vec2::splat(size as f32 - 1.0);
+            let closest = center.max(lo).min(hi);
+            let farthest = (center - lo).abs().max((hi - center).abs());
+            if (closest - center).len() > radius {
+                Coverage::None
+            } else if farthest.len() <= radius {
+                Coverage::Full
+            } else {
+                Coverage::Partial
+            }
+        };
+        paint(
+            &mut self.root,
+            IVec2::zero(),
+            self.size as i32,
+            &coverage,
+            &value,
+        );
+    }
+
+    /// every quarter that isn't split, as (bottom left cell, side in cells, value)
+    pub fn leaves(&self) -> impl Iterator<Item = (IVec2, u32, &T)> {
+        let mut stack = vec![(&self.root, IVec2::zero(), self.size)];
+        std::iter::from_fn(move || loop {
+            let (node, min, size) = stack.pop()?;
+            match node {
+                QuadTreeNode::Leaf(value) => return Some((min, size, value)),
+                QuadTreeNode::Branch(children) => {
+                    let half = size / 2;
+                    for (i, child) in children.iter().enumerate() {
+                        let offset = IVec2::new(i as i32 % 2, i as i32 / 2) * half as i32;
+                        stack.push((child, min + offset, half));
+                    }
+                }
+            }
+        })
+    }
+
+    /// the tree as bytes, each value written by `f`
+    /// ex: tree.to_bytes(|&explored, out| out.push(explored as u8))
+    pub fn to_bytes(&self, mut f: impl FnMut(&T, &mut Vec<u8>)) -> Vec<u8> {
+        // the size, then the nodes depth first: 0 and the value for leaves, 1 for branches
+        let mut ret = self.size.to_le_bytes().to_vec();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                QuadTreeNode::Leaf(value) => {
+                    ret.push(0);
+                    f(value, &mut ret);
+                }
+                QuadTreeNode::Branch(children) => {
+                    ret.push(1);
+                    stack.extend(children.iter().rev());
+                }
+            }
+        }
+        ret
+    }
+
+    /// reads back `to_bytes`, each value read by `f` which advances the slice past it
+    /// ex: RegionQuadTree::from_bytes(&bytes, |b| { let v = b[0] != 0; *b = &b[1..]; Ok(v) })
+    pub fn from_bytes(
+        bytes: &[u8],
+        mut f: impl FnMut(&mut &[u8]) -> Result<T, String>,
+    ) -> Result<Self, String> {
+        let (size, mut rest) = bytes
+            .split_first_chunk::<4>()
+            .ok_or("Bad region quadtree: too short")?;
+        let size = u32::from_le_bytes(*size);
+        if !size.is_power_of_two() || size > MAX_REGION_SIZE {
+            return Err(format!("Bad region quadtree: size {size}"));
+        }
+
+        let root = read(&mut rest, size, &mut f)?;
+        if !rest.is_empty() {
+            return Err("Bad region quadtree: trailing bytes".to_string());
+        }
+        Ok(Self { size, root })
+    }
+
+    fn in_bounds(&self, cell: IVec2) -> bool {
+        let size = self.size as i32;
+        0 <= cell.x && cell.x < size && 0 <= cell.y && cell.y < size
+    }
+}
+
+fn round_size(size: u32) -> u32 {
+    assert!(size <= MAX_REGION_SIZE, "Bad size");
+    size.max(1).next_power_of_two()
+}
+
+fn build<T: PartialEq>(min: IVec2, size: u32, f: &impl Fn(IVec2) -> T) -> QuadTreeNode<T> {
+    if size == 1 {
+        return QuadTreeNode::Leaf(f(min));
+    }
+    let half = size / 2;
+    let children = [0, 1, 2, 3].map(|i| {
+        let offset = IVec2::new(i % 2, i / 2) * half as i32;
+        build(min + offset, half, f)
+    });
+    merged(children)
+}
+
+// a single leaf if the quarters all hold the same value
+fn merged<T: PartialEq>(children: [QuadTreeNode<T>; 4]) -> QuadTreeNode<T> {
+    let same = match &children {
+        [QuadTreeNode::Leaf(a), QuadTreeNode::Leaf(b), QuadTreeNode::Leaf(c), QuadTreeNode::Leaf(d)] => {
+            a == b && a == c && a == d
+        }
+        _ => false,
+    };
+    if same {
+        let [first, ..] = children;
+        first
+    } else {
+        QuadTreeNode::Branch(Box::new(children))
+    }
+}
+
+fn paint<T: Clone + PartialEq>(
+    node: &mut QuadTreeNode<T>,
+    min: IVec2,
+    size: i32,
+    coverage: &impl Fn(IVec2, i32) -> Coverage,
+    value: &T,
+) {
+    match coverage(min, size) {
+        Coverage::None => {}
+        Coverage::Full => *node = QuadTreeNode::Leaf(value.clone()),
+        Coverage::Partial => {
+            if matches!(node, QuadTreeNode::Leaf(v) if v == value) {
+                return;
+            }
+            let mut children = match std::mem::replace(node, QuadTreeNode::Leaf(value.clone())) {
+                // split, to paint part of it
+                QuadTreeNode::Leaf(v) => [(); 4].map(|_| QuadTreeNode::Leaf(v.clone())),
+                QuadTreeNode::Branch(children) => *children,
+            };
+
+            let half = size / 2;
+            for (i, child) in children.iter_mut().enumerate() {
+                let offset = IVec2::new(i as i32 % 2, i as i32 / 2) * half;
+                paint(child, min + offset, half, coverage, value);
+            }
+            *node = merged(children);
+        }
+    }
+}
+
+// quarters holding the same value are merged, as if they had been painted
+fn read<T: PartialEq>(
+    bytes: &mut &[u8],
+    size: u32,
+    f: &mut impl FnMut(&mut &[u8]) -> Result<T, String>,
+) -> Result<QuadTreeNode<T>, String> {
+    let (&tag, rest) = bytes
+        .split_first()
+        .ok_or("Bad region quadtree: too short")?;
+    *bytes = rest;
+    match tag {
+        0 => Ok(QuadTreeNode::Leaf(f(bytes)?)),
+        1 if size > 1 => {
+            let a = read(bytes, size / 2, f)?;
+            let b = read(bytes, size / 2, f)?;
+            let c = read(bytes, size / 2, f)?;
+            let d = read(bytes, size / 2, f)?;
+            Ok(merged([a, b, c, d]))
+        }
+        _ => Err(format!("Bad region quadtree: node {tag} of size {size}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    fn read_bool(bytes: &mut &[u8]) -> Result<bool, String> {
+        let (&b, rest) = bytes.split_first().ok_or("Bad bool")?;
+        *bytes = rest;
+        Ok(b != 0)
+    }
+
+    #[test]
+    fn merge_and_split() {
+        let mut tree = RegionQuadTree::new(5, false);
+        assert_eq!(tree.size(), 8);
+
+        tree.set(IVec2::new(3, 3), true);
+        assert!(matches!(tree.root(), QuadTreeNode::Branch(_)));
+        // 4 quarters, the bottom left split twice more
+        assert_eq!(tree.leaves().count(), 10);
+        tree.set(IVec2::new(3, 3), false);
+        assert_eq!(tree, RegionQuadTree::new(8, false));
+
+        tree.fill_rect(IVec2::zero(), IVec2::splat(4), true);
+        assert_eq!(tree.leaves().count(), 4);
+        assert_eq!(tree.leaves().filter(|l| *l.2).count(), 1);
+        tree.fill_rect(IVec2::new(4, 0), IVec2::new(8, 8), true);
+        tree.fill_rect(IVec2::new(0, 4), IVec2::new(4, 8), true);
+        assert_eq!(tree, RegionQuadTree::new(8, true));
+
+        // the bounds are clipped to the tree
+        tree.fill_rect(IVec2::splat(-100), IVec2::splat(100), false);
+        assert_eq!(tree, RegionQuadTree::new(8, false));
+        tree.fill_circle(Vec2::splat(4.0), 100.0, true);
+        assert_eq!(tree, RegionQuadTree::new(8, true));
+    }
+
+    // paints random areas, then checks every cell against a grid painted the same way
+    #[test]
+    fn painting() {
+        const SIZE: i32 = 32;
+        let mut rng = Rng::new(47);
+        let mut tree = RegionQuadTree::new(SIZE as u32, 0u8);
+        let mut grid = vec![0u8; (SIZE * SIZE) as usize];
+        let index = |cell: IVec2| (cell.y * SIZE + cell.x) as usize;
+
+        for i in 0..200 {
+            let value = rng.range(0..3u32) as u8;
+            if i % 2 == 0 {
+                let a = IVec2::new(rng.range(-4..SIZE + 4), rng.range(-4..SIZE + 4));
+                let b = a + IVec2::new(rng.range(0..20), rng.range(0..20));
+                tree.fill_rect(a, b, value);
+                for y in a.y.max(0)..b.y.min(SIZE) {
+                    for x in a.x.max(0)..b.x.min(SIZE) {
+                        grid[index(IVec2::new(x, y))] = value;
+                    }
+                }
+            } else {
+                let center = Vec2::new(rng.range(-4.0..36.0), rng.range(-4.0..36.0));
+                let radius = rng.range(0.0..12.0);
+                tree.fill_circle(center, radius, value);
+                for y in 0..SIZE {
+                    for x in 0..SIZE {
+                        let cell = IVec2::new(x, y);
+                        if (cell.cast::<f32>() + Vec2::splat(0.5) - center).len() <= radius {
+                            grid[index(cell)] = value;
+                        }
+                    }
+                }
+            }
+
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let cell = IVec2::new(x, y);
+                    assert_eq!(tree.get(cell), Some(&grid[index(cell)]), "{cell:?}");
+                }
+            }
+            // painting keeps the tree as merged as building it from the grid
+            assert_eq!(
+                tree,
+                RegionQuadTree::from_fn(SIZE as u32, |c| grid[index(c)])
+            );
+        }
+
+        assert_eq!(tree.get(IVec2::new(-1, 0)), None);
+        assert_eq!(tree.get(IVec2::new(0, SIZE)), None);
+    }
+
+    #[test]
+    fn bytes() {
+        let mut tree = RegionQuadTree::new(64, false);
+        tree.fill_circle(Vec2::new(20.0, 30.0), 9.0, true);
+        tree.fill_rect(IVec2::new(40, 2), IVec2::new(63, 11), true);
+        let bytes = tree.to_bytes(|&v, out| out.push(v as u8));
+        assert_eq!(RegionQuadTree::from_bytes(&bytes, read_bool), Ok(tree));
+
+        for len in 0..bytes.len() {
+            assert!(RegionQuadTree::from_bytes(&bytes[..len], read_bool).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            RegionQuadTree::from_bytes(&trailing, read_bool),
+            Err("Bad region quadtree: trailing bytes".to_string())
+        );
+
+        let bad_sizes = [0, 3, 2 * MAX_REGION_SIZE];
+        for size in bad_sizes {
+            let mut bytes = size.to_le_bytes().to_vec();
+            bytes.extend([0, 1]);
+            assert_eq!(
+                RegionQuadTree::from_bytes(&bytes, read_bool),
+                Err(format!("Bad region quadtree: size {size}"))
+            );
+        }
+        // a branch can't split a single cell
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.extend([1, 0, 1, 0, 1, 0, 1, 0, 1]);
+        assert!(RegionQuadTree::from_bytes(&bytes, read_bool).is_err());
+    }
+
+    #[test]
+    fn bytes_merge_uniform_branches() {
+        // a branch of 4 equal leaves, one level down
+        let mut bytes = 4u32.to_le_bytes().to_vec();
+        bytes.extend([1, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0, 0, 1]);
+        let tree = RegionQuadTree::from_bytes(&bytes, read_bool).unwrap();
+        let mut expected = RegionQuadTree::new(4, false);
+        expected.fill_rect(IVec2::zero(), IVec2::splat(2), true);
+        expected.fill_rect(IVec2::splat(2), IVec2::splat(4), true);
+        assert_eq!(tree, expected);
+        assert_eq!(tree.leaves().count(), 4);
+
+        // merging all the way up
+        let mut bytes = 2u32.to_le_bytes().to_vec();
+        bytes.extend([1, 0, 1, 0, 1, 0, 1, 0, 1]);
+        let tree = RegionQuadTree::from_bytes(&bytes, read_bool).unwrap();
+        assert_eq!(tree, RegionQuadTree::new(2, true));
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad size")]
+    fn too_large() {
+        RegionQuadTree::new(MAX_REGION_SIZE + 1, false);
+    }
+}
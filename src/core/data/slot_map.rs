@@ -0,0 +1,593 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::ops::IndexMut;
+
+/// Identifies a value in a `SlotMap<T>`, it can be copied and kept around freely
+/// once the value is removed it matches nothing, even if its slot is reused
+///
+/// handles can be saved as a u64 with `to_bits`, see `SlotMap::from_entries` to load them back
+pub struct Handle<T> {
+    index: u32,
+    // odd while the slot is in use
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: u32, generation: u32) -> Self {
+        Self {
+            index,
+            generation,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+
+    /// the generation in the high bits and the index in the low bits
+    pub fn to_bits(self) -> u64 {
+        ((self.generation as u64) << 32) | self.index as u64
+    }
+
+    /// None for values that can't come from `to_bits`
+    pub fn from_bits(bits: u64) -> Option<Self> {
+        let generation = (bits >> 32) as u32;
+        (generation % 2 == 1).then(|| Self::new(bits as u32, generation))
+    }
+}
+
+// implemented by hand, the derives would require T to implement them
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bits() == other.to_bits()
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> PartialOrd for Handle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Handle<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.index, self.generation).cmp(&(other.index, other.generation))
+    }
+}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+/// the most slots `SlotMap::from_entries` creates, 8 bytes each
+pub const MAX_LOADED_SLOTS: usize = 1 << 24;
+
+/// A collection giving out handles to its values, instead of references
+///
+/// the values are stored contiguously, so iterating is as fast as over a Vec,
+/// and inserting or removing is O(1), which changes the iteration order
+///
+/// ex: sprites that game code refers to across frames
+/// let mut sprites = SlotMap::new();
+/// let player = sprites.insert(Sprite::new(texture));
+/// // later, after the player may have been removed
+/// if let Some(sprite) = sprites.get_mut(player) { sprite.position += velocity }
+#[derive(Clone)]
+pub struct SlotMap<T> {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    values: Vec<T>,
+    // the slot of each value
+    owners: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    generation: u32,
+    // where the value is in `values`, while in use
+    dense: u32,
+}
+
+impl Slot {
+    fn is_used(&self) -> bool {
+        self.generation % 2 == 1
+    }
+}
+
+impl<T> Default for SlotMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SlotMap<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            values: Vec::new(),
+            owners: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            values: Vec::with_capacity(capacity),
+            owners: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// rebuilds a map with the same handles, ex: when loading a saved game
+    /// the free slots start over, so handles to values removed before saving may match again
+    /// fails on handles with an index of `MAX_LOADED_SLOTS` or more,
+    /// so that a corrupted save can't allocate a huge map
+    pub fn from_entries(entries: impl IntoIterator<Item = (Handle<T>, T)>) -> Result<Self, String> {
+        let mut ret = Self::new();
+        for (handle, value) in entries {
+            let index = handle.index as usize;
+            if index >= MAX_LOADED_SLOTS {
+                return Err(format!("Bad handle: {handle:?}, the index is too large"));
+            }
+            if ret.slots.len() <= index {
+                ret.slots.resize(
+                    index + 1,
+                    Slot {
+                        generation: 0,
+                        dense: 0,
+                    },
+                );
+            }
+            let slot = &mut ret.slots[index];
+            if slot.is_used() || handle.generation % 2 == 0 {
+                return Err(format!("Bad handle: {handle:?}"));
+            }
+            *slot = Slot {
+                generation: handle.generation,
+                dense: ret.values.len() as u32,
+            };
+            ret.values.push(value);
+            ret.owners.push(handle.index);
+        }
+
+        // the lowest slots are reused first
+        ret.free = (0..ret.slots.len() as u32)
+            .rev()
+            .filter(|&i| !ret.slots[i as usize].is_used())
+            .collect();
+        Ok(ret)
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        self.insert_with(|_| value)
+    }
+
+    /// for values that hold their own handle
+    pub fn insert_with(&mut self, f: impl FnOnce(Handle<T>) -> T) -> Handle<T> {
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(Slot {
+                generation: 0,
+                dense: 0,
+            });
+            self.slots.len() as u32 - 1
+        });
+
+        let slot = &mut self.slots[index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.dense = self.values.len() as u32;
+        let handle = Handle::new(index, slot.generation);
+        self.values.push(f(handle));
+        self.owners.push(index);
+        handle
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let dense = self.dense(handle)?;
+        let slot = &mut self.slots[handle.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+
+        // the last value fills the hole
+        self.owners.swap_remove(dense);
+        if let Some(&moved) = self.owners.get(dense) {
+            self.slots[moved as usize].dense = dense as u32;
+        }
+        Some(self.values.swap_remove(dense))
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.dense(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.dense(handle).map(|i| &self.values[i])
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.dense(handle).map(|i| &mut self.values[i])
+    }
+
+    /// two values at once, None if either was removed or both handles are the same
+    pub fn get2_mut(&mut self, a: Handle<T>, b: Handle<T>) -> Option<(&mut T, &mut T)> {
+        let (i, j) = (self.dense(a)?, self.dense(b)?);
+        if i == j {
+            return None;
+        }
+        let (lo, hi) = self.values.split_at_mut(i.max(j));
+        let (first, second) = (&mut lo[i.min(j)], &mut hi[0]);
+        Some(if i < j {
+            (first, second)
+        } else {
+            (second, first)
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// removes every value, their handles never match again
+    pub fn clear(&mut self) {
+        for &index in &self.owners {
+            let slot = &mut self.slots[index as usize];
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(index);
+        }
+        self.values.clear();
+        self.owners.clear();
+    }
+
+    /// keeps the values for which `f` returns true
+    pub fn retain(&mut self, mut f: impl FnMut(Handle<T>, &mut T) -> bool) {
+        let mut i = 0;
+        while i < self.values.len() {
+            let handle = self.handle_at(i);
+            if f(handle, &mut self.values[i]) {
+                i += 1;
+            } else {
+                // the last value moves into `i`, and is checked next
+                self.remove(handle);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        (0..self.values.len()).map(|i| (self.handle_at(i), &self.values[i]))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        let slots = &self.slots;
+        self.owners
+            .iter()
+            .zip(&mut self.values)
+            .map(|(&index, value)| {
+                let handle = Handle::new(index, slots[index as usize].generation);
+                (handle, value)
+            })
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> + '_ {
+        (0..self.values.len()).map(|i| self.handle_at(i))
+    }
+
+    /// every value as a slice, in no particular order
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    fn handle_at(&self, dense: usize) -> Handle<T> {
+        let index = self.owners[dense];
+        Handle::new(index, self.slots[index as usize].generation)
+    }
+
+    fn dense(&self, handle: Handle<T>) -> Option<usize> {
+        let slot = self.slots.get(handle.index as usize)?;
+        (slot.generation == handle.generation && slot.is_used()).then_some(slot.dense as usize)
+    }
+}
+
+impl<T> Index<Handle<T>> for SlotMap<T> {
+    type Output = T;
+
+    fn index(&self, handle: Handle<T>) -> &T {
+        self.get(handle).expect("Bad handle")
+    }
+}
+
+impl<T> IndexMut<Handle<T>> for SlotMap<T> {
+    fn index_mut(&mut self, handle: Handle<T>) -> &mut T {
+        self.get_mut(handle).expect("Bad handle")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SlotMap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Extra values for some of the handles of a `SlotMap<K>`
+/// ex: the velocity of the few sprites that move, keyed by the sprite handles
+///
+/// removing a value from the slot map doesn't remove it from here,
+/// but the stale handle matches nothing, and its value is dropped when the slot is reused
+pub struct SecondaryMap<K, V> {
+    slots: Vec<Option<(u32, V)>>,
+    len: usize,
+    _marker: PhantomData<fn() -> K>,
+}
+
+impl<K, V> Default for SecondaryMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SecondaryMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// returns the previous value for this handle
+    /// ignored for a stale handle, when a newer handle of the same slot has a value
+    /// grows to the handle's index, so the map is as long as the slot map the handle comes from
+    /// handles read with `Handle::from_bits` should be checked against their slot map first
+    pub fn insert(&mut self, handle: Handle<K>, value: V) -> Option<V> {
+        let index = handle.index as usize;
+        if self.slots.len() <= index {
+            self.slots.resize_with(index + 1, || None);
+        }
+
+        let slot = &mut self.slots[index];
+        // generations wrap around
+        if slot
+            .as_ref()
+            .is_some_and(|(generation, _)| (generation.wrapping_sub(handle.generation) as i32) > 0)
+        {
+            return None;
+        }
+        match slot.replace((handle.generation, value)) {
+            Some((generation, old)) if generation == handle.generation => Some(old),
+            Some(_) => None,
+            None => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, handle: Handle<K>) -> Option<V> {
+        self.get(handle)?;
+        self.len -= 1;
+        self.slots[handle.index as usize].take().map(|(_, v)| v)
+    }
+
+    pub fn contains(&self, handle: Handle<K>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<K>) -> Option<&V> {
+        match self.slots.get(handle.index as usize)? {
+            Some((generation, value)) if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<K>) -> Option<&mut V> {
+        match self.slots.get_mut(handle.index as usize)? {
+            Some((generation, value)) if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    /// how many values are stored, stale ones included
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// keeps the values for which `f` returns true
+    /// ex: map.retain(|h, _| sprites.contains(h)) to drop the stale values
+    pub fn retain(&mut self, mut f: impl FnMut(Handle<K>, &mut V) -> bool) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if let Some((generation, value)) = slot {
+                if !f(Handle::new(i as u32, *generation), value) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<K>, &V)> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let (generation, value) = slot.as_ref()?;
+            Some((Handle::new(i as u32, *generation), value))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<K>, &mut V)> {
+        self.slots.iter_mut().enumerate().filter_map(|(i, slot)| {
+            let (generation, value) = slot.as_mut()?;
+            Some((Handle::new(i as u32, *generation), value))
+        })
+    }
+}
+
+impl<K, V> Index<Handle<K>> for SecondaryMap<K, V> {
+    type Output = V;
+
+    fn index(&self, handle: Handle<K>) -> &V {
+        self.get(handle).expect("Bad handle")
+    }
+}
+
+impl<K, V> IndexMut<Handle<K>> for SecondaryMap<K, V> {
+    fn index_mut(&mut self, handle: Handle<K>) -> &mut V {
+        self.get_mut(handle).expect("Bad handle")
+    }
+}
+
+impl<K, V: fmt::Debug> fmt::Debug for SecondaryMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V: Clone> Clone for SecondaryMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_handles() {
+        let mut map = SlotMap::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        assert_eq!(map.remove(a), Some("a"));
+        assert_eq!(map.get(a), None);
+
+        // the slot is reused with a new generation
+        let c = map.insert("c");
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert_eq!(map.get(a), None);
+        assert_eq!(map[b], "b");
+        assert_eq!(map[c], "c");
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn from_entries() {
+        let mut map = SlotMap::new();
+        let handles: Vec<_> = (0..10).map(|i| map.insert(i)).collect();
+        for &h in handles.iter().step_by(3) {
+            map.remove(h);
+        }
+        let entries = map.iter().map(|(h, &v)| (h, v));
+        let loaded = SlotMap::from_entries(entries).unwrap();
+        assert_eq!(loaded.len(), map.len());
+        for (h, v) in map.iter() {
+            assert_eq!(loaded.get(h), Some(v));
+        }
+
+        // the same handle twice, an unused generation
+        let twice = [(handles[1], 1), (handles[1], 2)];
+        assert!(SlotMap::from_entries(twice).is_err());
+        let unused = Handle::new(0, 2);
+        assert!(SlotMap::from_entries([(unused, 0)]).is_err());
+    }
+
+    #[test]
+    fn from_entries_bounds() {
+        // most values removed before saving
+        let mut map = SlotMap::new();
+        let handles: Vec<_> = (0..1000).map(|i| map.insert(i)).collect();
+        for &h in &handles[..999] {
+            map.remove(h);
+        }
+        let entries = map.iter().map(|(h, &v)| (h, v));
+        let loaded = SlotMap::from_entries(entries).unwrap();
+        assert_eq!(loaded[handles[999]], 999);
+        assert_eq!(loaded.len(), 1);
+
+        // an index that would allocate a huge map
+        let huge = Handle::from_bits((1 << 32) | u32::MAX as u64).unwrap();
+        assert!(SlotMap::from_entries([(huge, 0)]).is_err());
+    }
+
+    #[test]
+    fn secondary_map() {
+        let mut map = SlotMap::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        let mut extra = SecondaryMap::new();
+        assert_eq!(extra.insert(b, 2), None);
+        assert_eq!(extra.insert(b, 3), Some(2));
+        assert_eq!(extra.get(a), None);
+        assert_eq!(extra.len(), 1);
+
+        // a stale handle matches nothing, and its value is replaced when the slot is reused
+        map.remove(b);
+        let c = map.insert("c");
+        assert_eq!(extra.get(c), None);
+        assert_eq!(extra.insert(c, 4), None);
+        assert_eq!(extra.get(b), None);
+        assert_eq!(extra.remove(c), Some(4));
+        assert_eq!(extra.len(), 0);
+    }
+
+    #[test]
+    fn secondary_map_stale_insert() {
+        let mut map = SlotMap::new();
+        let a = map.insert("a");
+        map.remove(a);
+        let b = map.insert("b");
+        assert_eq!(b.index(), a.index());
+
+        let mut extra = SecondaryMap::new();
+        extra.insert(b, 1);
+        assert_eq!(extra.insert(a, 2), None);
+        assert_eq!(extra.get(b), Some(&1));
+        assert_eq!(extra.get(a), None);
+        assert_eq!(extra.len(), 1);
+    }
+}
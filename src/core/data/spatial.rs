@@ -0,0 +1,359 @@
+use std::cmp::Ordering;
+
+use crate::math::Aabb;
+use crate::math::Circle;
+use crate::math::Ray;
+use crate::math::RayHit;
+use crate::math::Segment;
+use crate::math::Shape;
+use crate::math::Vec2;
+
+/// Identifies an item stored in a spatial index
+/// stays valid until the item is removed, after which it matches nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ItemId {
+    index: u32,
+    generation: u32,
+}
+
+/// Queries shared by the spatial indices, which store items by their bounding box
+///
+/// ex: culling sprites outside of the camera
+/// let mut index = QuadTree::new(world_bounds);
+/// let id = index.insert(sprite_bounds, sprite_idx);
+/// // every frame
+/// index.query_rect(&camera_bounds, |_, &idx| draw(idx));
+pub trait SpatialIndex<T> {
+    fn insert(&mut self, bounds: Aabb, value: T) -> ItemId;
+    fn remove(&mut self, id: ItemId) -> Option<T>;
+    /// moves the item, returns false if it was removed
+    fn update(&mut self, id: ItemId, bounds: Aabb) -> bool;
+    fn get(&self, id: ItemId) -> Option<&T>;
+    fn get_mut(&mut self, id: ItemId) -> Option<&mut T>;
+    fn bounds(&self, id: ItemId) -> Option<Aabb>;
+    fn len(&self) -> usize;
+    fn clear(&mut self);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// calls `f` with every item whose bounds overlap `rect`
+    fn query_rect(&self, rect: &Aabb, f: impl FnMut(ItemId, &T));
+
+    /// calls `f` with every item whose bounds overlap the circle
+    fn query_circle(&self, circle: &Circle, mut f: impl FnMut(ItemId, &T)) {
+        self.query_rect(&circle.aabb(), |id, value| {
+            if self.bounds(id).is_some_and(|b| circle.overlaps_aabb(&b)) {
+                f(id, value);
+            }
+        });
+    }
+
+    /// calls `f` with every item whose bounds contain the point
+    fn query_point(&self, point: Vec2, f: impl FnMut(ItemId, &T)) {
+        self.query_rect(&Aabb::new(point, point), f);
+    }
+
+    /// every item whose bounds the ray hits within `max_distance`, closest first
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(ItemId, RayHit)>;
+
+    /// every item whose bounds the segment hits, closest to `segment.a` first
+    fn segment_cast(&self, segment: &Segment) -> Vec<(ItemId, RayHit)> {
+        let ray = Ray::new(segment.a, segment.b - segment.a);
+        self.raycast(&ray, segment.len())
+    }
+
+    /// the `k` items with the bounds closest to `point`, closest first, with their distance
+    fn nearest(&self, point: Vec2, k: usize) -> Vec<(ItemId, f32)>;
+}
+
+// the items of an index, where `L` is where the index keeps each item
+pub(super) struct Items<T, L> {
+    slots: Vec<Slot<T, L>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+struct Slot<T, L> {
+    generation: u32,
+    entry: Option<Entry<T, L>>,
+}
+
+pub(super) struct Entry<T, L> {
+    pub bounds: Aabb,
+    pub value: T,
+    pub location: L,
+}
+
+impl<T, L> Items<T, L> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, entry: Entry<T, L>) -> ItemId {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.entry = Some(entry);
+            return ItemId {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        self.slots.push(Slot {
+            generation: 0,
+            entry: Some(entry),
+        });
+        ItemId {
+            index: self.slots.len() as u32 - 1,
+            generation: 0,
+        }
+    }
+
+    pub fn remove(&mut self, id: ItemId) -> Option<Entry<T, L>> {
+        self.get(id)?;
+        let slot = &mut self.slots[id.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.len -= 1;
+        slot.entry.take()
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&Entry<T, L>> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.entry.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: ItemId) -> Option<&mut Entry<T, L>> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.entry.as_mut()
+    }
+
+    // panics on removed items, for ids kept by the index itself
+    pub fn entry(&self, id: ItemId) -> &Entry<T, L> {
+        self.get(id).expect("Bad item")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, &Entry<T, L>)> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let id = ItemId {
+                index: i as u32,
+                generation: slot.generation,
+            };
+            slot.entry.as_ref().map(|e| (id, e))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // invalidates every id
+    pub fn clear(&mut self) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if slot.entry.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free.push(i as u32);
+            }
+        }
+        self.len = 0;
+    }
+}
+
+// a distance that can be sorted, for the nearest neighbor searches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Distance(pub f32);
+
+impl Eq for Distance {}
+
+impl PartialOrd for Distance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Bvh;
+    use crate::data::QuadTree;
+    use crate::data::SpatialHash;
+    use crate::math::Rng;
+
+    const ITEMS: usize = 300;
+    const QUERIES: usize = 100;
+    const WORLD: f32 = 512.0;
+
+    fn random_point(rng: &mut Rng) -> Vec2 {
+        Vec2::new(rng.range(0.0..WORLD), rng.range(0.0..WORLD))
+    }
+
+    fn random_box(rng: &mut Rng, max_size: f32) -> Aabb {
+        let min = random_point(rng);
+        let size = Vec2::new(rng.range(1.0..max_size), rng.range(1.0..max_size));
+        Aabb::new(min, min + size)
+    }
+
+    fn sorted<T: Ord>(mut v: Vec<T>) -> Vec<T> {
+        v.sort();
+        v
+    }
+
+    // every overlapping pair once, smallest first
+    fn brute_force_pairs<T: Copy + Ord>(items: &[(T, Aabb)]) -> Vec<(T, T)> {
+        let mut pairs = Vec::new();
+        for (i, &(a, ba)) in items.iter().enumerate() {
+            for &(b, bb) in &items[i + 1..] {
+                if ba.overlaps(&bb) {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+        sorted(pairs)
+    }
+
+    // inserts, moves and removes random boxes, then checks every query against a scan
+    fn check(index: &mut impl SpatialIndex<usize>) {
+        let mut rng = Rng::new(44);
+        let mut boxes: Vec<Aabb> = (0..ITEMS).map(|_| random_box(&mut rng, 64.0)).collect();
+        let ids: Vec<ItemId> = (0..ITEMS).map(|i| index.insert(boxes[i], i)).collect();
+
+        for i in (0..ITEMS).step_by(3) {
+            boxes[i] = random_box(&mut rng, 64.0);
+            assert!(index.update(ids[i], boxes[i]));
+        }
+        for i in (0..ITEMS).step_by(7) {
+            assert_eq!(index.remove(ids[i]), Some(i));
+            assert!(index.get(ids[i]).is_none());
+            assert!(!index.update(ids[i], boxes[i]));
+        }
+        let alive: Vec<usize> = (0..ITEMS).filter(|i| i % 7 != 0).collect();
+        assert_eq!(index.len(), alive.len());
+        let expected = |f: &dyn Fn(&Aabb) -> bool| {
+            sorted(
+                alive
+                    .iter()
+                    .filter(|&&i| f(&boxes[i]))
+                    .map(|&i| ids[i])
+                    .collect(),
+            )
+        };
+
+        for _ in 0..QUERIES {
+            let rect = random_box(&mut rng, 128.0);
+            let mut found = Vec::new();
+            index.query_rect(&rect, |id, _| found.push(id));
+            assert_eq!(sorted(found), expected(&|b| b.overlaps(&rect)));
+
+            let circle = Circle::new(random_point(&mut rng), rng.range(1.0..64.0));
+            let mut found = Vec::new();
+            index.query_circle(&circle, |id, _| found.push(id));
+            assert_eq!(sorted(found), expected(&|b| circle.overlaps_aabb(b)));
+
+            let ray = Ray::new(random_point(&mut rng), rng.unit_vec2());
+            let hits = index.raycast(&ray, 256.0);
+            let found = hits.iter().map(|h| h.0).collect();
+            assert_eq!(
+                sorted(found),
+                expected(&|b| b.raycast(&ray, 256.0).is_some())
+            );
+            assert!(hits.windows(2).all(|w| w[0].1.distance <= w[1].1.distance));
+
+            let point = random_point(&mut rng);
+            let mut distances: Vec<f32> = alive
+                .iter()
+                .map(|&i| boxes[i].distance_to_point(point))
+                .collect();
+            distances.sort_by(f32::total_cmp);
+            let found: Vec<f32> = index.nearest(point, 5).iter().map(|n| n.1).collect();
+            assert_eq!(found, distances[..5]);
+        }
+
+        // far outside everything
+        for point in [Vec2::splat(-1e6), Vec2::new(1e7, 0.5 * WORLD)] {
+            let mut distances: Vec<f32> = alive
+                .iter()
+                .map(|&i| boxes[i].distance_to_point(point))
+                .collect();
+            distances.sort_by(f32::total_cmp);
+            let found: Vec<f32> = index.nearest(point, 3).iter().map(|n| n.1).collect();
+            assert_eq!(found, distances[..3]);
+        }
+    }
+
+    #[test]
+    fn quadtree() {
+        let region = Aabb::new(Vec2::zero(), Vec2::splat(WORLD + 64.0));
+        check(&mut QuadTree::new(region));
+    }
+
+    #[test]
+    fn bvh() {
+        check(&mut Bvh::new());
+    }
+
+    #[test]
+    fn spatial_hash() {
+        check(&mut SpatialHash::new(32.0));
+    }
+
+    #[test]
+    fn pairs() {
+        let mut rng = Rng::new(44);
+        let boxes: Vec<Aabb> = (0..ITEMS).map(|_| random_box(&mut rng, 64.0)).collect();
+        let expected = brute_force_pairs(&boxes.iter().copied().enumerate().collect::<Vec<_>>());
+        assert!(!expected.is_empty());
+
+        let mut hash = SpatialHash::new(32.0);
+        for (i, &b) in boxes.iter().enumerate() {
+            hash.insert(b, i);
+        }
+        let mut found = Vec::new();
+        hash.pairs(|a, b| {
+            let (a, b) = (hash.get(a).unwrap(), hash.get(b).unwrap());
+            found.push((*a.min(b), *a.max(b)));
+        });
+        assert_eq!(sorted(found), expected);
+
+        let bvh = Bvh::from_items(boxes.iter().copied().zip(0..ITEMS));
+        let mut found = Vec::new();
+        bvh.pairs(|a, b| {
+            let (a, b) = (bvh.get(a).unwrap(), bvh.get(b).unwrap());
+            found.push((*a.min(b), *a.max(b)));
+        });
+        assert_eq!(sorted(found), expected);
+
+        // every pair both ways, and every item with itself
+        let mut between = 0;
+        bvh.pairs_with(&bvh, |_, _| between += 1);
+        assert_eq!(between, 2 * expected.len() + bvh.len());
+
+        for _ in 0..QUERIES {
+            let ray = Ray::new(random_point(&mut rng), rng.unit_vec2());
+            let all = bvh.raycast(&ray, 256.0);
+            let first = bvh.raycast_first(&ray, 256.0);
+            assert_eq!(
+                first.map(|h| h.1.distance),
+                all.first().map(|h| h.1.distance)
+            );
+        }
+    }
+}
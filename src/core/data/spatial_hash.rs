@@ -0,0 +1,325 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+use super::spatial::Distance;
+use super::spatial::Entry;
+use super::spatial::Items;
+use super::ItemId;
+use super::SpatialIndex;
+use crate::math::Aabb;
+use crate::math::IVec2;
+use crate::math::Ray;
+use crate::math::RayHit;
+use crate::math::Shape;
+use crate::math::Vec2;
+
+/// A spatial index made of square cells of the same size, only storing the ones in use
+///
+/// faster than a tree when the items have similar sizes,
+/// pick a cell size about the size of a typical item
+/// ex: the bullets of a bullet hell, or a crowd of units
+///
+/// emptied cells keep their memory for the next items moving in,
+/// so that moving items around every frame doesn't allocate, see `shrink_to_fit`
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    // a fixed hasher, so that `pairs` gives the same order on every run
+    cells: HashMap<IVec2, Vec<ItemId>, BuildHasherDefault<DefaultHasher>>,
+    // located by their (min, max) cells
+    items: Items<T, (IVec2, IVec2)>,
+    // every cell ever used is in there
+    used: Option<(IVec2, IVec2)>,
+}
+
+impl<T> SpatialHash<T> {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Bad cell size");
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            items: Items::new(),
+            used: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn cell_of(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().cast()
+    }
+
+    /// the items whose bounds touch the cell
+    pub fn cell(&self, cell: IVec2) -> &[ItemId] {
+        self.cells.get(&cell).map_or(&[], |c| c.as_slice())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, Aabb, &T)> {
+        self.items.iter().map(|(id, e)| (id, e.bounds, &e.value))
+    }
+
+    /// calls `f` once with every pair of items whose bounds overlap
+    /// ex: the broad phase of collision detection
+    pub fn pairs(&self, mut f: impl FnMut(ItemId, ItemId)) {
+        for (&cell, ids) in &self.cells {
+            for (i, &a) in ids.iter().enumerate() {
+                let ea = self.items.entry(a);
+                for &b in &ids[i + 1..] {
+                    let eb = self.items.entry(b);
+                    // pairs sharing several cells are only reported in the first one
+                    let first = ea.location.0.max(eb.location.0);
+                    if cell == first && ea.bounds.overlaps(&eb.bounds) {
+                        f(a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    /// frees the memory of the empty cells
+    pub fn shrink_to_fit(&mut self) {
+        self.cells.retain(|_, ids| !ids.is_empty());
+        self.cells.shrink_to_fit();
+    }
+
+    fn cells_of(&self, bounds: &Aabb) -> (IVec2, IVec2) {
+        (self.cell_of(bounds.min), self.cell_of(bounds.max))
+    }
+
+    fn add(&mut self, id: ItemId, (min, max): (IVec2, IVec2), skip: Option<(IVec2, IVec2)>) {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let cell = IVec2::new(x, y);
+                if !skip.is_some_and(|s| within(cell, s)) {
+                    self.cells.entry(cell).or_default().push(id);
+                }
+            }
+        }
+
+        self.used = Some(match self.used {
+            Some((lo, hi)) => (lo.min(min), hi.max(max)),
+            None => (min, max),
+        });
+    }
+
+    fn detach(&mut self, id: ItemId, (min, max): (IVec2, IVec2), skip: Option<(IVec2, IVec2)>) {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let cell = IVec2::new(x, y);
+                if skip.is_some_and(|s| within(cell, s)) {
+                    continue;
+                }
+                let ids = self.cells.get_mut(&cell).unwrap();
+                let idx = ids.iter().position(|&i| i == id).unwrap();
+                ids.swap_remove(idx);
+            }
+        }
+    }
+}
+
+fn within(cell: IVec2, (min, max): (IVec2, IVec2)) -> bool {
+    min.x <= cell.x && cell.x <= max.x && min.y <= cell.y && cell.y <= max.y
+}
+
+impl<T> SpatialIndex<T> for SpatialHash<T> {
+    fn insert(&mut self, bounds: Aabb, value: T) -> ItemId {
+        let cells = self.cells_of(&bounds);
+        let id = self.items.insert(Entry {
+            bounds,
+            value,
+            location: cells,
+        });
+        self.add(id, cells, None);
+        id
+    }
+
+    fn remove(&mut self, id: ItemId) -> Option<T> {
+        let entry = self.items.remove(id)?;
+        self.detach(id, entry.location, None);
+        Some(entry.value)
+    }
+
+    fn update(&mut self, id: ItemId, bounds: Aabb) -> bool {
+        let cells = self.cells_of(&bounds);
+        let Some(entry) = self.items.get_mut(id) else {
+            return false;
+        };
+        let old = entry.location;
+        entry.bounds = bounds;
+        entry.location = cells;
+
+        // only the cells left or entered change
+        if old != cells {
+            self.detach(id, old, Some(cells));
+            self.add(id, cells, Some(old));
+        }
+        true
+    }
+
+    fn get(&self, id: ItemId) -> Option<&T> {
+        self.items.get(id).map(|e| &e.value)
+    }
+
+    fn get_mut(&mut self, id: ItemId) -> Option<&mut T> {
+        self.items.get_mut(id).map(|e| &mut e.value)
+    }
+
+    fn bounds(&self, id: ItemId) -> Option<Aabb> {
+        self.items.get(id).map(|e| e.bounds)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn clear(&mut self) {
+        for ids in self.cells.values_mut() {
+            ids.clear();
+        }
+        self.items.clear();
+    }
+
+    fn query_rect(&self, rect: &Aabb, mut f: impl FnMut(ItemId, &T)) {
+        let (min, max) = self.cells_of(rect);
+        let area = (max - min + IVec2::one()).cast::<f64>().element_product();
+
+        // scanning every item is faster than looking up that many cells
+        if area > self.items.len() as f64 {
+            for (id, entry) in self.items.iter() {
+                if entry.bounds.overlaps(rect) {
+                    f(id, &entry.value);
+                }
+            }
+            return;
+        }
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let cell = IVec2::new(x, y);
+                for &id in self.cell(cell) {
+                    let entry = self.items.entry(id);
+                    // items in several cells are only reported in the first one in range
+                    if cell == entry.location.0.max(min) && entry.bounds.overlaps(rect) {
+                        f(id, &entry.value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(ItemId, RayHit)> {
+        let mut ret: Vec<(ItemId, RayHit)> = Vec::new();
+        let Some((lo, hi)) = self.used else {
+            return ret;
+        };
+
+        // walks the cells along the ray, within the cells in use (Amanatides & Woo)
+        let used = Aabb::new(
+            lo.cast::<f32>() * self.cell_size,
+            (hi + IVec2::one()).cast::<f32>() * self.cell_size,
+        );
+        let Some(enter) = used.raycast(ray, max_distance) else {
+            return ret;
+        };
+        let end = (0..2)
+            .filter(|&axis| ray.direction[axis] != 0.0)
+            .map(|axis| {
+                let d = ray.direction[axis];
+                let side = if d > 0.0 { used.max } else { used.min };
+                (side[axis] - ray.origin[axis]) / d
+            })
+            .fold(max_distance, f32::min);
+
+        let mut t = enter.distance;
+        let mut cell = self.cell_of(ray.at(t)).clamp(lo, hi);
+        let step = ray.direction.signum().cast::<i32>();
+        let mut next = Vec2::zero();
+        let mut delta = Vec2::zero();
+        for axis in 0..2 {
+            let d = ray.direction[axis];
+            delta[axis] = if d == 0.0 {
+                f32::INFINITY
+            } else {
+                self.cell_size / d.abs()
+            };
+            let boundary = (cell[axis] + (d > 0.0) as i32) as f32 * self.cell_size;
+            next[axis] = if d == 0.0 {
+                f32::INFINITY
+            } else {
+                (boundary - ray.origin[axis]) / d
+            };
+        }
+
+        while t <= end {
+            for &id in self.cell(cell) {
+                if let Some(hit) = self.items.entry(id).bounds.raycast(ray, max_distance) {
+                    ret.push((id, hit));
+                }
+            }
+
+            let axis = if next.x < next.y { 0 } else { 1 };
+            t = next[axis];
+            next[axis] += delta[axis];
+            cell[axis] += step[axis];
+        }
+
+        // items in several cells were found several times
+        ret.sort_by_key(|h| h.0);
+        ret.dedup_by_key(|h| h.0);
+        ret.sort_by(|a, b| a.1.distance.total_cmp(&b.1.distance));
+        ret
+    }
+
+    fn nearest(&self, point: Vec2, k: usize) -> Vec<(ItemId, f32)> {
+        let mut found: Vec<(Distance, ItemId)> = Vec::new();
+        let Some((lo, hi)) = self.used.filter(|_| k > 0) else {
+            return Vec::new();
+        };
+
+        // rings of cells around the point, after ring r everything closer than r cells was seen
+        // the rings start where the cells in use begin and only walk the cells in use
+        let center = self.cell_of(point);
+        let first = (lo - center).max(center - hi).max_element().max(0);
+        let rings = (center - lo).max(hi - center).max_element().max(0);
+        let mut ring: Vec<IVec2> = Vec::new();
+        for r in first..=rings {
+            let (min, max) = (center - IVec2::splat(r), center + IVec2::splat(r));
+            let (clip_min, clip_max) = (min.max(lo), max.min(hi));
+            ring.clear();
+            for y in [min.y, max.y] {
+                if (lo.y..=hi.y).contains(&y) {
+                    ring.extend((clip_min.x..=clip_max.x).map(|x| IVec2::new(x, y)));
+                }
+            }
+            for x in [min.x, max.x] {
+                if (lo.x..=hi.x).contains(&x) {
+                    let (y0, y1) = ((min.y + 1).max(lo.y), (max.y - 1).min(hi.y));
+                    ring.extend((y0..=y1).map(|y| IVec2::new(x, y)));
+                }
+            }
+            if r == 0 {
+                ring.truncate(1);
+            }
+
+            for &cell in &ring {
+                for &id in self.cell(cell) {
+                    let d = self.items.entry(id).bounds.distance_to_point(point);
+                    found.push((Distance(d), id));
+                }
+            }
+
+            found.sort_by_key(|f| f.1);
+            found.dedup_by_key(|f| f.1);
+            found.sort();
+            let reached = r as f32 * self.cell_size;
+            if found.len() >= k && found[k - 1].0 .0 <= reached {
+                break;
+            }
+        }
+
+        found.truncate(k);
+        found.into_iter().map(|(d, id)| (id, d.0)).collect()
+    }
+}
@@ -0,0 +1,77 @@
+use super::Bundle;
+use super::Component;
+use super::Entity;
+use super::World;
+
+/// Changes to a world, made later when nothing is borrowed
+///
+/// ex: despawning from inside a query
+/// world.query::<(Entity, &Health)>().for_each(|(entity, health)| {
+///     if health.0 <= 0 {
+///         world.commands().despawn(entity);
+///     }
+/// });
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+impl Commands {
+    pub fn new() -> Self {
+        Self { queue: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// to set the entity up further, spawn it from `add` instead
+    pub fn spawn(&mut self, bundle: impl Bundle + 'static) {
+        self.add(move |world| {
+            world.spawn(bundle);
+        });
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    /// does nothing if the entity was despawned in the meantime
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        self.add(move |world| {
+            if world.is_alive(entity) {
+                world.insert(entity, component);
+            }
+        });
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.remove::<T>(entity);
+        });
+    }
+
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.add(move |world| world.insert_resource(resource));
+    }
+
+    /// any change to the world
+    pub fn add(&mut self, f: impl FnOnce(&mut World) + 'static) {
+        self.queue.push(Box::new(f));
+    }
+
+    /// makes the changes, in the order they were queued
+    pub fn apply(self, world: &mut World) {
+        for f in self.queue {
+            f(world);
+        }
+    }
+}
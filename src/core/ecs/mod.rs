@@ -0,0 +1,17 @@
+// an entity component system: entities are ids, their data is in components stored by type,
+// and the game logic is in systems, functions of the world run in stages every frame
+
+mod commands;
+pub use commands::*;
+
+mod query;
+pub use query::*;
+
+mod schedule;
+pub use schedule::*;
+
+mod sprite;
+pub use sprite::*;
+
+mod world;
+pub use world::*;
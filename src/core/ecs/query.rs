@@ -0,0 +1,497 @@
+use std::any::type_name;
+use std::cell::Cell;
+use std::cell::Ref;
+use std::cell::RefMut;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use super::world::Storage;
+use super::Component;
+use super::Entity;
+use super::World;
+
+/// What a query gives for each entity: `Entity`, `&T`, `&mut T`, `Option<&T>`, `Option<&mut T>`
+/// or a tuple of them
+///
+/// `&mut T` gives a `Mut<T>`, which marks the component as changed when written to, see `Changed`
+/// a query can't take the same component twice when one of them is `&mut`, it panics
+pub trait QueryData {
+    type State<'w>;
+    type Item<'s>;
+
+    /// None when the world has none of a required component
+    fn borrow(world: &World) -> Option<Self::State<'_>>;
+    /// the entities having a required component, the query only looks at the smallest set
+    fn candidates<'w>(state: &Self::State<'w>) -> Option<&'w [Entity]>;
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool;
+    /// for an entity that `matches`
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u64) -> Self::Item<'s>;
+}
+
+/// Which entities a query looks at: `With<T>`, `Without<T>`, `Changed<T>`, `Added<T>`,
+/// or a tuple of them for entities matching all of them
+pub trait QueryFilter {
+    type State<'w>;
+
+    fn borrow(world: &World) -> Self::State<'_>;
+    fn matches(state: &Self::State<'_>, entity: Entity, last_run: u64) -> bool;
+}
+
+/// Entities with a `T`
+pub struct With<T>(PhantomData<T>);
+
+/// Entities without a `T`
+pub struct Without<T>(PhantomData<T>);
+
+/// Entities whose `T` changed since the system last ran, added ones included
+/// ex: rebuilding a collider only when the `Transform2D` moved
+pub struct Changed<T>(PhantomData<T>);
+
+/// Entities that received a `T` since the system last ran
+pub struct Added<T>(PhantomData<T>);
+
+/// A component taken as `&mut T` by a query, marked as changed only once written to
+pub struct Mut<'a, T> {
+    value: &'a mut T,
+    changed: &'a Cell<u64>,
+    tick: u64,
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed.set(self.tick);
+        self.value
+    }
+}
+
+fn borrow_error<T>() -> String {
+    format!("Bad query: {} is already borrowed", type_name::<T>())
+}
+
+impl QueryData for Entity {
+    type State<'w> = ();
+    type Item<'s> = Entity;
+
+    fn borrow(_world: &World) -> Option<()> {
+        Some(())
+    }
+
+    fn candidates<'w>(_state: &()) -> Option<&'w [Entity]> {
+        None
+    }
+
+    fn matches(_state: &(), _entity: Entity) -> bool {
+        true
+    }
+
+    fn fetch(_state: &mut (), entity: Entity, _tick: u64) -> Entity {
+        entity
+    }
+}
+
+impl<T: Component> QueryData for &T {
+    type State<'w> = (&'w Storage<T>, Ref<'w, Vec<T>>);
+    type Item<'s> = &'s T;
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        let storage = world.storage::<T>()?;
+        let values = storage.values.try_borrow();
+        Some((
+            storage,
+            values.unwrap_or_else(|_| panic!("{}", borrow_error::<T>())),
+        ))
+    }
+
+    fn candidates<'w>(state: &Self::State<'w>) -> Option<&'w [Entity]> {
+        Some(&state.0.entities)
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        state.0.dense(entity).is_some()
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, _tick: u64) -> &'s T {
+        &state.1[state.0.dense(entity).unwrap()]
+    }
+}
+
+impl<T: Component> QueryData for &mut T {
+    type State<'w> = (&'w Storage<T>, RefMut<'w, Vec<T>>);
+    type Item<'s> = Mut<'s, T>;
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        let storage = world.storage::<T>()?;
+        let values = storage.values.try_borrow_mut();
+        Some((
+            storage,
+            values.unwrap_or_else(|_| panic!("{}", borrow_error::<T>())),
+        ))
+    }
+
+    fn candidates<'w>(state: &Self::State<'w>) -> Option<&'w [Entity]> {
+        Some(&state.0.entities)
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        state.0.dense(entity).is_some()
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u64) -> Mut<'s, T> {
+        let i = state.0.dense(entity).unwrap();
+        Mut {
+            value: &mut state.1[i],
+            changed: &state.0.changed[i],
+            tick,
+        }
+    }
+}
+
+impl<T: Component> QueryData for Option<&T> {
+    type State<'w> = Option<<&'static T as QueryData>::State<'w>>;
+    type Item<'s> = Option<&'s T>;
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        Some(<&T>::borrow(world))
+    }
+
+    fn candidates<'w>(_state: &Self::State<'w>) -> Option<&'w [Entity]> {
+        None
+    }
+
+    fn matches(_state: &Self::State<'_>, _entity: Entity) -> bool {
+        true
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u64) -> Option<&'s T> {
+        let state = state.as_mut()?;
+        <&T>::matches(state, entity).then(|| <&T>::fetch(state, entity, tick))
+    }
+}
+
+impl<T: Component> QueryData for Option<&mut T> {
+    type State<'w> = Option<<&'static mut T as QueryData>::State<'w>>;
+    type Item<'s> = Option<Mut<'s, T>>;
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        Some(<&mut T>::borrow(world))
+    }
+
+    fn candidates<'w>(_state: &Self::State<'w>) -> Option<&'w [Entity]> {
+        None
+    }
+
+    fn matches(_state: &Self::State<'_>, _entity: Entity) -> bool {
+        true
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u64) -> Option<Mut<'s, T>> {
+        let state = state.as_mut()?;
+        if <&mut T>::matches(state, entity) {
+            Some(<&mut T>::fetch(state, entity, tick))
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! impl_query_data {
+    ($($q:ident $i:tt),+) => {
+        impl<$($q: QueryData),+> QueryData for ($($q,)+) {
+            type State<'w> = ($($q::State<'w>,)+);
+            type Item<'s> = ($($q::Item<'s>,)+);
+
+            fn borrow(world: &World) -> Option<Self::State<'_>> {
+                Some(($($q::borrow(world)?,)+))
+            }
+
+            fn candidates<'w>(state: &Self::State<'w>) -> Option<&'w [Entity]> {
+                [$($q::candidates(&state.$i)),+]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|c| c.len())
+            }
+
+            fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+                $($q::matches(&state.$i, entity))&&+
+            }
+
+            fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u64) -> Self::Item<'s> {
+                ($($q::fetch(&mut state.$i, entity, tick),)+)
+            }
+        }
+    };
+}
+
+impl_query_data!(A 0);
+impl_query_data!(A 0, B 1);
+impl_query_data!(A 0, B 1, C 2);
+impl_query_data!(A 0, B 1, C 2, D 3);
+impl_query_data!(A 0, B 1, C 2, D 3, E 4);
+impl_query_data!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_query_data!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_query_data!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl QueryFilter for () {
+    type State<'w> = ();
+
+    fn borrow(_world: &World) {}
+
+    fn matches(_state: &(), _entity: Entity, _last_run: u64) -> bool {
+        true
+    }
+}
+
+impl<T: Component> QueryFilter for With<T> {
+    type State<'w> = Option<&'w Storage<T>>;
+
+    fn borrow(world: &World) -> Self::State<'_> {
+        world.storage::<T>()
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity, _last_run: u64) -> bool {
+        state.is_some_and(|s| s.dense(entity).is_some())
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    type State<'w> = Option<&'w Storage<T>>;
+
+    fn borrow(world: &World) -> Self::State<'_> {
+        world.storage::<T>()
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity, _last_run: u64) -> bool {
+        !state.is_some_and(|s| s.dense(entity).is_some())
+    }
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    type State<'w> = Option<&'w Storage<T>>;
+
+    fn borrow(world: &World) -> Self::State<'_> {
+        world.storage::<T>()
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity, last_run: u64) -> bool {
+        state.is_some_and(|s| {
+            s.dense(entity)
+                .is_some_and(|i| s.changed[i].get() > last_run)
+        })
+    }
+}
+
+impl<T: Component> QueryFilter for Added<T> {
+    type State<'w> = Option<&'w Storage<T>>;
+
+    fn borrow(world: &World) -> Self::State<'_> {
+        world.storage::<T>()
+    }
+
+    fn matches(state: &Self::State<'_>, entity: Entity, last_run: u64) -> bool {
+        state.is_some_and(|s| s.dense(entity).is_some_and(|i| s.added[i] > last_run))
+    }
+}
+
+macro_rules! impl_query_filter {
+    ($($f:ident $i:tt),+) => {
+        impl<$($f: QueryFilter),+> QueryFilter for ($($f,)+) {
+            type State<'w> = ($($f::State<'w>,)+);
+
+            fn borrow(world: &World) -> Self::State<'_> {
+                ($($f::borrow(world),)+)
+            }
+
+            fn matches(state: &Self::State<'_>, entity: Entity, last_run: u64) -> bool {
+                $($f::matches(&state.$i, entity, last_run))&&+
+            }
+        }
+    };
+}
+
+impl_query_filter!(A 0);
+impl_query_filter!(A 0, B 1);
+impl_query_filter!(A 0, B 1, C 2);
+impl_query_filter!(A 0, B 1, C 2, D 3);
+
+/// The entities of a world matching `Q` and `F`, see `World::query`
+///
+/// the components are borrowed while the query runs, so entities can't be spawned or despawned
+/// from inside of it, use `World::commands` for that
+pub struct Query<'w, Q, F = ()> {
+    world: &'w World,
+    _marker: PhantomData<(Q, F)>,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Query<'w, Q, F> {
+    pub(super) fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            _marker: PhantomData,
+        }
+    }
+
+    /// calls `f` with the components of every matching entity
+    pub fn for_each(&self, mut f: impl FnMut(Q::Item<'_>)) {
+        let Some(mut state) = Q::borrow(self.world) else {
+            return;
+        };
+        let filter = F::borrow(self.world);
+        let (tick, last_run) = (self.world.tick(), self.world.last_run());
+
+        let candidates = Q::candidates(&state);
+        let each = |entity: Entity| {
+            if Q::matches(&state, entity) && F::matches(&filter, entity, last_run) {
+                f(Q::fetch(&mut state, entity, tick));
+            }
+        };
+        match candidates {
+            Some(candidates) => candidates.iter().copied().for_each(each),
+            None => self.world.entities().for_each(each),
+        }
+    }
+
+    /// the components of one entity, if it matches
+    pub fn get<R>(&self, entity: Entity, f: impl FnOnce(Q::Item<'_>) -> R) -> Option<R> {
+        let mut state = Q::borrow(self.world)?;
+        let filter = F::borrow(self.world);
+        let (tick, last_run) = (self.world.tick(), self.world.last_run());
+
+        let alive = self.world.is_alive(entity);
+        (alive && Q::matches(&state, entity) && F::matches(&filter, entity, last_run))
+            .then(|| f(Q::fetch(&mut state, entity, tick)))
+    }
+
+    /// the matching entities, without touching their components
+    pub fn entities(&self) -> Vec<Entity> {
+        let Some(state) = Q::borrow(self.world) else {
+            return Vec::new();
+        };
+        let filter = F::borrow(self.world);
+        let last_run = self.world.last_run();
+
+        let matches = |&e: &Entity| Q::matches(&state, e) && F::matches(&filter, e, last_run);
+        match Q::candidates(&state) {
+            Some(candidates) => candidates.iter().copied().filter(matches).collect(),
+            None => self.world.entities().filter(matches).collect(),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.entities().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pos(i32);
+    struct Vel(i32);
+    struct Frozen;
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort();
+        entities
+    }
+
+    #[test]
+    fn filters() {
+        let mut world = World::new();
+        let a = world.spawn((Pos(0), Vel(1)));
+        let b = world.spawn((Pos(0), Vel(2), Frozen));
+        let c = world.spawn((Pos(0),));
+
+        assert_eq!(sorted(world.query::<&Pos>().entities()), [a, b, c]);
+        assert_eq!(sorted(world.query::<(&Pos, &Vel)>().entities()), [a, b]);
+        let moving = world.query_filtered::<&Pos, (With<Vel>, Without<Frozen>)>();
+        assert_eq!(moving.entities(), [a]);
+        assert_eq!(
+            world.query_filtered::<Entity, With<Frozen>>().entities(),
+            [b]
+        );
+
+        world
+            .query_filtered::<(&mut Pos, &Vel), Without<Frozen>>()
+            .for_each(|(mut pos, vel)| pos.0 += vel.0);
+        let mut seen = Vec::new();
+        world
+            .query::<(&Pos, Option<&Vel>)>()
+            .for_each(|(pos, vel)| seen.push((pos.0, vel.map(|v| v.0))));
+        seen.sort();
+        assert_eq!(seen, [(0, None), (0, Some(2)), (1, Some(1))]);
+    }
+
+    #[test]
+    fn change_detection() {
+        let mut world = World::new();
+        let a = world.spawn((Pos(0),));
+        let b = world.spawn((Pos(0),));
+        let changed =
+            |world: &World| sorted(world.query_filtered::<Entity, Changed<Pos>>().entities());
+        let added = |world: &World| sorted(world.query_filtered::<Entity, Added<Pos>>().entities());
+        assert_eq!(changed(&world), [a, b]);
+        assert_eq!(added(&world), [a, b]);
+
+        // only written components are changed
+        world.clear_trackers();
+        world
+            .query::<&mut Pos>()
+            .for_each(|pos| assert_eq!(pos.0, 0));
+        world
+            .query::<(Entity, Option<&mut Pos>)>()
+            .for_each(|(_, pos)| {
+                assert!(pos.is_some_and(|p| p.0 == 0));
+            });
+        assert!(changed(&world).is_empty());
+        world
+            .query::<(Entity, &mut Pos)>()
+            .for_each(|(entity, mut pos)| {
+                if entity == b {
+                    pos.0 = 1;
+                }
+            });
+        assert_eq!(changed(&world), [b]);
+        assert!(added(&world).is_empty());
+
+        // replacing and getting a component mutably are changes
+        world.clear_trackers();
+        world.insert(a, Pos(2));
+        assert_eq!(changed(&world), [a]);
+        world.clear_trackers();
+        world.get_mut::<Pos>(b).unwrap().0 = 3;
+        assert_eq!(changed(&world), [b]);
+        world.clear_trackers();
+        assert!(changed(&world).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "is already borrowed")]
+    fn borrow_conflict() {
+        let mut world = World::new();
+        world.spawn((Pos(0),));
+        world.query::<(&mut Pos, &Pos)>().for_each(|_| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "is already borrowed")]
+    fn nested_borrow_conflict() {
+        let mut world = World::new();
+        world.spawn((Pos(0), Vel(0)));
+        world
+            .query::<&Vel>()
+            .for_each(|_| world.query::<&mut Vel>().for_each(|_| {}));
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use super::World;
+
+/// When a system runs in a frame, in this order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// once, before the first tick
+    Startup,
+    PreTick,
+    /// the game logic, ex: movement
+    Tick,
+    /// reacting to the tick, ex: despawning what died
+    PostTick,
+    /// once per frame, before drawing, ex: animating sprites
+    PreDraw,
+    Draw,
+}
+
+impl Stage {
+    const TICK: [Stage; 3] = [Stage::PreTick, Stage::Tick, Stage::PostTick];
+    const DRAW: [Stage; 2] = [Stage::PreDraw, Stage::Draw];
+}
+
+/// The frame timing, kept up to date by `Schedule::tick` as a resource
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Time {
+    /// since the previous tick
+    pub delta: Duration,
+    pub elapsed: Duration,
+    pub frame: u64,
+}
+
+impl Time {
+    /// the delta in seconds, to scale speeds with
+    pub fn dt(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+}
+
+struct System {
+    stage: Stage,
+    // the tick it last ran at, for the change detection
+    last_run: u64,
+    run: Box<dyn FnMut(&mut World)>,
+}
+
+/// The systems of a game, run by stage from `GameLoop::tick` and `GameLoop::draw`
+///
+/// within a stage systems run in the order they were added,
+/// the commands of each system are applied before the next one runs
+///
+/// ex:
+/// let schedule = Schedule::new()
+///     .with_system(Stage::Startup, spawn_level)
+///     .with_system(Stage::Tick, move_things);
+/// // in GameLoop::tick
+/// self.schedule.tick(&mut self.world, dt);
+/// // in GameLoop::draw
+/// self.schedule.draw(&mut self.world);
+/// draw_sprites(&self.world, &mut self.queue, &self.sprites, &self.shader);
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<System>,
+    started: bool,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            started: false,
+        }
+    }
+
+    pub fn with_system(mut self, stage: Stage, system: impl FnMut(&mut World) + 'static) -> Self {
+        self.add_system(stage, system);
+        self
+    }
+
+    pub fn add_system(&mut self, stage: Stage, system: impl FnMut(&mut World) + 'static) {
+        self.systems.push(System {
+            stage,
+            last_run: 0,
+            run: Box::new(system),
+        });
+    }
+
+    /// updates the `Time` resource, then runs the startup systems the first time,
+    /// and the `PreTick`, `Tick` and `PostTick` systems
+    pub fn tick(&mut self, world: &mut World, dt: Duration) {
+        let mut time = world.try_resource::<Time>().map_or(Time::default(), |t| *t);
+        time.delta = dt;
+        time.elapsed += dt;
+        time.frame += 1;
+        world.insert_resource(time);
+
+        if !self.started {
+            self.started = true;
+            self.run_stage(world, Stage::Startup);
+        }
+        for stage in Stage::TICK {
+            self.run_stage(world, stage);
+        }
+    }
+
+    /// runs the `PreDraw` and `Draw` systems
+    pub fn draw(&mut self, world: &mut World) {
+        for stage in Stage::DRAW {
+            self.run_stage(world, stage);
+        }
+    }
+
+    pub fn run_stage(&mut self, world: &mut World, stage: Stage) {
+        for system in self.systems.iter_mut().filter(|s| s.stage == stage) {
+            system.last_run = world.run_system(system.last_run, &mut system.run);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::ecs::Added;
+    use crate::ecs::Changed;
+    use crate::ecs::Entity;
+
+    struct Pos(i32);
+    struct Vel(i32);
+
+    // the entities each system saw, by name
+    type Log = Rc<RefCell<Vec<(&'static str, usize)>>>;
+
+    fn counter<F: crate::ecs::QueryFilter + 'static>(
+        log: &Log,
+        name: &'static str,
+    ) -> impl FnMut(&mut World) + 'static {
+        let log = log.clone();
+        move |world| {
+            let count = world.query_filtered::<Entity, F>().count();
+            log.borrow_mut().push((name, count));
+        }
+    }
+
+    #[test]
+    fn change_detection_across_stages() {
+        let log = Log::default();
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Startup, |world| {
+                world.spawn((Pos(0), Vel(1)));
+                world.spawn((Pos(0), Vel(0)));
+            })
+            .with_system(Stage::Tick, |world| {
+                // iterating without writing changes nothing
+                world
+                    .query::<(&mut Pos, &Vel)>()
+                    .for_each(|(mut pos, vel)| {
+                        if vel.0 != 0 {
+                            pos.0 += vel.0;
+                        }
+                    });
+            })
+            .with_system(Stage::PostTick, counter::<Changed<Pos>>(&log, "changed"))
+            .with_system(Stage::PostTick, counter::<Added<Pos>>(&log, "added"))
+            .with_system(Stage::Draw, counter::<Changed<Pos>>(&log, "drawn"));
+
+        let mut world = World::new();
+        for _ in 0..3 {
+            schedule.tick(&mut world, Duration::from_millis(16));
+            schedule.draw(&mut world);
+        }
+        let frame = [("changed", 1), ("added", 0), ("drawn", 1)];
+        let first = [("changed", 2), ("added", 2), ("drawn", 2)];
+        let expected: Vec<_> = [first, frame, frame].concat();
+        assert_eq!(*log.borrow(), expected);
+        assert_eq!(world.resource::<Time>().frame, 3);
+
+        // the velocity set to 0, nothing moves
+        world.query::<&mut Vel>().for_each(|mut vel| vel.0 = 0);
+        log.borrow_mut().clear();
+        schedule.tick(&mut world, Duration::from_millis(16));
+        assert_eq!(*log.borrow(), [("changed", 0), ("added", 0)]);
+    }
+
+    #[test]
+    fn commands_between_systems() {
+        let log = Log::default();
+        let mut schedule = Schedule::new()
+            .with_system(Stage::Tick, |world| {
+                world.commands().spawn((Pos(0),));
+                assert_eq!(world.query::<&Pos>().count(), world.len());
+            })
+            .with_system(Stage::Tick, counter::<Added<Pos>>(&log, "added"))
+            .with_system(Stage::Tick, |world| {
+                let entities = world.query::<Entity>().entities();
+                world.commands().despawn(entities[0]);
+            })
+            .with_system(Stage::PostTick, counter::<()>(&log, "left"));
+
+        let mut world = World::new();
+        schedule.tick(&mut world, Duration::from_millis(16));
+        schedule.tick(&mut world, Duration::from_millis(16));
+        let expected = [("added", 1), ("left", 0), ("added", 1), ("left", 0)];
+        assert_eq!(*log.borrow(), expected);
+        assert!(world.is_empty());
+    }
+}
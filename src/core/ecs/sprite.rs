@@ -0,0 +1,59 @@
+use super::Without;
+use super::World;
+use crate::math::Transform2D;
+use crate::render::queue::DrawParams;
+use crate::render::queue::RenderQueue;
+use crate::render::shader::IShader;
+use crate::render::sprite::ISprite;
+use crate::render::texture::ITexture;
+
+/// Draws its entity where its `Transform2D` puts it, see `draw_sprites`
+///
+/// ex: world.spawn((Transform2D::from_position(p), SpriteRenderer::new(PLAYER)))
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpriteRenderer {
+    /// the index of the sprite in the slice given to `draw_sprites`
+    pub sprite: usize,
+    pub params: DrawParams,
+}
+
+impl SpriteRenderer {
+    pub fn new(sprite: usize) -> Self {
+        Self {
+            sprite,
+            params: DrawParams::default(),
+        }
+    }
+
+    pub fn with_params(mut self, params: DrawParams) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+/// Skips drawing the entity, without removing its `SpriteRenderer`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hidden;
+
+/// queues every entity with a `Transform2D` and a `SpriteRenderer`, unless `Hidden`
+/// the queue is drawn on `RenderQueue::flush`
+/// entities whose sprite index is past `sprites` are skipped
+pub fn draw_sprites<'q, 'a, Sh, Tx, S>(
+    world: &World,
+    queue: &mut RenderQueue<'q>,
+    sprites: &'q [S],
+    shader: &'q Sh,
+) where
+    Sh: IShader,
+    Tx: ITexture<'a>,
+    S: ISprite<'a, Sh, Tx>,
+{
+    world
+        .query_filtered::<(&Transform2D, &SpriteRenderer), Without<Hidden>>()
+        .for_each(|(transform, renderer)| {
+            // an index past the sprites is skipped, ex: sprites not loaded yet
+            if let Some(sprite) = sprites.get(renderer.sprite) {
+                sprite.enqueue(queue, shader, transform.matrix(), renderer.params);
+            }
+        });
+}
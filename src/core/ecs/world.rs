@@ -0,0 +1,473 @@
+use std::any::type_name;
+use std::any::Any;
+use std::any::TypeId;
+use std::cell::Cell;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Commands;
+use super::Query;
+use super::QueryData;
+use super::QueryFilter;
+use crate::data::Handle;
+use crate::data::SlotMap;
+
+const NONE: u32 = u32::MAX;
+
+/// Identifies an entity of a `World`, stale once the entity is despawned
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity(Handle<()>);
+
+impl Entity {
+    pub fn to_bits(self) -> u64 {
+        self.0.to_bits()
+    }
+
+    pub fn from_bits(bits: u64) -> Option<Self> {
+        Handle::from_bits(bits).map(Self)
+    }
+}
+
+impl fmt::Debug for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Entity({}v{})", self.0.index(), self.0.generation())
+    }
+}
+
+/// Any type can be a component, as long as it doesn't borrow anything
+pub trait Component: 'static {}
+
+impl<T: 'static> Component for T {}
+
+/// Components added together, ex: `world.spawn((Transform2D::default(), Velocity(v)))`
+pub trait Bundle {
+    fn insert_into(self, world: &mut World, entity: Entity);
+}
+
+impl Bundle for () {
+    fn insert_into(self, _world: &mut World, _entity: Entity) {}
+}
+
+macro_rules! impl_bundle {
+    ($($c:ident),+) => {
+        impl<$($c: Component),+> Bundle for ($($c,)+) {
+            #[allow(non_snake_case)]
+            fn insert_into(self, world: &mut World, entity: Entity) {
+                let ($($c,)+) = self;
+                $(world.insert(entity, $c);)+
+            }
+        }
+    };
+}
+
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+
+/// The components of one type, in a sparse set: iterating them is iterating a Vec
+/// only reachable through the queries
+pub struct Storage<T> {
+    // the index in `entities` of each entity index, or NONE
+    sparse: Vec<u32>,
+    pub(super) entities: Vec<Entity>,
+    pub(super) added: Vec<u64>,
+    pub(super) changed: Vec<Cell<u64>>,
+    // borrowed by the queries, the rest only changes with a &mut World
+    pub(super) values: RefCell<Vec<T>>,
+}
+
+impl<T> Storage<T> {
+    fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            added: Vec::new(),
+            changed: Vec::new(),
+            values: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn dense(&self, entity: Entity) -> Option<usize> {
+        let &i = self.sparse.get(entity.0.index() as usize)?;
+        (i != NONE && self.entities[i as usize] == entity).then_some(i as usize)
+    }
+
+    fn insert(&mut self, entity: Entity, value: T, tick: u64) -> Option<T> {
+        if let Some(i) = self.dense(entity) {
+            self.changed[i].set(tick);
+            return Some(std::mem::replace(&mut self.values.get_mut()[i], value));
+        }
+
+        let index = entity.0.index() as usize;
+        if self.sparse.len() <= index {
+            self.sparse.resize(index + 1, NONE);
+        }
+        self.sparse[index] = self.entities.len() as u32;
+        self.entities.push(entity);
+        self.added.push(tick);
+        self.changed.push(Cell::new(tick));
+        self.values.get_mut().push(value);
+        None
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        let i = self.dense(entity)?;
+        self.sparse[entity.0.index() as usize] = NONE;
+        self.entities.swap_remove(i);
+        self.added.swap_remove(i);
+        self.changed.swap_remove(i);
+        if let Some(moved) = self.entities.get(i) {
+            self.sparse[moved.0.index() as usize] = i as u32;
+        }
+        Some(self.values.get_mut().swap_remove(i))
+    }
+}
+
+// the storages of every component type, without their type
+trait AnyStorage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for Storage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The entities, their components and the resources of a game
+///
+/// components are plain structs, stored by type, and found with queries
+/// resources are the single values shared by the systems, ex: the score or the input state
+///
+/// ex:
+/// let mut world = World::new();
+/// let player = world.spawn((Transform2D::default(), Velocity(Vec2::new(10.0, 0.0))));
+/// world
+///     .query::<(&mut Transform2D, &Velocity)>()
+///     .for_each(|(mut transform, velocity)| transform.translate(velocity.0 * dt));
+pub struct World {
+    entities: SlotMap<()>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    commands: RefCell<Commands>,
+    // stamped on the components as they change, see `Changed`
+    tick: u64,
+    // the tick at which the running system last ran
+    last_run: u64,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            entities: SlotMap::new(),
+            storages: HashMap::new(),
+            resources: HashMap::new(),
+            commands: RefCell::new(Commands::new()),
+            tick: 1,
+            last_run: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        let entity = Entity(self.entities.insert(()));
+        bundle.insert_into(self, entity);
+        entity
+    }
+
+    /// removes the entity and all of its components, false if it was already gone
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if self.entities.remove(entity.0).is_none() {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.contains(entity.0)
+    }
+
+    /// how many entities are alive
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.handles().map(Entity)
+    }
+
+    /// adds or replaces a component of the entity, returns the replaced one
+    /// panics if the entity was despawned
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "Bad entity: {entity:?}");
+        let tick = self.tick;
+        self.storage_mut::<T>().insert(entity, component, tick)
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap()
+            .remove(entity)
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|s| s.dense(entity).is_some())
+    }
+
+    /// panics if the component is mutably borrowed by a running query
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        let storage = self.storage::<T>()?;
+        let i = storage.dense(entity)?;
+        Some(Ref::map(storage.values.borrow(), |v| &v[i]))
+    }
+
+    /// the component, marked as changed
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        let tick = self.tick;
+        let storage = self
+            .storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap();
+        let i = storage.dense(entity)?;
+        storage.changed[i].set(tick);
+        Some(&mut storage.values.get_mut()[i])
+    }
+
+    /// the components given by `Q`, of every entity that has them and matches no filter
+    /// ex: world.query::<(Entity, &Health)>()
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        Query::new(self)
+    }
+
+    /// like `query`, with only the entities matching the filters
+    /// ex: world.query_filtered::<&mut Transform2D, (With<Player>, Without<Frozen>)>()
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(&self) -> Query<'_, Q, F> {
+        Query::new(self)
+    }
+
+    /// adds or replaces a resource
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.resources
+            .insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)));
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        let resource = self.resources.remove(&TypeId::of::<R>())?;
+        Some(*resource.into_inner().downcast::<R>().unwrap())
+    }
+
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    /// panics if there is no such resource, or if it is mutably borrowed
+    pub fn resource<R: 'static>(&self) -> Ref<'_, R> {
+        self.try_resource()
+            .unwrap_or_else(|| panic!("Bad resource: {}", type_name::<R>()))
+    }
+
+    /// panics if there is no such resource, or if it is already borrowed
+    pub fn resource_mut<R: 'static>(&self) -> RefMut<'_, R> {
+        self.try_resource_mut()
+            .unwrap_or_else(|| panic!("Bad resource: {}", type_name::<R>()))
+    }
+
+    pub fn try_resource<R: 'static>(&self) -> Option<Ref<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?;
+        Some(Ref::map(resource.borrow(), |r| r.downcast_ref().unwrap()))
+    }
+
+    pub fn try_resource_mut<R: 'static>(&self) -> Option<RefMut<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?;
+        Some(RefMut::map(resource.borrow_mut(), |r| {
+            r.downcast_mut().unwrap()
+        }))
+    }
+
+    /// changes to make once nothing is borrowed, ex: despawning entities found by a query
+    /// applied after each system of a `Schedule`, or by `apply_commands`
+    pub fn commands(&self) -> RefMut<'_, Commands> {
+        self.commands.borrow_mut()
+    }
+
+    pub fn apply_commands(&mut self) {
+        // commands may queue more commands
+        loop {
+            let commands = std::mem::take(self.commands.get_mut());
+            if commands.is_empty() {
+                return;
+            }
+            commands.apply(self);
+        }
+    }
+
+    /// starts a new round of change detection: `Changed` and `Added` only match what changes after this
+    /// done by `Schedule` for each system, only needed when querying outside of one
+    pub fn clear_trackers(&mut self) {
+        self.last_run = self.tick;
+        self.tick += 1;
+    }
+
+    pub(super) fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub(super) fn last_run(&self) -> u64 {
+        self.last_run
+    }
+
+    // runs a system, with the changes since `last_run` detected, returns the tick it ran at
+    pub(super) fn run_system(&mut self, last_run: u64, f: impl FnOnce(&mut World)) -> u64 {
+        self.last_run = last_run;
+        f(self);
+        self.apply_commands();
+        let ran_at = self.tick;
+        self.tick += 1;
+        ran_at
+    }
+
+    pub(super) fn storage<T: Component>(&self) -> Option<&Storage<T>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        Some(storage.as_any().downcast_ref().unwrap())
+    }
+
+    fn storage_mut<T: Component>(&mut self) -> &mut Storage<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(i32);
+    struct Player;
+
+    fn values(world: &World) -> Vec<(Entity, i32)> {
+        let mut ret = Vec::new();
+        world
+            .query::<(Entity, &Health)>()
+            .for_each(|(e, h)| ret.push((e, h.0)));
+        ret.sort();
+        ret
+    }
+
+    #[test]
+    fn despawn_moves_the_last_component() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..4).map(|i| world.spawn((Health(i),))).collect();
+        world.insert(entities[3], Player);
+
+        // the last value is moved into the removed one's place
+        assert!(world.despawn(entities[1]));
+        let expected = [(entities[0], 0), (entities[2], 2), (entities[3], 3)];
+        assert_eq!(values(&world), expected);
+        assert_eq!(*world.get::<Health>(entities[3]).unwrap(), Health(3));
+        assert!(world.has::<Player>(entities[3]));
+
+        assert_eq!(world.remove::<Health>(entities[0]), Some(Health(0)));
+        assert_eq!(world.get_mut::<Health>(entities[3]), Some(&mut Health(3)));
+        assert_eq!(values(&world), [(entities[2], 2), (entities[3], 3)]);
+        assert_eq!(world.len(), 3);
+    }
+
+    #[test]
+    fn stale_entities() {
+        let mut world = World::new();
+        let old = world.spawn((Health(1),));
+        assert!(world.despawn(old));
+        assert!(!world.despawn(old));
+
+        // the slot is reused, the old entity matches nothing
+        let new = world.spawn((Health(2),));
+        assert_eq!(Entity::from_bits(new.to_bits()), Some(new));
+        assert_ne!(old, new);
+        assert!(!world.is_alive(old));
+        assert!(world.get::<Health>(old).is_none());
+        assert!(world.get_mut::<Health>(old).is_none());
+        assert!(!world.has::<Health>(old));
+        assert_eq!(world.query::<&Health>().get(old, |h| h.0), None);
+        assert_eq!(world.query::<&Health>().get(new, |h| h.0), Some(2));
+        assert_eq!(values(&world), [(new, 2)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad entity")]
+    fn insert_on_stale_entity() {
+        let mut world = World::new();
+        let entity = world.spawn(());
+        world.despawn(entity);
+        world.insert(entity, Player);
+    }
+
+    #[test]
+    fn commands() {
+        let mut world = World::new();
+        let a = world.spawn((Health(0),));
+        let b = world.spawn((Health(5),));
+        world
+            .query::<(Entity, &Health)>()
+            .for_each(|(entity, health)| {
+                if health.0 <= 0 {
+                    world.commands().despawn(entity);
+                    world.commands().spawn((Health(10),));
+                }
+            });
+        assert_eq!(world.len(), 2);
+
+        world.commands().insert(a, Player);
+        world.commands().insert(b, Player);
+        world
+            .commands()
+            .add(|world| world.commands().insert_resource(7u32));
+        world.apply_commands();
+        assert!(!world.is_alive(a));
+        assert!(world.has::<Player>(b));
+        assert_eq!(*world.resource::<u32>(), 7);
+        let mut healths: Vec<i32> = values(&world).into_iter().map(|v| v.1).collect();
+        healths.sort();
+        assert_eq!(healths, [5, 10]);
+    }
+}
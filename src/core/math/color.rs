@@ -0,0 +1,377 @@
+use std::fmt::Display;
+use std::ops::Mul;
+use std::str::FromStr;
+
+use super::Vec3;
+use super::Vec4;
+
+/// An RGBA color
+/// the components are stored in linear space, which is what shaders and blending work in
+/// (the GL context encodes to sRGB when writing to the screen)
+///
+/// colors picked by people (hex codes, HSV, HSL, 8-bit values) are in sRGB space,
+/// so the matching constructors convert from sRGB
+/// ex: Color::from_hex("#ff8800").unwrap(), Color::from_hsv(30.0, 1.0, 1.0, 1.0)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+// a single component, sRGB <-> linear
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl Color {
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+    pub const RED: Self = Self::new(1.0, 0.0, 0.0, 1.0);
+    pub const GREEN: Self = Self::new(0.0, 1.0, 0.0, 1.0);
+    pub const BLUE: Self = Self::new(0.0, 0.0, 1.0, 1.0);
+
+    /// from linear components
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// from sRGB components, alpha is always linear
+    pub fn from_srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+    }
+
+    /// the sRGB components, alpha is unchanged
+    pub fn to_srgb(self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        ]
+    }
+
+    pub fn from_srgb8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let [r, g, b, a] = [r, g, b, a].map(|c| c as f32 / 255.0);
+        Self::from_srgb(r, g, b, a)
+    }
+
+    pub fn to_srgb8(self) -> [u8; 4] {
+        self.to_srgb()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// parses `#rrggbb` or `#rrggbbaa` (the `#` is optional), in sRGB space
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        // from_str_radix alone would also take a sign
+        if !matches!(digits.len(), 6 | 8) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("Bad color: {hex}"));
+        }
+
+        let mut c = [255u8; 4];
+        for (i, c) in c.iter_mut().enumerate().take(digits.len() / 2) {
+            *c = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+        }
+
+        Ok(Self::from_srgb8(c[0], c[1], c[2], c[3]))
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` if the color isn't opaque
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self.to_srgb8();
+        if a == 255 {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+
+    /// hue in degrees, saturation and value in [0, 1]
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma, alpha)
+    }
+
+    /// (hue in degrees, saturation, value)
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, min, max) = self.hue();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// hue in degrees, saturation and lightness in [0, 1]
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.0, alpha)
+    }
+
+    /// (hue in degrees, saturation, lightness)
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, min, max) = self.hue();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max > min {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// (L, a, b) in the Oklab perceptual color space
+    pub fn to_oklab(self) -> Vec3 {
+        let (r, g, b) = (self.r, self.g, self.b);
+        let l = (0.41222147 * r + 0.53633254 * g + 0.05144599 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+        let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+        Vec3::new(
+            0.21045426 * l + 0.7936178 * m - 0.00407205 * s,
+            1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            0.02590404 * l + 0.78277177 * m - 0.80867577 * s,
+        )
+    }
+
+    pub fn from_oklab(lab: Vec3, alpha: f32) -> Self {
+        let l = (lab.x + 0.39633778 * lab.y + 0.21580376 * lab.z).powi(3);
+        let m = (lab.x - 0.10556135 * lab.y - 0.06385417 * lab.z).powi(3);
+        let s = (lab.x - 0.08948418 * lab.y - 1.2914855 * lab.z).powi(3);
+
+        Self::new(
+            4.0767417 * l - 3.3077116 * m + 0.23096993 * s,
+            -1.268438 * l + 2.6097574 * m - 0.3413194 * s,
+            -0.00419609 * l - 0.7034186 * m + 1.7076147 * s,
+            alpha,
+        )
+    }
+
+    /// the color with its rgb multiplied by its alpha
+    pub fn premultiply(self) -> Self {
+        Self::new(self.r * self.a, self.g * self.a, self.b * self.a, self.a)
+    }
+
+    /// undoes `premultiply`, a transparent color stays transparent black
+    pub fn unpremultiply(self) -> Self {
+        if self.a <= 0.0 {
+            return Self::TRANSPARENT;
+        }
+        Self::new(self.r / self.a, self.g / self.a, self.b / self.a, self.a)
+    }
+
+    /// draws `self` over `dst`, like the default blend mode of the renderer
+    pub fn over(self, dst: Self) -> Self {
+        let a = self.a + dst.a * (1.0 - self.a);
+        let src = self.premultiply();
+        let dst = dst.premultiply();
+        Self::new(
+            src.r + dst.r * (1.0 - self.a),
+            src.g + dst.g * (1.0 - self.a),
+            src.b + dst.b * (1.0 - self.a),
+            a,
+        )
+        .unpremultiply()
+    }
+
+    /// interpolates every component in linear space
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Vec4::from(self).lerp(other.into(), t).into()
+    }
+
+    /// interpolates in Oklab, giving an even looking transition
+    /// ex: red to green goes through yellow instead of a muddy brown
+    pub fn mix(self, other: Self, t: f32) -> Self {
+        let lab = self.to_oklab().lerp(other.to_oklab(), t);
+        Self::from_oklab(lab, self.a + t * (other.a - self.a))
+    }
+
+    // the sRGB color with the given hue, chroma and added lightness
+    fn from_hue(hue: f32, chroma: f32, m: f32, alpha: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::from_srgb(r + m, g + m, b + m, alpha)
+    }
+
+    // (hue in degrees, min component, max component), in sRGB space
+    fn hue(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_srgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta <= 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, min, max)
+    }
+}
+
+/// A smooth transition between colors, interpolated in Oklab
+///
+/// ex: a sunset
+/// let sky = Gradient::new(vec![(0.0, Color::from_hex("#1e3c72")?), (1.0, Color::from_hex("#ff7e5f")?)]);
+/// let c = sky.sample(0.25);
+#[derive(Debug, Clone, Default)]
+pub struct Gradient {
+    // sorted by position
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// `stops` are (position, color) pairs, in any order
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    pub fn add_stop(&mut self, position: f32, color: Color) {
+        let idx = self.stops.partition_point(|s| s.0 <= position);
+        self.stops.insert(idx, (position, color));
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// positions before the first or after the last stop get the color of that stop
+    /// an empty gradient is transparent
+    pub fn sample(&self, position: f32) -> Color {
+        let idx = self.stops.partition_point(|s| s.0 <= position);
+        match (self.stops.get(idx.wrapping_sub(1)), self.stops.get(idx)) {
+            (Some(&(p0, c0)), Some(&(p1, c1))) => c0.mix(c1, (position - p0) / (p1 - p0)),
+            (Some(&(_, c)), None) | (None, Some(&(_, c))) => c,
+            (None, None) => Color::TRANSPARENT,
+        }
+    }
+}
+
+/// component-wise, for tinting
+impl Mul for Color {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.r * rhs.r,
+            self.g * rhs.g,
+            self.b * rhs.b,
+            self.a * rhs.a,
+        )
+    }
+}
+
+impl From<Color> for Vec4 {
+    fn from(value: Color) -> Self {
+        Vec4::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Vec4> for Color {
+    fn from(value: Vec4) -> Self {
+        Self::new(value.x, value.y, value.z, value.w)
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        for hex in ["#000000", "#ffffff", "#ff8800", "#12345678", "#abcdef00"] {
+            assert_eq!(Color::from_hex(hex).unwrap().to_hex(), hex);
+        }
+        for v in 0..=255u8 {
+            let hex = format!("#{v:02x}{:02x}{:02x}{:02x}", 255 - v, v / 2, v.min(254));
+            assert_eq!(Color::from_hex(&hex).unwrap().to_hex(), hex);
+        }
+
+        assert_eq!(Color::from_hex("FF8800").unwrap().to_hex(), "#ff8800");
+        assert_eq!(Color::from_hex("#ffffffff").unwrap().to_hex(), "#ffffff");
+        assert_eq!(Color::from_hex("#ffffff").unwrap(), Color::WHITE);
+        assert_eq!(Color::from_hex("#00000000").unwrap(), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn bad_hex() {
+        let bad = [
+            "",
+            "#",
+            "#fff",
+            "#fffff",
+            "#fffffff",
+            "#fffffffff",
+            "##ffffff",
+            "#+fffff",
+            "#-fffff",
+            "#ff+fff",
+            "#fffff+ff",
+            "#ff 800",
+            "#gggggg",
+            "#ffé00",
+            " ffffff",
+        ];
+        for hex in bad {
+            assert_eq!(
+                Color::from_hex(hex),
+                Err(format!("Bad color: {hex}")),
+                "{hex}"
+            );
+        }
+    }
+}
@@ -0,0 +1,649 @@
+use std::time::Duration;
+
+use super::Segment;
+use super::Shape;
+use super::Vec2;
+use crate::time::Tickable;
+
+/// A smooth curve, parameterized by `t` from 0 (start) to 1 (end)
+///
+/// note that `t` doesn't advance at a constant speed along the curve,
+/// use `ArcLength` or `PathFollower` for constant speed motion
+pub trait Spline {
+    fn point(&self, t: f32) -> Vec2;
+
+    /// the derivative of `point`, its length is the speed at `t`
+    fn derivative(&self, t: f32) -> Vec2;
+
+    /// the number of polynomial pieces, used to pick sample counts
+    fn pieces(&self) -> usize {
+        1
+    }
+
+    /// the unit direction of travel, zero where the curve stops
+    fn tangent(&self, t: f32) -> Vec2 {
+        let d = self.derivative(t);
+        if d.is_zero() {
+            Vec2::zero()
+        } else {
+            d.normalize()
+        }
+    }
+
+    /// approximates the curve with straight lines, for drawing
+    /// every point of the curve is at most `tolerance` away from the lines
+    fn to_polyline(&self, tolerance: f32) -> Vec<Vec2> {
+        // start with a few samples per piece, so that s-bends aren't mistaken for lines
+        let n = 4 * self.pieces();
+        let mut ret = vec![self.point(0.0)];
+        for i in 0..n {
+            let (a, b) = (i as f32 / n as f32, (i + 1) as f32 / n as f32);
+            subdivide(
+                self,
+                a,
+                b,
+                self.point(a),
+                self.point(b),
+                tolerance,
+                0,
+                &mut ret,
+            );
+        }
+        ret
+    }
+
+    /// (t, point) of the point on the curve closest to `point`
+    fn closest_point(&self, point: Vec2) -> (f32, Vec2) {
+        let n = 16 * self.pieces();
+        let distance = |t: f32| self.point(t).distance(point);
+
+        let mut best = (0..=n)
+            .map(|i| i as f32 / n as f32)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap();
+
+        // narrow down around the best sample
+        let mut step = 1.0 / n as f32;
+        for _ in 0..24 {
+            step /= 2.0;
+            for t in [best - step, best + step] {
+                let t = t.clamp(0.0, 1.0);
+                if distance(t) < distance(best) {
+                    best = t;
+                }
+            }
+        }
+
+        (best, self.point(best))
+    }
+
+    fn length(&self) -> f32 {
+        ArcLength::new(self, 64 * self.pieces()).length()
+    }
+}
+
+const MAX_DEPTH: u32 = 16;
+
+// adds the end of [a, b] and any points needed in between
+#[allow(clippy::too_many_arguments)]
+fn subdivide<S: Spline + ?Sized>(
+    spline: &S,
+    a: f32,
+    b: f32,
+    pa: Vec2,
+    pb: Vec2,
+    tolerance: f32,
+    depth: u32,
+    out: &mut Vec<Vec2>,
+) {
+    let m = 0.5 * (a + b);
+    let pm = spline.point(m);
+    if depth < MAX_DEPTH && Segment::new(pa, pb).distance_to_point(pm) > tolerance {
+        subdivide(spline, a, m, pa, pm, tolerance, depth + 1, out);
+        subdivide(spline, m, b, pm, pb, tolerance, depth + 1, out);
+    } else {
+        out.push(pb);
+    }
+}
+
+/// A Bezier curve with one control point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadraticBezier {
+    pub start: Vec2,
+    pub control: Vec2,
+    pub end: Vec2,
+}
+
+impl QuadraticBezier {
+    pub fn new(start: Vec2, control: Vec2, end: Vec2) -> Self {
+        Self {
+            start,
+            control,
+            end,
+        }
+    }
+
+    /// the two halves of the curve, split at `t`
+    pub fn split(&self, t: f32) -> (Self, Self) {
+        let a = self.start.lerp(self.control, t);
+        let b = self.control.lerp(self.end, t);
+        let m = a.lerp(b, t);
+        (Self::new(self.start, a, m), Self::new(m, b, self.end))
+    }
+
+    /// the same curve as a cubic
+    pub fn to_cubic(&self) -> CubicBezier {
+        CubicBezier::new(
+            self.start,
+            self.start.lerp(self.control, 2.0 / 3.0),
+            self.end.lerp(self.control, 2.0 / 3.0),
+            self.end,
+        )
+    }
+}
+
+impl Spline for QuadraticBezier {
+    fn point(&self, t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        s * s * self.start + 2.0 * s * t * self.control + t * t * self.end
+    }
+
+    fn derivative(&self, t: f32) -> Vec2 {
+        2.0 * (1.0 - t) * (self.control - self.start) + 2.0 * t * (self.end - self.control)
+    }
+}
+
+/// A Bezier curve with two control points
+/// the curve leaves `start` towards `c0`, and arrives at `end` coming from `c1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CubicBezier {
+    pub start: Vec2,
+    pub c0: Vec2,
+    pub c1: Vec2,
+    pub end: Vec2,
+}
+
+impl CubicBezier {
+    pub fn new(start: Vec2, c0: Vec2, c1: Vec2, end: Vec2) -> Self {
+        Self { start, c0, c1, end }
+    }
+
+    /// the two halves of the curve, split at `t`
+    pub fn split(&self, t: f32) -> (Self, Self) {
+        let a = self.start.lerp(self.c0, t);
+        let b = self.c0.lerp(self.c1, t);
+        let c = self.c1.lerp(self.end, t);
+        let ab = a.lerp(b, t);
+        let bc = b.lerp(c, t);
+        let m = ab.lerp(bc, t);
+        (
+            Self::new(self.start, a, ab, m),
+            Self::new(m, bc, c, self.end),
+        )
+    }
+
+    pub fn second_derivative(&self, t: f32) -> Vec2 {
+        let a = self.c1 - 2.0 * self.c0 + self.start;
+        let b = self.end - 2.0 * self.c1 + self.c0;
+        6.0 * ((1.0 - t) * a + t * b)
+    }
+}
+
+impl Spline for CubicBezier {
+    fn point(&self, t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        s * s * s * self.start
+            + 3.0 * s * s * t * self.c0
+            + 3.0 * s * t * t * self.c1
+            + t * t * t * self.end
+    }
+
+    fn derivative(&self, t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        3.0 * s * s * (self.c0 - self.start)
+            + 6.0 * s * t * (self.c1 - self.c0)
+            + 3.0 * t * t * (self.end - self.c1)
+    }
+}
+
+// the piece containing `t` out of `count`, and the position within it
+fn piece(t: f32, count: usize) -> (usize, f32) {
+    let x = t.clamp(0.0, 1.0) * count as f32;
+    let idx = (x as usize).min(count - 1);
+    (idx, x - idx as f32)
+}
+
+// the 4 points around piece `i`, starting at `first`
+// open curves repeat their end points, closed ones wrap around
+fn window(points: &[Vec2], closed: bool, first: isize) -> [Vec2; 4] {
+    let n = points.len() as isize;
+    std::array::from_fn(|k| {
+        let i = first + k as isize;
+        let i = if closed {
+            i.rem_euclid(n)
+        } else {
+            i.clamp(0, n - 1)
+        };
+        points[i as usize]
+    })
+}
+
+/// A smooth curve going through every point
+/// ex: a patrol route through waypoints
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CatmullRom {
+    pub points: Vec<Vec2>,
+    /// loops back from the last point to the first
+    pub closed: bool,
+}
+
+impl CatmullRom {
+    pub fn new(points: Vec<Vec2>, closed: bool) -> Self {
+        Self { points, closed }
+    }
+
+    fn window(&self, t: f32) -> ([Vec2; 4], f32) {
+        let (i, u) = piece(t, self.pieces());
+        (window(&self.points, self.closed, i as isize - 1), u)
+    }
+}
+
+impl Spline for CatmullRom {
+    fn point(&self, t: f32) -> Vec2 {
+        if self.points.len() < 2 {
+            return self.points.first().copied().unwrap_or_default();
+        }
+
+        let ([p0, p1, p2, p3], u) = self.window(t);
+        0.5 * (2.0 * p1
+            + u * (p2 - p0)
+            + u * u * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3)
+            + u * u * u * (3.0 * p1 - p0 - 3.0 * p2 + p3))
+    }
+
+    fn derivative(&self, t: f32) -> Vec2 {
+        if self.points.len() < 2 {
+            return Vec2::zero();
+        }
+
+        let ([p0, p1, p2, p3], u) = self.window(t);
+        let d = 0.5
+            * ((p2 - p0)
+                + 2.0 * u * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3)
+                + 3.0 * u * u * (3.0 * p1 - p0 - 3.0 * p2 + p3));
+        // every piece covers 1 / pieces of t
+        self.pieces() as f32 * d
+    }
+
+    fn pieces(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 1,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+}
+
+/// A smooth curve pulled towards every point, without going through them
+/// (apart from the ends of open curves)
+/// smoother than `CatmullRom`, ex: camera paths
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BSpline {
+    pub points: Vec<Vec2>,
+    /// loops back from the last point to the first
+    pub closed: bool,
+}
+
+impl BSpline {
+    pub fn new(points: Vec<Vec2>, closed: bool) -> Self {
+        Self { points, closed }
+    }
+
+    fn window(&self, t: f32) -> ([Vec2; 4], f32) {
+        let (i, u) = piece(t, self.pieces());
+        // open curves triple their ends so that they reach them
+        let first = if self.closed {
+            i as isize - 1
+        } else {
+            i as isize - 2
+        };
+        (window(&self.points, self.closed, first), u)
+    }
+}
+
+impl Spline for BSpline {
+    fn point(&self, t: f32) -> Vec2 {
+        if self.points.len() < 2 {
+            return self.points.first().copied().unwrap_or_default();
+        }
+
+        let ([p0, p1, p2, p3], u) = self.window(t);
+        let (u2, u3) = (u * u, u * u * u);
+        (1.0 / 6.0)
+            * ((1.0 - 3.0 * u + 3.0 * u2 - u3) * p0
+                + (4.0 - 6.0 * u2 + 3.0 * u3) * p1
+                + (1.0 + 3.0 * u + 3.0 * u2 - 3.0 * u3) * p2
+                + u3 * p3)
+    }
+
+    fn derivative(&self, t: f32) -> Vec2 {
+        if self.points.len() < 2 {
+            return Vec2::zero();
+        }
+
+        let ([p0, p1, p2, p3], u) = self.window(t);
+        let u2 = u * u;
+        let d = 0.5
+            * ((-1.0 + 2.0 * u - u2) * p0
+                + (3.0 * u2 - 4.0 * u) * p1
+                + (1.0 + 2.0 * u - 3.0 * u2) * p2
+                + u2 * p3);
+        self.pieces() as f32 * d
+    }
+
+    fn pieces(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 1,
+            n if self.closed => n,
+            n => n + 1,
+        }
+    }
+}
+
+/// Converts between distance along a curve and its `t` parameter
+/// built once from samples of the curve, more samples are more precise
+#[derive(Debug, Clone)]
+pub struct ArcLength {
+    // the distance travelled at t = i / (len - 1)
+    distances: Vec<f32>,
+}
+
+impl ArcLength {
+    pub fn new<S: Spline + ?Sized>(spline: &S, samples: usize) -> Self {
+        let samples = samples.max(1);
+        let mut distances = Vec::with_capacity(samples + 1);
+        let mut total = 0.0;
+        let mut prev = spline.point(0.0);
+        distances.push(0.0);
+
+        for i in 1..=samples {
+            let p = spline.point(i as f32 / samples as f32);
+            total += prev.distance(p);
+            distances.push(total);
+            prev = p;
+        }
+
+        Self { distances }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    /// the `t` reached after travelling `distance` along the curve, clamped to the ends
+    pub fn t_at(&self, distance: f32) -> f32 {
+        let n = self.distances.len() - 1;
+        let i = self.distances.partition_point(|&d| d <= distance);
+        if i == 0 {
+            return 0.0;
+        }
+        if i > n {
+            return 1.0;
+        }
+
+        let (d0, d1) = (self.distances[i - 1], self.distances[i]);
+        let u = (distance - d0) / (d1 - d0);
+        (i - 1) as f32 / n as f32 + u / n as f32
+    }
+
+    /// the distance travelled along the curve when reaching `t`
+    pub fn distance_at(&self, t: f32) -> f32 {
+        let n = self.distances.len() - 1;
+        let (i, u) = piece(t, n);
+        self.distances[i] + u * (self.distances[i + 1] - self.distances[i])
+    }
+}
+
+/// Moves along a curve at a constant speed
+///
+/// ex: an enemy on a patrol route
+/// let mut follower = PathFollower::new(route, 3.0).looping(true);
+/// // every frame
+/// follower.tick(dt);
+/// enemy.set_position(follower.position());
+pub struct PathFollower<S: Spline> {
+    spline: S,
+    arc: ArcLength,
+    distance: f32,
+    /// units per second, negative to go backwards
+    pub speed: f32,
+    looping: bool,
+}
+
+impl<S: Spline> PathFollower<S> {
+    pub fn new(spline: S, speed: f32) -> Self {
+        let arc = ArcLength::new(&spline, 64 * spline.pieces());
+        Self {
+            spline,
+            arc,
+            distance: 0.0,
+            speed,
+            looping: false,
+        }
+    }
+
+    /// starts over at the other end instead of stopping
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn spline(&self) -> &S {
+        &self.spline
+    }
+
+    /// the distance travelled from the start
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn set_distance(&mut self, distance: f32) {
+        let length = self.arc.length();
+        self.distance = if self.looping && length > 0.0 {
+            distance.rem_euclid(length)
+        } else {
+            distance.clamp(0.0, length)
+        };
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.spline.point(self.t())
+    }
+
+    /// the unit direction of travel
+    pub fn direction(&self) -> Vec2 {
+        self.spline.tangent(self.t()) * self.speed.signum()
+    }
+
+    /// whether a non-looping follower reached the end it's heading to
+    pub fn is_finished(&self) -> bool {
+        !self.looping
+            && ((self.speed > 0.0 && self.distance >= self.arc.length())
+                || (self.speed < 0.0 && self.distance <= 0.0))
+    }
+
+    fn t(&self) -> f32 {
+        self.arc.t_at(self.distance)
+    }
+}
+
+impl<S: Spline> Tickable for PathFollower<S> {
+    fn tick(&mut self, dt: Duration) {
+        self.set_distance(self.distance + self.speed * dt.as_secs_f32());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2::new(x, y)
+    }
+
+    fn close(a: Vec2, b: Vec2, tolerance: f32) -> bool {
+        a.distance(b) <= tolerance
+    }
+
+    fn splines() -> Vec<Box<dyn Spline>> {
+        let points = vec![v(0.0, 0.0), v(4.0, 1.0), v(5.0, 6.0), v(-2.0, 3.0)];
+        vec![
+            Box::new(QuadraticBezier::new(v(0.0, 0.0), v(5.0, 8.0), v(10.0, 0.0))),
+            Box::new(CubicBezier::new(
+                v(0.0, 0.0),
+                v(0.0, 5.0),
+                v(10.0, -5.0),
+                v(10.0, 0.0),
+            )),
+            Box::new(CatmullRom::new(points.clone(), false)),
+            Box::new(CatmullRom::new(points.clone(), true)),
+            Box::new(BSpline::new(points.clone(), false)),
+            Box::new(BSpline::new(points, true)),
+        ]
+    }
+
+    #[test]
+    fn derivatives() {
+        for (i, s) in splines().iter().enumerate() {
+            // away from the piece boundaries, where the second derivative jumps
+            for k in 0..50 {
+                let t = (k as f32 + 0.37) / 50.0;
+                let h = 1e-3;
+                let numeric = (s.point(t + h) - s.point(t - h)) / (2.0 * h);
+                let d = s.derivative(t);
+                assert!(close(numeric, d, 0.01 * d.len().max(1.0)), "{i} {t}");
+            }
+        }
+    }
+
+    #[test]
+    fn piece_boundaries() {
+        // through every point, with the same direction on both sides
+        let points = vec![v(0.0, 0.0), v(4.0, 1.0), v(5.0, 6.0), v(-2.0, 3.0)];
+        for closed in [false, true] {
+            let cr = CatmullRom::new(points.clone(), closed);
+            let bs = BSpline::new(points.clone(), closed);
+            for s in [&cr as &dyn Spline, &bs] {
+                let n = s.pieces();
+                for k in 1..n {
+                    let t = k as f32 / n as f32;
+                    let (before, after) = (s.derivative(t - 1e-6), s.derivative(t + 1e-6));
+                    assert!(close(before, after, 0.01 * before.len()), "{closed} {k}");
+                    assert!(close(s.point(t - 1e-6), s.point(t + 1e-6), 1e-3));
+                }
+            }
+            for (k, &p) in points.iter().enumerate() {
+                assert!(close(cr.point(k as f32 / cr.pieces() as f32), p, 1e-5));
+            }
+        }
+
+        let bs = BSpline::new(points.clone(), false);
+        assert!(close(bs.point(0.0), points[0], 1e-5));
+        assert!(close(bs.point(1.0), points[3], 1e-5));
+        let closed = BSpline::new(points.clone(), true);
+        assert!(close(closed.point(0.0), closed.point(1.0), 1e-5));
+    }
+
+    #[test]
+    fn polylines_and_closest_points() {
+        for (i, s) in splines().iter().enumerate() {
+            let line = s.to_polyline(0.01);
+            assert!(close(line[0], s.point(0.0), 1e-6));
+            assert!(close(*line.last().unwrap(), s.point(1.0), 1e-6));
+            for k in 0..=500 {
+                let p = s.point(k as f32 / 500.0);
+                let d = line
+                    .windows(2)
+                    .map(|w| Segment::new(w[0], w[1]).distance_to_point(p))
+                    .fold(f32::INFINITY, f32::min);
+                assert!(d <= 0.011, "{i} {k} {d}");
+            }
+
+            for k in 0..10 {
+                let t = (k as f32 + 0.5) / 10.0;
+                let (found, p) = s.closest_point(s.point(t) + 0.01 * s.tangent(t).perp());
+                assert!(close(p, s.point(found), 1e-6));
+                assert!(p.distance(s.point(t)) < 0.02, "{i} {t} {found}");
+            }
+        }
+    }
+
+    #[test]
+    fn arc_length() {
+        // a straight line, with the control point near the start so t is uneven
+        let line = QuadraticBezier::new(v(0.0, 0.0), v(1.0, 0.0), v(10.0, 0.0));
+        let arc = ArcLength::new(&line, 256);
+        assert!((arc.length() - 10.0).abs() < 1e-4);
+        assert!((line.length() - 10.0).abs() < 1e-4);
+        for d in [0.0, 0.5, 2.5, 5.0, 7.25, 10.0] {
+            let t = arc.t_at(d);
+            assert!((line.point(t).x - d).abs() < 0.01, "{d} {t}");
+            assert!((arc.distance_at(t) - d).abs() < 1e-3);
+        }
+        assert!(arc.t_at(5.0) > 0.6);
+        assert_eq!(arc.t_at(-1.0), 0.0);
+        assert_eq!(arc.t_at(11.0), 1.0);
+
+        // a quarter circle of radius 10
+        const K: f32 = 0.552_284_8;
+        let quarter = CubicBezier::new(
+            v(10.0, 0.0),
+            v(10.0, 10.0 * K),
+            v(10.0 * K, 10.0),
+            v(0.0, 10.0),
+        );
+        let length = 10.0 * std::f32::consts::FRAC_PI_2;
+        assert!((quarter.length() - length).abs() < 0.01);
+    }
+
+    #[test]
+    fn path_follower() {
+        // a closed square, each side a piece of 10
+        let square = vec![v(0.0, 0.0), v(10.0, 0.0), v(10.0, 10.0), v(0.0, 10.0)];
+        let route = CatmullRom::new(square.clone(), true);
+        let mut follower = PathFollower::new(route, 4.0).looping(true);
+        let length = follower.arc.length();
+
+        // the corners at a quarter of the way each
+        for (k, &corner) in square.iter().enumerate() {
+            follower.set_distance(k as f32 * length / 4.0);
+            assert!(close(follower.position(), corner, 0.05), "{k}");
+            assert!(follower.direction().is_unit());
+        }
+
+        // constant speed, across the corners
+        follower.set_distance(0.0);
+        let mut prev = follower.position();
+        for _ in 0..200 {
+            follower.tick(Duration::from_millis(50));
+            let p = follower.position();
+            let step = p.distance(prev);
+            assert!(step > 0.19 && step < 0.201, "{step}");
+            prev = p;
+        }
+        assert!((follower.distance() - (40.0f32).rem_euclid(length)).abs() < 1e-3);
+
+        // stops at the ends when not looping
+        let route = CatmullRom::new(square.clone(), false);
+        let mut follower = PathFollower::new(route, 100.0);
+        follower.tick(Duration::from_secs(1));
+        assert!(follower.is_finished());
+        assert!(close(follower.position(), square[3], 1e-4));
+        follower.speed = -100.0;
+        assert!(!follower.is_finished());
+        follower.tick(Duration::from_secs(1));
+        assert!(follower.is_finished());
+        assert!(close(follower.position(), square[0], 1e-4));
+        assert_eq!(follower.direction(), -follower.spline().tangent(0.0));
+    }
+}
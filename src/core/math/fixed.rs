@@ -0,0 +1,523 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Div;
+use std::ops::DivAssign;
+use std::ops::Mul;
+use std::ops::MulAssign;
+use std::ops::Neg;
+use std::ops::Rem;
+use std::ops::RemAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+use super::Float;
+use super::Scalar;
+use super::Signed;
+use super::Vector;
+
+/// A fixed point number with 32 integer and 32 fraction bits (Q32.32)
+///
+/// every operation is done with integer math, so results are bit-identical on every machine,
+/// which `f32` can't promise once sin, sqrt or compiler optimizations are involved
+/// ex: the simulation of a lockstep multiplayer game, or one replayed from its inputs
+///
+/// arithmetic wraps on overflow, in debug and release builds alike
+/// convert to `f32` types for rendering only, never feed them back into the simulation
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i64);
+
+// pi with 62 fraction bits, the other constants are derived from it
+const PI_Q62: i128 = 14488038916154245685;
+
+// sin over a quarter turn, QUARTER_STEPS + 1 entries
+const QUARTER_BITS: u32 = 10;
+const QUARTER_STEPS: usize = 1 << QUARTER_BITS;
+const SIN_TABLE: [i64; QUARTER_STEPS + 1] = sin_table();
+
+// atan(2^-i), for the CORDIC iterations of atan2
+const ATAN_TABLE: [i64; 34] = atan_table();
+
+impl Fixed {
+    pub const FRAC_BITS: u32 = 32;
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << 32);
+    pub const HALF: Self = Self(1 << 31);
+    pub const MIN: Self = Self(i64::MIN);
+    pub const MAX: Self = Self(i64::MAX);
+    /// the smallest positive value, 2^-32
+    pub const EPSILON: Self = Self(1);
+    pub const PI: Self = Self(round_shift(PI_Q62, 30) as i64);
+    pub const FRAC_PI_2: Self = Self(round_shift(PI_Q62, 31) as i64);
+    pub const TAU: Self = Self(round_shift(PI_Q62, 29) as i64);
+
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_int(value: i32) -> Self {
+        Self((value as i64) << 32)
+    }
+
+    /// the nearest fixed point number, saturating outside of the range
+    /// the same float always gives the same number, on every machine
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * (1u64 << 32) as f64).round() as i64)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / (1u64 << 32) as f64
+    }
+
+    /// the integer part, rounded towards negative infinity
+    pub fn to_int(self) -> i32 {
+        (self.0 >> 32) as i32
+    }
+
+    pub fn floor(self) -> Self {
+        Self(self.0 & !0xffff_ffff)
+    }
+
+    pub fn ceil(self) -> Self {
+        Self(self.0.wrapping_add(0xffff_ffff)).floor()
+    }
+
+    /// halfway cases round away from zero, like `f32::round`
+    pub fn round(self) -> Self {
+        if self.0 < 0 {
+            -(-self + Self::HALF).floor()
+        } else {
+            (self + Self::HALF).floor()
+        }
+    }
+
+    /// the part after the point, always positive
+    pub fn fract(self) -> Self {
+        Self(self.0 & 0xffff_ffff)
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.wrapping_abs())
+    }
+
+    pub fn signum(self) -> Self {
+        Self::from_int(self.0.signum() as i32)
+    }
+
+    /// panics if the number is negative
+    pub fn sqrt(self) -> Self {
+        assert!(self.0 >= 0, "Negative square root");
+        // sqrt(bits / 2^32) * 2^32 = sqrt(bits * 2^32)
+        Self((((self.0 as u128) << 32).isqrt()) as i64)
+    }
+
+    /// `self` in radians, from a lookup table
+    /// accurate to about 3e-7
+    pub fn sin(self) -> Self {
+        sin_turns(self.turns())
+    }
+
+    pub fn cos(self) -> Self {
+        sin_turns(self.turns().wrapping_add(1 << 30))
+    }
+
+    pub fn sin_cos(self) -> (Self, Self) {
+        let turns = self.turns();
+        (sin_turns(turns), sin_turns(turns.wrapping_add(1 << 30)))
+    }
+
+    /// the angle of (other, self) in radians, in [-pi, pi], like `f32::atan2`
+    pub fn atan2(self, other: Self) -> Self {
+        let (mut x, mut y) = (other.0 as i128, self.0 as i128);
+        // exact on the axes, the iterations would be off by a bit
+        if y == 0 {
+            return if x < 0 { Self::PI } else { Self::ZERO };
+        }
+        if x == 0 {
+            return if y > 0 {
+                Self::FRAC_PI_2
+            } else {
+                -Self::FRAC_PI_2
+            };
+        }
+
+        // scaled up for precision, with room for the growth of the iterations
+        let bits = 128 - x.abs().max(y.abs()).leading_zeros() as i32;
+        let shift = 60 - bits;
+        (x, y) = if shift >= 0 {
+            (x << shift, y << shift)
+        } else {
+            (x >> -shift, y >> -shift)
+        };
+
+        // rotate into the right half, then towards the x axis
+        let mut angle = 0;
+        if x < 0 {
+            (x, y, angle) = if y >= 0 {
+                (y, -x, Self::FRAC_PI_2.0)
+            } else {
+                (-y, x, -Self::FRAC_PI_2.0)
+            };
+        }
+        for (i, step) in ATAN_TABLE.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if y > 0 {
+                (x, y, angle) = (x + dx, y - dy, angle + step);
+            } else {
+                (x, y, angle) = (x - dx, y + dy, angle - step);
+            }
+        }
+        Self(angle)
+    }
+
+    /// in radians, `self` is clamped to [-1, 1]
+    pub fn acos(self) -> Self {
+        let x = self.clamp(-Self::ONE, Self::ONE);
+        (Self::ONE - x * x).sqrt().atan2(x)
+    }
+
+    pub fn asin(self) -> Self {
+        let x = self.clamp(-Self::ONE, Self::ONE);
+        x.atan2((Self::ONE - x * x).sqrt())
+    }
+
+    pub fn to_degrees(self) -> Self {
+        // 180 / pi, with 56 fraction bits
+        const RAD_TO_DEG: i128 = (180 << 118) / PI_Q62;
+        Self(round_shift(self.0 as i128 * RAD_TO_DEG, 56) as i64)
+    }
+
+    pub fn to_radians(self) -> Self {
+        const DEG_TO_RAD: i128 = PI_Q62 / 180;
+        Self(round_shift(self.0 as i128 * DEG_TO_RAD, 62) as i64)
+    }
+
+    // the angle in turns, keeping only the fraction as a full circle of 2^32
+    fn turns(self) -> u32 {
+        const INV_TAU: i128 = (1 << 123) / PI_Q62;
+        ((self.0 as i128 * INV_TAU) >> 62) as u32
+    }
+}
+
+// sin of an angle where a full turn is 2^32
+fn sin_turns(turns: u32) -> Fixed {
+    const FRAC_BITS: u32 = 30 - QUARTER_BITS;
+    let quadrant = turns >> 30;
+    let mut within = turns & ((1 << 30) - 1);
+    // the second half of each half turn mirrors the first
+    if quadrant % 2 == 1 {
+        within = (1 << 30) - within;
+    }
+
+    let idx = (within >> FRAC_BITS) as usize;
+    let frac = (within & ((1 << FRAC_BITS) - 1)) as i64;
+    let a = SIN_TABLE[idx];
+    let value = if idx < QUARTER_STEPS {
+        a + (((SIN_TABLE[idx + 1] - a) * frac) >> FRAC_BITS)
+    } else {
+        a
+    };
+
+    Fixed(if quadrant >= 2 { -value } else { value })
+}
+
+// divides by 2^shift, rounding to nearest
+const fn round_shift(value: i128, shift: u32) -> i128 {
+    (value + (1 << (shift - 1))) >> shift
+}
+
+// computed with integer math, so the tables don't depend on the machine that builds them
+const fn sin_table() -> [i64; QUARTER_STEPS + 1] {
+    let mut ret = [0; QUARTER_STEPS + 1];
+    let mut i = 0;
+    while i <= QUARTER_STEPS {
+        // taylor series with 62 fraction bits, x is at most pi / 2
+        let x = PI_Q62 * i as i128 / (2 * QUARTER_STEPS as i128);
+        let x2 = (x * x) >> 62;
+        let mut term = x;
+        let mut sum = x;
+        let mut k = 1;
+        while k < 14 {
+            term = -((term * x2) >> 62) / ((2 * k) * (2 * k + 1));
+            sum += term;
+            k += 1;
+        }
+        ret[i] = round_shift(sum, 30) as i64;
+        i += 1;
+    }
+    ret
+}
+
+const fn atan_table() -> [i64; 34] {
+    let mut ret = [0; 34];
+    ret[0] = round_shift(PI_Q62 / 4, 30) as i64;
+    let mut i = 1;
+    while i < 34 {
+        // taylor series, x is at most 1/2
+        let x: i128 = 1 << (62 - i);
+        let x2 = (x * x) >> 62;
+        let mut power = x;
+        let mut sum = x;
+        let mut k = 1;
+        while power != 0 {
+            power = (power * x2) >> 62;
+            let term = power / (2 * k + 1);
+            sum += if k % 2 == 1 { -term } else { term };
+            k += 1;
+        }
+        ret[i] = round_shift(sum, 30) as i64;
+        i += 1;
+    }
+    ret
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Self::from_int(value)
+    }
+}
+
+impl Debug for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.to_f64(), f)
+    }
+}
+
+// as the nearest f64, so that precision flags work
+impl Display for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.to_f64(), f)
+    }
+}
+
+macro_rules! impl_op {
+    ($op:ident $fn:ident, $op_assign:ident $fn_assign:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $op for Fixed {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                let ($a, $b) = (self.0, rhs.0);
+                Self($body)
+            }
+        }
+
+        impl $op_assign for Fixed {
+            fn $fn_assign(&mut self, rhs: Self) {
+                *self = $op::$fn(*self, rhs);
+            }
+        }
+    };
+}
+
+impl_op!(Add add, AddAssign add_assign, |a, b| a.wrapping_add(b));
+impl_op!(Sub sub, SubAssign sub_assign, |a, b| a.wrapping_sub(b));
+impl_op!(Mul mul, MulAssign mul_assign, |a, b| ((a as i128 * b as i128) >> 32) as i64);
+// panics when dividing by zero
+impl_op!(Div div, DivAssign div_assign, |a, b| (((a as i128) << 32) / b as i128) as i64);
+impl_op!(Rem rem, RemAssign rem_assign, |a, b| a.wrapping_rem(b));
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+impl Scalar for Fixed {
+    const ZERO: Self = Self::ZERO;
+    const ONE: Self = Self::ONE;
+
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    fn from_f64(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl Signed for Fixed {
+    fn abs(self) -> Self {
+        self.abs()
+    }
+
+    fn signum(self) -> Self {
+        self.signum()
+    }
+}
+
+impl Float for Fixed {
+    // about 0.001
+    const TOLERANCE: Self = Self(4_294_967);
+
+    fn sqrt(self) -> Self {
+        self.sqrt()
+    }
+
+    fn floor(self) -> Self {
+        self.floor()
+    }
+
+    fn ceil(self) -> Self {
+        self.ceil()
+    }
+
+    fn round(self) -> Self {
+        self.round()
+    }
+
+    fn acos(self) -> Self {
+        self.acos()
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        self.atan2(other)
+    }
+
+    fn to_degrees(self) -> Self {
+        self.to_degrees()
+    }
+}
+
+/// for rendering, ex: sprite.set_position(body.position.into())
+impl<const N: usize> From<Vector<Fixed, N>> for Vector<f32, N> {
+    fn from(value: Vector<Fixed, N>) -> Self {
+        value.map(Fixed::to_f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    fn f(value: f64) -> Fixed {
+        Fixed::from_f64(value)
+    }
+
+    // the exact bits, a change here breaks replays and lockstep games
+    #[test]
+    fn snapshots() {
+        let sin_cos = [
+            (f(0.5), 2059116888, 3769188191),
+            (f(1.0), 3614089971, 2320580488),
+            (f(-2.5), -2570417713, -3440884862),
+            (f(3.0), 606105672, -4251984336),
+            (f(100.0), -2174823571, 3703630843),
+            (Fixed::PI, 0, -4294967296),
+            (Fixed::FRAC_PI_2, 4294967295, 6),
+        ];
+        for (x, sin, cos) in sin_cos {
+            assert_eq!((x.sin().to_bits(), x.cos().to_bits()), (sin, cos), "{x}");
+            assert_eq!(x.sin_cos(), (x.sin(), x.cos()));
+        }
+
+        let atan2 = [
+            (f(1.0), f(1.0), 3373259425),
+            (f(1.0), f(-2.0), 11501686389),
+            (f(-3.0), f(0.5), -6037210683),
+            (f(-0.25), f(-0.75), -12111129595),
+        ];
+        for (y, x, bits) in atan2 {
+            assert_eq!(y.atan2(x).to_bits(), bits, "{y} {x}");
+        }
+
+        let sqrt = [
+            (f(2.0), 6074000999),
+            (f(0.25), 2147483648),
+            (f(12345.678), 477218568878),
+            (Fixed::EPSILON, 65536),
+            (Fixed::MAX, 199032864766430),
+        ];
+        for (x, bits) in sqrt {
+            assert_eq!(x.sqrt().to_bits(), bits, "{x}");
+        }
+
+        assert_eq!(Fixed::PI.to_bits(), 13493037705);
+        assert_eq!(Fixed::ONE.to_bits(), 1 << 32);
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert_eq!(Fixed::ZERO.atan2(f(-2.0)), Fixed::PI);
+        assert_eq!(Fixed::ZERO.atan2(f(-1e-9)), Fixed::PI);
+        assert_eq!(Fixed::ZERO.atan2(f(3.0)), Fixed::ZERO);
+        assert_eq!(Fixed::ZERO.atan2(Fixed::ZERO), Fixed::ZERO);
+        assert_eq!(f(1.0).atan2(Fixed::ZERO), Fixed::FRAC_PI_2);
+        assert_eq!(Fixed::MIN.atan2(Fixed::ZERO), -Fixed::FRAC_PI_2);
+
+        assert_eq!(Fixed::ZERO.sqrt(), Fixed::ZERO);
+        assert_eq!(Fixed::ONE.sqrt(), Fixed::ONE);
+        assert_eq!(f(16.0).sqrt(), f(4.0));
+
+        // (value, floor, ceil, round)
+        let rounding = [
+            (-2.0, -2.0, -2.0, -2.0),
+            (-1.5, -2.0, -1.0, -2.0),
+            (-1.25, -2.0, -1.0, -1.0),
+            (-0.5, -1.0, 0.0, -1.0),
+            (-0.25, -1.0, 0.0, 0.0),
+            (0.5, 0.0, 1.0, 1.0),
+            (2.75, 2.0, 3.0, 3.0),
+        ];
+        for (x, floor, ceil, round) in rounding {
+            assert_eq!(f(x).floor(), f(floor), "{x}");
+            assert_eq!(f(x).ceil(), f(ceil), "{x}");
+            assert_eq!(f(x).round(), f(round), "{x}");
+            assert_eq!(f(x).round().to_f64(), x.round(), "{x}");
+        }
+        assert_eq!(f(-1.25).fract(), f(0.75));
+        assert_eq!(f(-0.5).to_int(), -1);
+        assert_eq!((-Fixed::EPSILON).floor(), f(-1.0));
+        assert_eq!((-Fixed::EPSILON).ceil(), Fixed::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "Negative square root")]
+    fn negative_sqrt() {
+        (-Fixed::EPSILON).sqrt();
+    }
+
+    #[test]
+    fn error_bounds() {
+        let mut rng = Rng::new(43);
+        for _ in 0..10_000 {
+            let x = f(rng.range(-1000.0..1000.0));
+            let y = f(rng.range(-1000.0..1000.0));
+            let (a, b) = (x.to_f64(), y.to_f64());
+
+            assert!((x.sin().to_f64() - a.sin()).abs() < 3e-7, "sin {a}");
+            assert!((x.cos().to_f64() - a.cos()).abs() < 3e-7, "cos {a}");
+            assert!(
+                (x.atan2(y).to_f64() - a.atan2(b)).abs() < 2e-9,
+                "atan2 {a} {b}"
+            );
+            let q = x.abs();
+            assert!((q.sqrt().to_f64() - q.to_f64().sqrt()).abs() <= Fixed::EPSILON.to_f64());
+
+            let t = f(rng.range(-1.0..1.0));
+            assert!(
+                (t.acos().to_f64() - t.to_f64().acos()).abs() < 1e-4,
+                "acos {t}"
+            );
+            assert!(
+                (t.asin().to_f64() - t.to_f64().asin()).abs() < 1e-4,
+                "asin {t}"
+            );
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use std::fmt::Display;
+use std::ops::Index;
+use std::ops::IndexMut;
+use std::ops::Mul;
+
+use super::simd;
+use super::FVec2;
+use super::FVec3;
+use super::Fixed;
+use super::Scalar;
+use super::Vec2;
+use super::Vec3;
+use super::Vec4;
+
+macro_rules! make_mat {
+    // use: Mat3 of Vec3 size 3, or FMat3 of FVec3 size 3 elem Fixed (the default is f32)
+    // implements:
+    // The matrix struct, stored column major (with debug, clone, copy, partial eq)
+    // Identity, row & column access, transposition
+    // Pretty printing
+    // Matrix-matrix and matrix-vector multiplication, using the given implementation
+    // Indexing by (row, column)
+    ($name:ident of $vec:ident size $n:literal, mul: $mul:path, $mul_vec:path) => {
+        make_mat!($name of $vec size $n elem f32, mul: $mul, $mul_vec);
+    };
+    ($name:ident of $vec:ident size $n:literal elem $t:ty, mul: $mul:path, $mul_vec:path) => {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[repr(C)]
+        pub struct $name([$t; $n * $n]);
+
+        impl $name {
+            const N: usize = $n;
+
+            pub fn identity() -> Self {
+                let mut ret = Self([<$t as Scalar>::ZERO; $n * $n]);
+                for i in 0..Self::N {
+                    ret.0[Self::N * i + i] = <$t as Scalar>::ONE;
+                }
+                ret
+            }
+
+            pub fn col(&self, idx: usize) -> $vec {
+                let mut ret = $vec::default();
+                for r in 0..Self::N {
+                    ret[r] = self.0[Self::N * idx + r];
+                }
+                ret
+            }
+
+            pub fn row(&self, idx: usize) -> $vec {
+                let mut ret = $vec::default();
+                for c in 0..Self::N {
+                    ret[c] = self.0[Self::N * c + idx];
+                }
+                ret
+            }
+
+            pub fn transpose(&self) -> Self {
+                let mut ret = *self;
+                for c in 0..Self::N {
+                    for r in 0..Self::N {
+                        ret.0[Self::N * c + r] = self.0[Self::N * r + c];
+                    }
+                }
+                ret
+            }
+
+            pub fn approx_eq(&self, other: &Self, epsilon: $t) -> bool {
+                self.0
+                    .iter()
+                    .zip(other.0.iter())
+                    .all(|(&a, &b)| (a - b).abs() <= epsilon)
+            }
+
+            // panics if the matrix is singular
+            pub fn inverse(&self) -> Self {
+                self.try_inverse().expect("Singular matrix")
+            }
+
+            // the elements, column by column
+            pub fn from_array(elements: [$t; $n * $n]) -> Self {
+                Self(elements)
+            }
+
+            pub fn to_array(&self) -> [$t; $n * $n] {
+                self.0
+            }
+
+            pub fn as_ptr(&self) -> *const $t {
+                self.0.as_ptr()
+            }
+
+            // portable matrix multiplication
+            pub fn scalar_mul(self, rhs: Self) -> Self {
+                const N: usize = $n;
+                let mut ret = Self::default();
+
+                for y in 0..N {
+                    for x in 0..N {
+                        let mut sum = <$t as Scalar>::ZERO;
+                        for e in 0..N {
+                            sum += self.0[N * e + x] * rhs.0[N * y + e];
+                        }
+                        ret.0[N * y + x] = sum;
+                    }
+                }
+
+                ret
+            }
+
+            // portable matrix-vector multiplication
+            pub fn scalar_mul_vec(self, rhs: $vec) -> $vec {
+                const N: usize = $n;
+                let mut ret = $vec::default();
+
+                for x in 0..N {
+                    let mut sum = <$t as Scalar>::ZERO;
+                    for e in 0..N {
+                        sum += self.0[N * e + x] * rhs[e];
+                    }
+                    ret[x] = sum;
+                }
+
+                ret
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::identity()
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                for r in 0..Self::N {
+                    if r > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "[ ")?;
+                    for c in 0..Self::N {
+                        write!(f, "{:.2} ", self.0[Self::N * c + r])?;
+                    }
+                    write!(f, "]")?;
+                }
+                Ok(())
+            }
+        }
+
+        impl Mul for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                $mul(self, rhs)
+            }
+        }
+
+        impl Mul<$vec> for $name {
+            type Output = $vec;
+
+            fn mul(self, rhs: $vec) -> Self::Output {
+                $mul_vec(self, rhs)
+            }
+        }
+
+        // indexed by (row, column)
+        impl Index<(usize, usize)> for $name {
+            type Output = $t;
+
+            fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+                assert!(row < Self::N && col < Self::N, "Bad index");
+                &self.0[Self::N * col + row]
+            }
+        }
+
+        impl IndexMut<(usize, usize)> for $name {
+            fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+                assert!(row < Self::N && col < Self::N, "Bad index");
+                &mut self.0[Self::N * col + row]
+            }
+        }
+    };
+}
+
+make_mat!(Mat2 of Vec2 size 2, mul: Mat2::scalar_mul, Mat2::scalar_mul_vec);
+make_mat!(Mat3 of Vec3 size 3, mul: Mat3::scalar_mul, Mat3::scalar_mul_vec);
+make_mat!(Mat4 of Vec4 size 4, mul: simd::mat4_mul, simd::mat4_mul_vec);
+make_mat!(FMat3 of FVec3 size 3 elem Fixed, mul: FMat3::scalar_mul, FMat3::scalar_mul_vec);
+
+// rotations follow the convention of `Vec2::angle`:
+// angles are in degrees, and positive angles turn clockwise
+
+impl Mat2 {
+    pub fn from_cols(c0: Vec2, c1: Vec2) -> Self {
+        Self([c0.x, c0.y, c1.x, c1.y])
+    }
+
+    pub fn scale(scale: Vec2) -> Self {
+        Self([scale.x, 0.0, 0.0, scale.y])
+    }
+
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self([cos, -sin, sin, cos])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        m[0] * m[3] - m[2] * m[1]
+    }
+
+    // returns None if the matrix is singular
+    pub fn try_inverse(&self) -> Option<Self> {
+        let inv_det = 1.0 / self.determinant();
+        if !inv_det.is_finite() {
+            return None;
+        }
+
+        let m = &self.0;
+        Some(Self([m[3], -m[1], -m[2], m[0]].map(|e| e * inv_det)))
+    }
+}
+
+impl Mat3 {
+    pub fn from_cols(c0: Vec3, c1: Vec3, c2: Vec3) -> Self {
+        Self([c0.x, c0.y, c0.z, c1.x, c1.y, c1.z, c2.x, c2.y, c2.z])
+    }
+
+    pub fn from_rows(r0: Vec3, r1: Vec3, r2: Vec3) -> Self {
+        Self::from_cols(r0, r1, r2).transpose()
+    }
+
+    pub fn scale(scale: Vec2) -> Self {
+        Self([scale.x, 0.0, 0.0, 0.0, scale.y, 0.0, 0.0, 0.0, 1.0])
+    }
+
+    pub fn translate(translate: Vec2) -> Self {
+        Self([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, translate.x, translate.y, 1.0])
+    }
+
+    pub fn rotate(angle: f32) -> Self {
+        let angle = angle.to_radians();
+        let sin = angle.sin();
+        let cos = angle.cos();
+
+        Self([cos, -sin, 0.0, sin, cos, 0.0, 0.0, 0.0, 1.0])
+    }
+
+    // translate * rotate * scale
+    // that is, scale first, then rotate, then translate
+    pub fn from_trs(translation: Vec2, rotation: f32, scale: Vec2) -> Self {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        Self([
+            scale.x * cos,
+            -scale.x * sin,
+            0.0,
+            scale.y * sin,
+            scale.y * cos,
+            0.0,
+            translation.x,
+            translation.y,
+            1.0,
+        ])
+    }
+
+    // the inverse of `from_trs`
+    // returns (translation, rotation, scale)
+    // a reflection is represented as a negative y scale
+    pub fn to_trs(&self) -> (Vec2, f32, Vec2) {
+        let c0 = Vec2::new(self.0[0], self.0[1]);
+        let c1 = Vec2::new(self.0[3], self.0[4]);
+        let rotation = (-c0.y).atan2(c0.x).to_degrees();
+        let sy = if self.determinant() < 0.0 {
+            -c1.len()
+        } else {
+            c1.len()
+        };
+
+        (self.translation(), rotation, Vec2::new(c0.len(), sy))
+    }
+
+    // the translation part of an affine transform
+    pub fn translation(&self) -> Vec2 {
+        Vec2::new(self.0[6], self.0[7])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        m[0] * (m[4] * m[8] - m[7] * m[5]) - m[3] * (m[1] * m[8] - m[7] * m[2])
+            + m[6] * (m[1] * m[5] - m[4] * m[2])
+    }
+
+    // returns None if the matrix is singular
+    pub fn try_inverse(&self) -> Option<Self> {
+        // a zero determinant, or one too small to divide by
+        let inv_det = 1.0 / self.determinant();
+        if !inv_det.is_finite() {
+            return None;
+        }
+
+        let m = &self.0;
+        // transposed cofactor matrix
+        let adj = [
+            m[4] * m[8] - m[5] * m[7],
+            m[2] * m[7] - m[1] * m[8],
+            m[1] * m[5] - m[2] * m[4],
+            m[5] * m[6] - m[3] * m[8],
+            m[0] * m[8] - m[2] * m[6],
+            m[2] * m[3] - m[0] * m[5],
+            m[3] * m[7] - m[4] * m[6],
+            m[1] * m[6] - m[0] * m[7],
+            m[0] * m[4] - m[1] * m[3],
+        ];
+
+        Some(Self(adj.map(|e| e * inv_det)))
+    }
+
+    // applies the full transform, including translation
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        let m = &self.0;
+        Vec2::new(
+            m[0] * point.x + m[3] * point.y + m[6],
+            m[1] * point.x + m[4] * point.y + m[7],
+        )
+    }
+
+    // applies the transform without translation, for directions and offsets
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        let m = &self.0;
+        Vec2::new(
+            m[0] * vector.x + m[3] * vector.y,
+            m[1] * vector.x + m[4] * vector.y,
+        )
+    }
+}
+
+impl Mat4 {
+    pub fn from_cols(c0: Vec4, c1: Vec4, c2: Vec4, c3: Vec4) -> Self {
+        Self([
+            c0.x, c0.y, c0.z, c0.w, c1.x, c1.y, c1.z, c1.w, c2.x, c2.y, c2.z, c2.w, c3.x, c3.y,
+            c3.z, c3.w,
+        ])
+    }
+
+    pub fn scale(scale: Vec3) -> Self {
+        let mut ret = Self::identity();
+        ret.0[0] = scale.x;
+        ret.0[5] = scale.y;
+        ret.0[10] = scale.z;
+        ret
+    }
+
+    pub fn translate(translate: Vec3) -> Self {
+        let mut ret = Self::identity();
+        ret.0[12] = translate.x;
+        ret.0[13] = translate.y;
+        ret.0[14] = translate.z;
+        ret
+    }
+
+    // rotation around the z axis, in the xy plane
+    pub fn rotate(angle: f32) -> Self {
+        Mat3::rotate(angle).into()
+    }
+
+    // maps the given box to clip space, [-1,1] on every axis
+    pub fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let mut ret = Self::identity();
+        ret.0[0] = 2.0 / (right - left);
+        ret.0[5] = 2.0 / (top - bottom);
+        ret.0[10] = -2.0 / (far - near);
+        ret.0[12] = -(right + left) / (right - left);
+        ret.0[13] = -(top + bottom) / (top - bottom);
+        ret.0[14] = -(far + near) / (far - near);
+        ret
+    }
+
+    // right handed, looking down the negative z axis
+    // `fov_y` is the vertical field of view in degrees
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (0.5 * fov_y.to_radians()).tan();
+        let mut ret = Self([0.0; 16]);
+        ret.0[0] = f / aspect;
+        ret.0[5] = f;
+        ret.0[10] = (far + near) / (near - far);
+        ret.0[11] = -1.0;
+        ret.0[14] = 2.0 * far * near / (near - far);
+        ret
+    }
+
+    // a view matrix placing the camera at `eye` looking towards `target`
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let cross = |a: Vec3, b: Vec3| {
+            Vec3::new(
+                a.y * b.z - a.z * b.y,
+                a.z * b.x - a.x * b.z,
+                a.x * b.y - a.y * b.x,
+            )
+        };
+
+        let f = (target - eye).normalize();
+        let s = cross(f, up).normalize();
+        let u = cross(s, f);
+
+        Self([
+            s.x,
+            u.x,
+            -f.x,
+            0.0,
+            s.y,
+            u.y,
+            -f.y,
+            0.0,
+            s.z,
+            u.z,
+            -f.z,
+            0.0,
+            -s.dot(eye),
+            -u.dot(eye),
+            f.dot(eye),
+            1.0,
+        ])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let c = self.cofactors();
+        let m = &self.0;
+        m[0] * c[0] + m[1] * c[4] + m[2] * c[8] + m[3] * c[12]
+    }
+
+    // returns None if the matrix is singular
+    pub fn try_inverse(&self) -> Option<Self> {
+        let adj = self.cofactors();
+        let m = &self.0;
+        let det = m[0] * adj[0] + m[1] * adj[4] + m[2] * adj[8] + m[3] * adj[12];
+        let inv_det = 1.0 / det;
+        if !inv_det.is_finite() {
+            return None;
+        }
+
+        Some(Self(adj.map(|e| e * inv_det)))
+    }
+
+    // applies the full transform and divides by w
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let p = *self * Vec4::new(point.x, point.y, point.z, 1.0);
+        (1.0 / p.w) * Vec3::new(p.x, p.y, p.z)
+    }
+
+    // applies the transform without translation
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let v = *self * Vec4::new(vector.x, vector.y, vector.z, 0.0);
+        Vec3::new(v.x, v.y, v.z)
+    }
+
+    // the transposed cofactor matrix (adjugate)
+    fn cofactors(&self) -> [f32; 16] {
+        let m = &self.0;
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        inv
+    }
+}
+
+// the deterministic counterpart of Mat3, see `Fixed`
+impl FMat3 {
+    pub fn from_cols(c0: FVec3, c1: FVec3, c2: FVec3) -> Self {
+        Self([c0.x, c0.y, c0.z, c1.x, c1.y, c1.z, c2.x, c2.y, c2.z])
+    }
+
+    pub fn scale(scale: FVec2) -> Self {
+        let mut ret = Self::identity();
+        ret.0[0] = scale.x;
+        ret.0[4] = scale.y;
+        ret
+    }
+
+    pub fn translate(translate: FVec2) -> Self {
+        let mut ret = Self::identity();
+        ret.0[6] = translate.x;
+        ret.0[7] = translate.y;
+        ret
+    }
+
+    pub fn rotate(angle: Fixed) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let mut ret = Self::identity();
+        ret.0[0] = cos;
+        ret.0[1] = -sin;
+        ret.0[3] = sin;
+        ret.0[4] = cos;
+        ret
+    }
+
+    // translate * rotate * scale
+    pub fn from_trs(translation: FVec2, rotation: Fixed, scale: FVec2) -> Self {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        Self::from_cols(
+            FVec3::new(scale.x * cos, -scale.x * sin, Fixed::ZERO),
+            FVec3::new(scale.y * sin, scale.y * cos, Fixed::ZERO),
+            FVec3::new(translation.x, translation.y, Fixed::ONE),
+        )
+    }
+
+    pub fn translation(&self) -> FVec2 {
+        FVec2::new(self.0[6], self.0[7])
+    }
+
+    pub fn determinant(&self) -> Fixed {
+        let m = &self.0;
+        m[0] * (m[4] * m[8] - m[7] * m[5]) - m[3] * (m[1] * m[8] - m[7] * m[2])
+            + m[6] * (m[1] * m[5] - m[4] * m[2])
+    }
+
+    // returns None if the matrix is singular
+    pub fn try_inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == Fixed::ZERO {
+            return None;
+        }
+
+        let m = &self.0;
+        // transposed cofactor matrix
+        let adj = [
+            m[4] * m[8] - m[5] * m[7],
+            m[2] * m[7] - m[1] * m[8],
+            m[1] * m[5] - m[2] * m[4],
+            m[5] * m[6] - m[3] * m[8],
+            m[0] * m[8] - m[2] * m[6],
+            m[2] * m[3] - m[0] * m[5],
+            m[3] * m[7] - m[4] * m[6],
+            m[1] * m[6] - m[0] * m[7],
+            m[0] * m[4] - m[1] * m[3],
+        ];
+
+        Some(Self(adj.map(|e| e / det)))
+    }
+
+    // applies the full transform, including translation
+    pub fn transform_point(&self, point: FVec2) -> FVec2 {
+        let m = &self.0;
+        FVec2::new(
+            m[0] * point.x + m[3] * point.y + m[6],
+            m[1] * point.x + m[4] * point.y + m[7],
+        )
+    }
+
+    // applies the transform without translation, for directions and offsets
+    pub fn transform_vector(&self, vector: FVec2) -> FVec2 {
+        let m = &self.0;
+        FVec2::new(
+            m[0] * vector.x + m[3] * vector.y,
+            m[1] * vector.x + m[4] * vector.y,
+        )
+    }
+}
+
+// for rendering
+impl From<FMat3> for Mat3 {
+    fn from(m: FMat3) -> Self {
+        Self(m.0.map(Fixed::to_f32))
+    }
+}
+
+// the linear part of an affine transform
+impl From<Mat3> for Mat2 {
+    fn from(m: Mat3) -> Self {
+        let m = &m.0;
+        Self([m[0], m[1], m[3], m[4]])
+    }
+}
+
+impl From<Mat2> for Mat3 {
+    fn from(m: Mat2) -> Self {
+        let m = &m.0;
+        Self([m[0], m[1], 0.0, m[2], m[3], 0.0, 0.0, 0.0, 1.0])
+    }
+}
+
+// embeds a 2D affine transform in the xy plane, leaving z untouched
+impl From<Mat3> for Mat4 {
+    fn from(m: Mat3) -> Self {
+        let m = &m.0;
+        Self([
+            m[0], m[1], 0.0, m[2], m[3], m[4], 0.0, m[5], 0.0, 0.0, 1.0, 0.0, m[6], m[7], 0.0, m[8],
+        ])
+    }
+}
+
+// the inverse of the above, anything involving z is discarded
+impl From<Mat4> for Mat3 {
+    fn from(m: Mat4) -> Self {
+        let m = &m.0;
+        Self([m[0], m[1], m[3], m[4], m[5], m[7], m[12], m[13], m[15]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    const EPSILON: f32 = 1e-4;
+
+    // random, but far from singular: small entries around a large diagonal
+    macro_rules! random_mat {
+        ($name:ident, $rng:expr) => {{
+            let mut m = $name::identity();
+            for e in m.0.iter_mut() {
+                *e = *e * 4.0 + $rng.range(-1.0..1.0);
+            }
+            m
+        }};
+    }
+
+    macro_rules! check_mat {
+        ($name:ident) => {
+            let mut rng = Rng::new(30);
+            for _ in 0..100 {
+                let m = random_mat!($name, rng);
+                let identity = $name::identity();
+                assert!((m * m.inverse()).approx_eq(&identity, EPSILON), "{m:?}");
+                assert!((m.inverse() * m).approx_eq(&identity, EPSILON), "{m:?}");
+
+                let det = m.determinant();
+                assert!((m.transpose().determinant() - det).abs() <= EPSILON * det.abs());
+                assert_eq!(m.transpose().transpose(), m);
+            }
+            assert!($name::from_array(Default::default())
+                .try_inverse()
+                .is_none());
+        };
+    }
+
+    #[test]
+    fn mat2() {
+        check_mat!(Mat2);
+        let m = Mat2::from_cols(Vec2::new(1.0, 2.0), Vec2::new(2.0, 4.0));
+        assert!(m.try_inverse().is_none());
+    }
+
+    #[test]
+    fn mat3() {
+        check_mat!(Mat3);
+        let m = Mat3::from_cols(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(4.0, 5.0, 6.0),
+            Vec3::new(5.0, 7.0, 9.0),
+        );
+        assert!(m.try_inverse().is_none());
+    }
+
+    #[test]
+    fn mat4() {
+        check_mat!(Mat4);
+        let m = Mat4::translate(Vec3::new(1.0, 2.0, 3.0)) * Mat4::scale(Vec3::new(2.0, 3.0, 0.0));
+        assert!(m.try_inverse().is_none());
+    }
+
+    #[test]
+    fn small_scale_inverse() {
+        let m = Mat3::scale(Vec2::splat(1e-4));
+        let inverse = m.try_inverse().unwrap();
+        assert!(inverse.approx_eq(&Mat3::scale(Vec2::splat(1e4)), 1e-1));
+        assert!((m * inverse).approx_eq(&Mat3::identity(), EPSILON));
+
+        assert!(Mat2::scale(Vec2::splat(1e-4)).try_inverse().is_some());
+        assert!(Mat4::scale(Vec3::new(1e-3, 1e-3, 1e-3))
+            .try_inverse()
+            .is_some());
+    }
+
+    #[test]
+    fn trs_round_trip() {
+        let mut rng = Rng::new(31);
+        for _ in 0..100 {
+            let t = Vec2::new(rng.range(-100.0..100.0), rng.range(-100.0..100.0));
+            let r = rng.range(-179.0..179.0);
+            let s = Vec2::new(rng.range(0.1..10.0), rng.range(-10.0..10.0));
+            if s.y.abs() < 0.1 {
+                continue;
+            }
+
+            let (t2, r2, s2) = Mat3::from_trs(t, r, s).to_trs();
+            assert!((t2 - t).len() <= EPSILON * 100.0, "{t:?} {t2:?}");
+            assert!((r2 - r).abs() <= 1e-3, "{r} {r2}");
+            assert!((s2 - s).len() <= EPSILON * 10.0, "{s:?} {s2:?}");
+
+            let m = Mat3::translate(t) * Mat3::rotate(r) * Mat3::scale(s);
+            assert!(m.approx_eq(&Mat3::from_trs(t, r, s), EPSILON * 100.0));
+        }
+    }
+}
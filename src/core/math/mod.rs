@@ -0,0 +1,43 @@
+mod color;
+pub use color::*;
+
+mod curve;
+pub use curve::*;
+
+mod fixed;
+pub use fixed::*;
+
+mod matrix;
+pub use matrix::*;
+
+pub mod noise;
+pub mod polygon;
+
+mod random;
+pub use random::*;
+
+mod shapes;
+pub use shapes::*;
+
+pub mod simd;
+
+mod transform;
+pub use transform::*;
+
+mod vector;
+pub use vector::*;
+
+// commodity macro to create vectors easily
+#[macro_export]
+macro_rules! vec {
+    ($x:expr, $y:expr) => {
+        From::<_>::from([$x, $y])
+    };
+    ($x:expr, $y:expr, $z:expr) => {
+        From::<_>::from([$x, $y, $z])
+    };
+
+    ($(_:tt)*) => {
+        compile_error!("No vector type for specified number of components")
+    };
+}
@@ -0,0 +1,655 @@
+//! Coherent noise, for terrain, clouds, textures and screen effects
+//!
+//! every generator is seeded and only uses exact float operations (no sin/cos/exp),
+//! so the same seed gives the same values on every platform
+//!
+//! ex: a cloud texture
+//! let clouds = Fractal::fbm(Perlin::new(7)).octaves(5);
+//! let pixels = noise::grayscale(&clouds, size, 1.0 / 64.0);
+//! let tex = Texture::from_memory_in(ctx, size, pixels.as_ptr() as _, ColorSpace::Linear);
+
+use std::ops::Mul;
+
+use super::Color;
+use super::Rng;
+use super::UVec2;
+use super::Vec2;
+use super::Vec3;
+
+/// A noise function, giving values roughly in [-1, 1]
+/// nearby points give nearby values, and features are about 1 unit apart
+/// scale the input to change the size of the features
+pub trait Noise {
+    fn sample1(&self, x: f32) -> f32;
+    fn sample2(&self, p: Vec2) -> f32;
+    fn sample3(&self, p: Vec3) -> f32;
+}
+
+// a shuffled permutation of 0..256, repeated twice to avoid wrapping indices
+#[derive(Clone)]
+struct Permutation([u8; 512]);
+
+impl Permutation {
+    fn new(seed: u64) -> Self {
+        let mut p: [u8; 256] = std::array::from_fn(|i| i as u8);
+        Rng::new(seed).shuffle(&mut p);
+
+        let mut ret = [0; 512];
+        ret[..256].copy_from_slice(&p);
+        ret[256..].copy_from_slice(&p);
+        Self(ret)
+    }
+
+    fn hash1(&self, x: i32) -> u8 {
+        self.0[(x & 255) as usize]
+    }
+
+    fn hash2(&self, x: i32, y: i32) -> u8 {
+        self.0[(x & 255) as usize + self.hash1(y) as usize]
+    }
+
+    fn hash3(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.0[(x & 255) as usize + self.hash2(y, z) as usize]
+    }
+}
+
+fn floor(x: f32) -> i32 {
+    x.floor() as i32
+}
+
+// smootherstep, with zero first and second derivatives at 0 and 1
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+// gradient magnitudes of 1 to 8, either sign
+fn grad1(hash: u8, x: f32) -> f32 {
+    let g = 1.0 + (hash & 7) as f32;
+    if hash & 8 == 0 {
+        g * x
+    } else {
+        -g * x
+    }
+}
+
+// the 8 directions of a square's edges and corners
+fn grad2(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+// the 12 directions to a cube's edges, padded to 16 (Perlin's improved noise)
+fn grad3(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+/// Gradient noise (Ken Perlin's improved noise)
+#[derive(Clone)]
+pub struct Perlin {
+    perm: Permutation,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            perm: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn sample1(&self, x: f32) -> f32 {
+        let xi = floor(x);
+        let xf = x - xi as f32;
+
+        let a = grad1(self.perm.hash1(xi), xf);
+        let b = grad1(self.perm.hash1(xi + 1), xf - 1.0);
+        // the gradients go up to 8
+        0.25 * lerp(a, b, fade(xf))
+    }
+
+    fn sample2(&self, p: Vec2) -> f32 {
+        let (xi, yi) = (floor(p.x), floor(p.y));
+        let (xf, yf) = (p.x - xi as f32, p.y - yi as f32);
+        let h = |dx, dy| self.perm.hash2(xi + dx, yi + dy);
+
+        let (u, v) = (fade(xf), fade(yf));
+        let bottom = lerp(grad2(h(0, 0), xf, yf), grad2(h(1, 0), xf - 1.0, yf), u);
+        let top = lerp(
+            grad2(h(0, 1), xf, yf - 1.0),
+            grad2(h(1, 1), xf - 1.0, yf - 1.0),
+            u,
+        );
+        lerp(bottom, top, v)
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        let (xi, yi, zi) = (floor(p.x), floor(p.y), floor(p.z));
+        let (xf, yf, zf) = (p.x - xi as f32, p.y - yi as f32, p.z - zi as f32);
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let h = self.perm.hash3(xi + dx, yi + dy, zi + dz);
+            grad3(h, xf - dx as f32, yf - dy as f32, zf - dz as f32)
+        };
+
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+        let near = lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        );
+        let far = lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        );
+        lerp(near, far, w)
+    }
+}
+
+/// Simplex noise
+/// smoother and less grid-aligned than `Perlin`, and cheaper in 3D
+/// (the patents on 3D simplex noise expired in 2022)
+#[derive(Clone)]
+pub struct Simplex {
+    perm: Permutation,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            perm: Permutation::new(seed),
+        }
+    }
+}
+
+// the contribution of a simplex corner, fading out at distance sqrt(r2)
+fn corner(r2: f32, d2: f32, grad: impl FnOnce() -> f32) -> f32 {
+    let t = r2 - d2;
+    if t <= 0.0 {
+        0.0
+    } else {
+        let t2 = t * t;
+        t2 * t2 * grad()
+    }
+}
+
+impl Noise for Simplex {
+    fn sample1(&self, x: f32) -> f32 {
+        let i = floor(x);
+        let x0 = x - i as f32;
+        let x1 = x0 - 1.0;
+
+        let n0 = corner(1.0, x0 * x0, || grad1(self.perm.hash1(i), x0));
+        let n1 = corner(1.0, x1 * x1, || grad1(self.perm.hash1(i + 1), x1));
+        0.395 * (n0 + n1)
+    }
+
+    fn sample2(&self, p: Vec2) -> f32 {
+        // (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6
+        const SKEW: f32 = 0.3660254;
+        const UNSKEW: f32 = 0.21132487;
+
+        // the cell in the skewed grid, made of two triangles
+        let s = (p.x + p.y) * SKEW;
+        let (i, j) = (floor(p.x + s), floor(p.y + s));
+        let t = (i + j) as f32 * UNSKEW;
+        let (x0, y0) = (p.x - (i as f32 - t), p.y - (j as f32 - t));
+
+        // which of the two triangles
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (x1, y1) = (x0 - i1 as f32 + UNSKEW, y0 - j1 as f32 + UNSKEW);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * UNSKEW, y0 - 1.0 + 2.0 * UNSKEW);
+
+        let h = |di, dj| self.perm.hash2(i + di, j + dj);
+        let n0 = corner(0.5, x0 * x0 + y0 * y0, || grad2(h(0, 0), x0, y0));
+        let n1 = corner(0.5, x1 * x1 + y1 * y1, || grad2(h(i1, j1), x1, y1));
+        let n2 = corner(0.5, x2 * x2 + y2 * y2, || grad2(h(1, 1), x2, y2));
+        70.0 * (n0 + n1 + n2)
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        const SKEW: f32 = 1.0 / 3.0;
+        const UNSKEW: f32 = 1.0 / 6.0;
+
+        let s = (p.x + p.y + p.z) * SKEW;
+        let (i, j, k) = (floor(p.x + s), floor(p.y + s), floor(p.z + s));
+        let t = (i + j + k) as f32 * UNSKEW;
+        let d0 = Vec3::new(
+            p.x - (i as f32 - t),
+            p.y - (j as f32 - t),
+            p.z - (k as f32 - t),
+        );
+
+        // the cell is split into 6 tetrahedrons, found by ordering the coordinates
+        let (o1, o2) = if d0.x >= d0.y {
+            if d0.y >= d0.z {
+                ([1, 0, 0], [1, 1, 0])
+            } else if d0.x >= d0.z {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if d0.y < d0.z {
+            ([0, 0, 1], [0, 1, 1])
+        } else if d0.x < d0.z {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+
+        let offset = |o: [i32; 3], n: f32| {
+            d0 - Vec3::new(o[0] as f32, o[1] as f32, o[2] as f32) + Vec3::splat(n * UNSKEW)
+        };
+        let corners = [
+            ([0, 0, 0], d0),
+            (o1, offset(o1, 1.0)),
+            (o2, offset(o2, 2.0)),
+            ([1, 1, 1], offset([1, 1, 1], 3.0)),
+        ];
+
+        let sum: f32 = corners
+            .into_iter()
+            .map(|(o, d)| {
+                corner(0.6, d.len2(), || {
+                    let h = self.perm.hash3(i + o[0], j + o[1], k + o[2]);
+                    grad3(h, d.x, d.y, d.z)
+                })
+            })
+            .sum();
+        32.0 * sum
+    }
+}
+
+/// How `Worley` measures the distance to feature points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// round cells
+    #[default]
+    Euclidean,
+    /// diamond shaped cells
+    Manhattan,
+    /// square cells
+    Chebyshev,
+}
+
+impl Metric {
+    fn distance<const N: usize>(self, d: [f32; N]) -> f32 {
+        match self {
+            Metric::Euclidean => d.iter().map(|c| c * c).sum::<f32>().sqrt(),
+            Metric::Manhattan => d.iter().map(|c| c.abs()).sum(),
+            Metric::Chebyshev => d.iter().fold(0.0, |m, c| c.abs().max(m)),
+        }
+    }
+}
+
+/// Cellular noise, based on the distance to randomly scattered feature points
+/// there is one point per unit cell
+/// ex: stone tiles, scales, caustics
+#[derive(Debug, Clone)]
+pub struct Worley {
+    seed: u64,
+    metric: Metric,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            metric: Metric::Euclidean,
+        }
+    }
+
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// the distances to the closest (F1) and second closest (F2) feature points
+    /// F2 - F1 gives the borders between cells
+    pub fn distances2(&self, p: Vec2) -> (f32, f32) {
+        let (xi, yi) = (floor(p.x), floor(p.y));
+        let mut ret = (f32::MAX, f32::MAX);
+
+        for y in yi - 1..=yi + 1 {
+            for x in xi - 1..=xi + 1 {
+                let h = self.hash(x, y, 0);
+                let point = Vec2::new(x as f32 + unit(h), y as f32 + unit(h >> 32));
+                let d = self.metric.distance((point - p).0);
+                ret = closest(ret, d);
+            }
+        }
+
+        ret
+    }
+
+    pub fn distances3(&self, p: Vec3) -> (f32, f32) {
+        let (xi, yi, zi) = (floor(p.x), floor(p.y), floor(p.z));
+        let mut ret = (f32::MAX, f32::MAX);
+
+        for z in zi - 1..=zi + 1 {
+            for y in yi - 1..=yi + 1 {
+                for x in xi - 1..=xi + 1 {
+                    let h = self.hash(x, y, z);
+                    let hz = self.hash(z, x, y);
+                    let point = Vec3::new(
+                        x as f32 + unit(h),
+                        y as f32 + unit(h >> 32),
+                        z as f32 + unit(hz),
+                    );
+                    let d = self.metric.distance((point - p).0);
+                    ret = closest(ret, d);
+                }
+            }
+        }
+
+        ret
+    }
+
+    // a well mixed hash of the cell, see splitmix64
+    fn hash(&self, x: i32, y: i32, z: i32) -> u64 {
+        let mut h = self.seed
+            ^ (x as u32 as u64).wrapping_mul(0x9E3779B97F4A7C15)
+            ^ (y as u32 as u64).wrapping_mul(0xC2B2AE3D27D4EB4F)
+            ^ (z as u32 as u64).wrapping_mul(0x165667B19E3779F9);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D049BB133111EB);
+        h ^ (h >> 31)
+    }
+}
+
+// the low 24 bits of the hash, in [0, 1)
+fn unit(hash: u64) -> f32 {
+    (hash & 0xFFFFFF) as f32 / (1 << 24) as f32
+}
+
+fn closest((f1, f2): (f32, f32), d: f32) -> (f32, f32) {
+    if d < f1 {
+        (d, f1)
+    } else {
+        (f1, f2.min(d))
+    }
+}
+
+// F1, mapped from [0, 1] to [-1, 1]
+impl Noise for Worley {
+    fn sample1(&self, x: f32) -> f32 {
+        self.sample2(Vec2::new(x, 0.0))
+    }
+
+    fn sample2(&self, p: Vec2) -> f32 {
+        2.0 * self.distances2(p).0 - 1.0
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        2.0 * self.distances3(p).0 - 1.0
+    }
+}
+
+/// How the octaves of a `Fractal` are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalKind {
+    /// fractional brownian motion, a plain sum, ex: clouds, rolling hills
+    Fbm,
+    /// sharp crests where the noise crosses zero, ex: mountain ridges
+    Ridged,
+    /// sum of absolute values, creased valleys, ex: fire, marble
+    Turbulence,
+}
+
+/// Layers several octaves of a noise, each one smaller and fainter than the last
+pub struct Fractal<N: Noise> {
+    noise: N,
+    kind: FractalKind,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+}
+
+impl<N: Noise> Fractal<N> {
+    pub fn new(noise: N, kind: FractalKind) -> Self {
+        Self {
+            noise,
+            kind,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn fbm(noise: N) -> Self {
+        Self::new(noise, FractalKind::Fbm)
+    }
+
+    pub fn ridged(noise: N) -> Self {
+        Self::new(noise, FractalKind::Ridged)
+    }
+
+    pub fn turbulence(noise: N) -> Self {
+        Self::new(noise, FractalKind::Turbulence)
+    }
+
+    /// the number of layers, 4 by default
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// how much the frequency grows every octave, 2 by default
+    pub fn lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// how much the amplitude shrinks every octave, 0.5 by default
+    pub fn gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    fn combine<P: Copy + Mul<f32, Output = P>>(&self, p: P, sample: impl Fn(P) -> f32) -> f32 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for _ in 0..self.octaves {
+            let v = sample(p * frequency);
+            sum += amplitude
+                * match self.kind {
+                    FractalKind::Fbm => v,
+                    FractalKind::Ridged => (1.0 - v.abs()) * (1.0 - v.abs()),
+                    FractalKind::Turbulence => v.abs(),
+                };
+            total += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+
+        match self.kind {
+            FractalKind::Fbm => sum / total,
+            // these are in [0, 1]
+            _ => 2.0 * sum / total - 1.0,
+        }
+    }
+}
+
+impl<N: Noise> Noise for Fractal<N> {
+    fn sample1(&self, x: f32) -> f32 {
+        self.combine(x, |x| self.noise.sample1(x))
+    }
+
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.combine(p, |p| self.noise.sample2(p))
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.combine(p, |p| self.noise.sample3(p))
+    }
+}
+
+/// Domain warping: offsets the input of a noise by another noise
+/// turns regular noise into swirly, flowing shapes
+/// ex: Warp::new(Fractal::fbm(Perlin::new(1)), Fractal::fbm(Perlin::new(2)), 4.0)
+pub struct Warp<N: Noise, W: Noise> {
+    noise: N,
+    warp: W,
+    strength: f32,
+}
+
+impl<N: Noise, W: Noise> Warp<N, W> {
+    /// `strength` is the largest offset, in input units
+    pub fn new(noise: N, warp: W, strength: f32) -> Self {
+        Self {
+            noise,
+            warp,
+            strength,
+        }
+    }
+}
+
+// every axis samples the warp far from the others, so that they don't correlate
+impl<N: Noise, W: Noise> Noise for Warp<N, W> {
+    fn sample1(&self, x: f32) -> f32 {
+        let dx = self.warp.sample1(x);
+        self.noise.sample1(x + self.strength * dx)
+    }
+
+    fn sample2(&self, p: Vec2) -> f32 {
+        let d = Vec2::new(
+            self.warp.sample2(p),
+            self.warp.sample2(p + Vec2::new(5.2, 1.3)),
+        );
+        self.noise.sample2(p + self.strength * d)
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        let d = Vec3::new(
+            self.warp.sample3(p),
+            self.warp.sample3(p + Vec3::new(5.2, 1.3, 2.8)),
+            self.warp.sample3(p + Vec3::new(1.7, 9.2, 4.6)),
+        );
+        self.noise.sample3(p + self.strength * d)
+    }
+}
+
+/// Builds RGBA8 pixels for `ITexture::from_memory`, from the color of every pixel
+/// `f` gets the pixel coordinates, with (0, 0) at the bottom left like textures loaded from files
+pub fn image(size: UVec2, mut f: impl FnMut(UVec2) -> Color) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(4 * size.x as usize * size.y as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            ret.extend_from_slice(&f(UVec2::new(x, y)).to_srgb8());
+        }
+    }
+    ret
+}
+
+/// Builds RGBA8 pixels from a noise, mapping [-1, 1] to black..white
+/// pixel (x, y) samples the noise at (x, y) * `scale`
+/// the bytes are the raw noise values, upload them with `ColorSpace::Linear` to sample them as is
+pub fn grayscale(noise: &impl Noise, size: UVec2, scale: f32) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(4 * size.x as usize * size.y as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let v = noise.sample2(Vec2::new(x as f32, y as f32) * scale);
+            let v = ((0.5 * v + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8;
+            ret.extend_from_slice(&[v, v, v, 255]);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // recorded values: a change here changes every generated level and texture
+    // the values are exact, every platform computes them the same way
+    fn check(noise: &impl Noise, expected: ([f32; 3], [f32; 3], [f32; 2])) {
+        let xs = [0.3, 1.7, -2.45];
+        let points2 = [
+            Vec2::new(0.3, 0.7),
+            Vec2::new(-5.2, 3.1),
+            Vec2::new(12.5, -0.25),
+        ];
+        let points3 = [Vec3::new(0.3, 0.7, 0.1), Vec3::new(-5.2, 3.1, 8.9)];
+
+        assert_eq!(xs.map(|x| noise.sample1(x)), expected.0);
+        assert_eq!(points2.map(|p| noise.sample2(p)), expected.1);
+        assert_eq!(points3.map(|p| noise.sample3(p)), expected.2);
+    }
+
+    #[test]
+    fn perlin() {
+        let expected = (
+            [-0.61630803, -0.011382163, -0.26894367],
+            [-0.0218938, -0.08644291, -0.56030273],
+            [0.013963098, -0.038852125],
+        );
+        check(&Perlin::new(40), expected);
+
+        // zero on the lattice
+        let perlin = Perlin::new(40);
+        assert_eq!(perlin.sample1(3.0), 0.0);
+        assert_eq!(perlin.sample2(Vec2::new(-2.0, 5.0)), 0.0);
+        assert_eq!(perlin.sample3(Vec3::new(1.0, 0.0, -7.0)), 0.0);
+    }
+
+    #[test]
+    fn simplex() {
+        let expected = (
+            [-0.7249137, -0.08769957, -0.23662272],
+            [-0.19850795, -0.7847188, 0.4026117],
+            [-0.022210073, -0.43308026],
+        );
+        check(&Simplex::new(40), expected);
+    }
+
+    #[test]
+    fn worley() {
+        let expected = (
+            [-0.19193166, 0.2473172, 0.55060995],
+            [0.116806984, -0.27249128, -0.3320552],
+            [0.6414304, -0.12439859],
+        );
+        check(&Worley::new(40), expected);
+    }
+
+    #[test]
+    fn fbm() {
+        let expected = (
+            [-0.4973462, 0.05621864, -0.17166701],
+            [0.03737287, 0.12147082, -0.29882813],
+            [-0.022798752, 0.043812655],
+        );
+        check(&Fractal::fbm(Perlin::new(40)).octaves(4), expected);
+    }
+
+    #[test]
+    fn seeds_differ() {
+        let p = Vec2::new(0.3, 0.7);
+        assert_ne!(Perlin::new(1).sample2(p), Perlin::new(2).sample2(p));
+        assert_eq!(Simplex::new(1).sample2(p), Simplex::new(1).sample2(p));
+    }
+}
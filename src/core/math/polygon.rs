@@ -0,0 +1,960 @@
+//! Algorithms on simple polygons, given as their points in order
+//!
+//! a simple polygon's edges don't cross each other, holes are given as separate polygons
+//! outlines are counter clockwise and holes clockwise, unless stated otherwise
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use image::RgbaImage;
+
+use super::Aabb;
+use super::Polygon;
+use super::Segment;
+use super::Shape;
+use super::Vec2;
+
+const EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// shoelace formula, positive for counter clockwise polygons
+pub fn signed_area(points: &[Vec2]) -> f32 {
+    let n = points.len();
+    0.5 * (0..n)
+        .map(|i| points[i].cross(points[(i + 1) % n]))
+        .sum::<f32>()
+}
+
+pub fn winding(points: &[Vec2]) -> Winding {
+    if signed_area(points) < 0.0 {
+        Winding::Clockwise
+    } else {
+        Winding::CounterClockwise
+    }
+}
+
+/// reverses the points if they don't have the given winding
+pub fn set_winding(points: &mut [Vec2], winding: Winding) {
+    if self::winding(points) != winding {
+        points.reverse();
+    }
+}
+
+/// straight angles are allowed
+pub fn is_convex(points: &[Vec2]) -> bool {
+    let n = points.len();
+    let turns = (0..n).map(|i| {
+        let (a, b, c) = (points[i], points[(i + 1) % n], points[(i + 2) % n]);
+        (b - a).cross(c - b)
+    });
+
+    let (mut left, mut right) = (false, false);
+    for turn in turns {
+        left |= turn > EPSILON;
+        right |= turn < -EPSILON;
+    }
+    !(left && right)
+}
+
+/// points on the outline are inside
+pub fn contains_point(points: &[Vec2], point: Vec2) -> bool {
+    let n = points.len();
+    let edges = (0..n).map(|i| (points[i], points[(i + 1) % n]));
+
+    let mut inside = false;
+    for (a, b) in edges {
+        if Segment::new(a, b).distance_to_point(point) <= EPSILON {
+            return true;
+        }
+        // even-odd rule, with a ray going right
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// splits the polygon into counter clockwise triangles, given as indices into `points`
+/// works with either winding
+/// ex: to fill a shape, use the triangles as an index buffer
+pub fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut ring: Vec<usize> = (0..points.len()).collect();
+    if signed_area(points) < 0.0 {
+        ring.reverse();
+    }
+    ear_clip(points, ring)
+}
+
+/// like `triangulate`, with holes cut out of the polygon
+/// returns the outline's points followed by each hole's, and the triangles indexing them
+/// the holes must be inside the outline and not overlap each other, with either winding
+pub fn triangulate_with_holes(
+    outline: &[Vec2],
+    holes: &[Vec<Vec2>],
+) -> (Vec<Vec2>, Vec<[usize; 3]>) {
+    let mut points = outline.to_vec();
+    let mut ring: Vec<usize> = (0..points.len()).collect();
+    if signed_area(outline) < 0.0 {
+        ring.reverse();
+    }
+
+    // holes are walked clockwise
+    let mut hole_rings: Vec<Vec<usize>> = Vec::new();
+    for hole in holes.iter().filter(|h| h.len() >= 3) {
+        let mut idx: Vec<usize> = (points.len()..points.len() + hole.len()).collect();
+        if signed_area(hole) > 0.0 {
+            idx.reverse();
+        }
+        points.extend_from_slice(hole);
+        hole_rings.push(idx);
+    }
+
+    // bridging the rightmost holes first keeps later bridges from crossing them
+    let rightmost = |h: &Vec<usize>| {
+        h.iter()
+            .copied()
+            .max_by(|&a, &b| points[a].x.total_cmp(&points[b].x))
+            .unwrap()
+    };
+    hole_rings.sort_by(|a, b| points[rightmost(b)].x.total_cmp(&points[rightmost(a)].x));
+
+    for hole in hole_rings {
+        let m = rightmost(&hole);
+        if let Some(k) = bridge(&points, &ring, points[m]) {
+            let start = hole.iter().position(|&i| i == m).unwrap();
+            let mut spliced = ring[..=k].to_vec();
+            spliced.extend(hole[start..].iter().chain(&hole[..start]));
+            spliced.extend([m, ring[k]]);
+            spliced.extend_from_slice(&ring[k + 1..]);
+            ring = spliced;
+        }
+    }
+
+    let triangles = ear_clip(&points, ring);
+    (points, triangles)
+}
+
+// the position in the ring of a vertex that can be joined to `m` without crossing any edge
+// (David Eberly, Triangulation by Ear Clipping)
+fn bridge(points: &[Vec2], ring: &[usize], m: Vec2) -> Option<usize> {
+    let n = ring.len();
+    let edge = |k: usize| (points[ring[k]], points[ring[(k + 1) % n]]);
+
+    // the closest edge hit by a ray going right from m
+    let mut hit: Option<(f32, usize)> = None;
+    for k in 0..n {
+        let (a, b) = edge(k);
+        if (a.y > m.y) == (b.y > m.y) {
+            continue;
+        }
+        let x = a.x + (m.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x >= m.x && hit.is_none_or(|(best, _)| x < best) {
+            hit = Some((x, k));
+        }
+    }
+    let (x, k) = hit?;
+    let hit = Vec2::new(x, m.y);
+
+    // the end of the edge furthest right is visible, unless reflex vertices are in the way
+    let (a, b) = edge(k);
+    let mut best = if a.x > b.x { k } else { (k + 1) % n };
+    let p = points[ring[best]];
+
+    if p != hit {
+        let angle = |v: Vec2| {
+            let d = v - m;
+            (d.y.abs().atan2(d.x), d.len2())
+        };
+        for i in 0..n {
+            let v = points[ring[i]];
+            let reflex = {
+                let (prev, next) = (points[ring[(i + n - 1) % n]], points[ring[(i + 1) % n]]);
+                (v - prev).cross(next - v) < 0.0
+            };
+            if v != p && reflex && in_triangle(v, m, hit, p) {
+                let (a0, d0) = angle(v);
+                let (a1, d1) = angle(points[ring[best]]);
+                if a0 < a1 || (a0 == a1 && d0 < d1) {
+                    best = i;
+                }
+            }
+        }
+    }
+
+    // a vertex shared by an earlier bridge is in the ring twice, take the side facing m
+    let idx = ring[best];
+    let ret = (0..n)
+        .filter(|&i| ring[i] == idx)
+        .find(|&i| {
+            let prev = points[ring[(i + n - 1) % n]];
+            let next = points[ring[(i + 1) % n]];
+            in_cone(prev, points[idx], next, m)
+        })
+        .unwrap_or(best);
+    Some(ret)
+}
+
+// whether `m` is on the inside of the corner at `p`, for a counter clockwise ring
+fn in_cone(prev: Vec2, p: Vec2, next: Vec2, m: Vec2) -> bool {
+    let d = m - p;
+    if (p - prev).cross(next - p) >= 0.0 {
+        (next - p).cross(d) >= 0.0 && d.cross(prev - p) >= 0.0
+    } else {
+        !((prev - p).cross(d) > 0.0 && d.cross(next - p) > 0.0)
+    }
+}
+
+// inclusive, for a triangle with either winding
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let d0 = (b - a).cross(p - a);
+    let d1 = (c - b).cross(p - b);
+    let d2 = (a - c).cross(p - c);
+    let neg = d0 < 0.0 || d1 < 0.0 || d2 < 0.0;
+    let pos = d0 > 0.0 || d1 > 0.0 || d2 > 0.0;
+    !(neg && pos)
+}
+
+// `ring` indexes `points`, counter clockwise
+fn ear_clip(points: &[Vec2], mut ring: Vec<usize>) -> Vec<[usize; 3]> {
+    let mut ret = Vec::with_capacity(ring.len().saturating_sub(2));
+    let mut i = 0;
+    let mut misses = 0;
+
+    while ring.len() > 3 {
+        let n = ring.len();
+        let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+        let turn = (pb - pa).cross(pc - pb);
+
+        let clip = if turn.abs() <= EPSILON * (pb - pa).len() * (pc - pb).len() {
+            // no area, drop the vertex without a triangle
+            true
+        } else if turn > 0.0 {
+            let blocked = ring.iter().enumerate().any(|(k, &v)| {
+                let pv = points[v];
+                let prev = points[ring[(k + n - 1) % n]];
+                let next = points[ring[(k + 1) % n]];
+                let reflex = (pv - prev).cross(next - pv) <= 0.0;
+                reflex && pv != pa && pv != pb && pv != pc && in_triangle(pv, pa, pb, pc)
+            });
+            if !blocked {
+                ret.push([a, b, c]);
+            }
+            !blocked
+        } else {
+            false
+        };
+
+        if clip || misses > n {
+            // nothing clipped after a full round means the polygon isn't simple, force progress
+            if !clip && turn > 0.0 {
+                ret.push([a, b, c]);
+            }
+            ring.remove(i);
+            misses = 0;
+            if i >= ring.len() {
+                i = 0;
+            }
+        } else {
+            i = (i + 1) % n;
+            misses += 1;
+        }
+    }
+
+    if let [a, b, c] = ring[..] {
+        if (points[b] - points[a]).cross(points[c] - points[b]) > 0.0 {
+            ret.push([a, b, c]);
+        }
+    }
+    ret
+}
+
+/// the smallest convex polygon containing every point, counter clockwise
+/// (Andrew's monotone chain)
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(2 * sorted.len());
+    // lower half left to right, then upper half right to left
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let floor = hull.len();
+        for p in pass {
+            while hull.len() >= floor + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if (b - a).cross(p - b) > 0.0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point starts the other half
+        hull.pop();
+    }
+    hull
+}
+
+/// splits the polygon into convex pieces (Hertel-Mehlhorn)
+/// at most 4 times as many pieces as the optimal decomposition, usually much fewer
+/// ex: colliders for a concave shape
+pub fn convex_decomposition(points: &[Vec2]) -> Vec<Polygon> {
+    let mut pieces: Vec<Vec<usize>> = triangulate(points)
+        .into_iter()
+        .map(|t| t.to_vec())
+        .collect();
+
+    // removes the edges between pieces, as long as they stay convex
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(piece) = merge(points, &pieces[i], &pieces[j]) {
+                    pieces[i] = piece;
+                    pieces.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    pieces
+        .into_iter()
+        .map(|piece| Polygon::new(piece.into_iter().map(|i| points[i]).collect()))
+        .collect()
+}
+
+// the union of two counter clockwise pieces sharing an edge, if it's convex
+fn merge(points: &[Vec2], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (na, nb) = (a.len(), b.len());
+    for i in 0..na {
+        let (u, v) = (a[i], a[(i + 1) % na]);
+        // the same edge goes the other way in b
+        let Some(j) = (0..nb).find(|&j| b[j] == v && b[(j + 1) % nb] == u) else {
+            continue;
+        };
+
+        // a from v around to u, then b between u and v
+        let mut ret: Vec<usize> = (1..=na).map(|k| a[(i + k) % na]).collect();
+        ret.extend((2..nb).map(|k| b[(j + k) % nb]));
+
+        let poly: Vec<Vec2> = ret.iter().map(|&k| points[k]).collect();
+        return is_convex(&poly).then_some(ret);
+    }
+    None
+}
+
+/// removes the points that change the shape by less than `tolerance` (Ramer-Douglas-Peucker)
+/// `closed` polygons connect the last point to the first, otherwise both ends are kept
+/// ex: cleaning up a traced outline before making colliders
+pub fn simplify(points: &[Vec2], tolerance: f32, closed: bool) -> Vec<Vec2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    if !closed {
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+        mark_kept(points, tolerance, &mut keep);
+        return points
+            .iter()
+            .zip(keep)
+            .filter_map(|(&p, keep)| keep.then_some(p))
+            .collect();
+    }
+
+    // split at the point furthest from the first, which is always kept
+    let far = (1..points.len())
+        .max_by(|&a, &b| {
+            let da = points[a].distance(points[0]);
+            let db = points[b].distance(points[0]);
+            da.total_cmp(&db)
+        })
+        .unwrap();
+
+    let mut ret = simplify(&points[..=far], tolerance, false);
+    let mut back: Vec<Vec2> = points[far..].to_vec();
+    back.push(points[0]);
+    let back = simplify(&back, tolerance, false);
+    ret.extend_from_slice(&back[1..back.len() - 1]);
+    ret
+}
+
+// marks the points between the first and last that are needed
+fn mark_kept(points: &[Vec2], tolerance: f32, keep: &mut [bool]) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let chord = Segment::new(points[0], points[points.len() - 1]);
+    let (idx, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i + 1, chord.distance_to_point(p)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap();
+
+    if distance > tolerance {
+        keep[idx] = true;
+        mark_kept(&points[..=idx], tolerance, &mut keep[..=idx]);
+        mark_kept(&points[idx..], tolerance, &mut keep[idx..]);
+    }
+}
+
+/// the area covered by either polygon, as counter clockwise outlines and clockwise holes
+/// the largest polygon comes first, polygons touching at a single point stay separate
+/// Err if the polygons aren't simple
+pub fn union(a: &[Vec2], b: &[Vec2]) -> Result<Vec<Vec<Vec2>>, String> {
+    boolean(a, b, Operation::Union)
+}
+
+/// the area covered by both polygons, like `union`
+pub fn intersection(a: &[Vec2], b: &[Vec2]) -> Result<Vec<Vec<Vec2>>, String> {
+    boolean(a, b, Operation::Intersection)
+}
+
+/// the area covered by `a` but not `b`, like `union`
+/// ex: cutting a crater out of destructible terrain
+pub fn difference(a: &[Vec2], b: &[Vec2]) -> Result<Vec<Vec<Vec2>>, String> {
+    boolean(a, b, Operation::Difference)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+// the edges of both polygons are split wherever they meet the other polygon,
+// with points closer than `CLOSE` merged, so that shared vertices and edges are exactly the same,
+// then each piece of edge is kept depending on the side of the other polygon it's on
+// and the kept pieces are joined into the result
+fn boolean(a: &[Vec2], b: &[Vec2], op: Operation) -> Result<Vec<Vec<Vec2>>, String> {
+    const CLOSE: f32 = 1e-5;
+
+    let bounds = [a, b]
+        .into_iter()
+        .flatten()
+        .fold(None, |acc: Option<Aabb>, &p| {
+            Some(acc.map_or(Aabb::new(p, p), |b| b.union(&Aabb::new(p, p))))
+        });
+    let scale = bounds
+        .map_or(0.0, |b| b.size().x.max(b.size().y))
+        .max(EPSILON);
+    let tolerance = CLOSE * scale;
+
+    // polygons without area are empty
+    let empty = |p: &[Vec2]| p.len() < 3 || signed_area(p).abs() <= EPSILON * scale * scale;
+    let mut a = if empty(a) { Vec::new() } else { a.to_vec() };
+    let mut b = if empty(b) { Vec::new() } else { b.to_vec() };
+    set_winding(&mut a, Winding::CounterClockwise);
+    set_winding(&mut b, Winding::CounterClockwise);
+
+    // every vertex, then every crossing between an edge of a and an edge of b
+    let mut points: Vec<Vec2> = Vec::new();
+    let ring_a: Vec<usize> = a.iter().map(|&p| snap(&mut points, p, tolerance)).collect();
+    let ring_b: Vec<usize> = b.iter().map(|&p| snap(&mut points, p, tolerance)).collect();
+    for (pa, qa) in edges(&a) {
+        for (pb, qb) in edges(&b) {
+            let (r, s) = (qa - pa, qb - pb);
+            let denom = r.cross(s);
+            // parallel edges meet at their vertices
+            if denom.abs() <= EPSILON * r.len() * s.len() {
+                continue;
+            }
+            let t = (pb - pa).cross(s) / denom;
+            let u = (pb - pa).cross(r) / denom;
+            if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                snap(&mut points, pa + t * r, tolerance);
+            }
+        }
+    }
+
+    let split_a = split_edges(&points, &ring_a, tolerance);
+    let split_b = split_edges(&points, &ring_b, tolerance);
+    let set_b: HashSet<(usize, usize)> = split_b.iter().copied().collect();
+    let set_a: HashSet<(usize, usize)> = split_a.iter().copied().collect();
+    let midpoint = |(u, v): (usize, usize)| 0.5 * (points[u] + points[v]);
+
+    let mut kept = Vec::new();
+    for &(u, v) in &split_a {
+        let keep = if set_b.contains(&(u, v)) {
+            // both polygons on the same side
+            op != Operation::Difference
+        } else if set_b.contains(&(v, u)) {
+            // the polygons on either side
+            op == Operation::Difference
+        } else {
+            let inside = contains_point(&b, midpoint((u, v)));
+            inside == (op == Operation::Intersection)
+        };
+        if keep {
+            kept.push((u, v));
+        }
+    }
+    for &(u, v) in &split_b {
+        if set_a.contains(&(u, v)) || set_a.contains(&(v, u)) {
+            continue;
+        }
+        let inside = contains_point(&a, midpoint((u, v)));
+        match op {
+            Operation::Union if !inside => kept.push((u, v)),
+            Operation::Intersection if inside => kept.push((u, v)),
+            // the part of b inside a is a hole, walked the other way
+            Operation::Difference if inside => kept.push((v, u)),
+            _ => {}
+        }
+    }
+
+    let mut ret: Vec<Vec<Vec2>> = join_edges(&points, &kept)?
+        .into_iter()
+        .map(|ring| remove_collinear(ring.iter().map(|&i| points[i]).collect(), tolerance))
+        .filter(|p| !empty(p))
+        .collect();
+    ret.sort_by(|a, b| signed_area(b).abs().total_cmp(&signed_area(a).abs()));
+    Ok(ret)
+}
+
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let n = points.len();
+    (0..n).map(move |i| (points[i], points[(i + 1) % n]))
+}
+
+// the index of the point within `tolerance` of `p`, added if there is none
+fn snap(points: &mut Vec<Vec2>, p: Vec2, tolerance: f32) -> usize {
+    match points.iter().position(|&q| (q - p).len() <= tolerance) {
+        Some(i) => i,
+        None => {
+            points.push(p);
+            points.len() - 1
+        }
+    }
+}
+
+// the edges of the ring, split at every point lying on them
+fn split_edges(points: &[Vec2], ring: &[usize], tolerance: f32) -> Vec<(usize, usize)> {
+    let n = ring.len();
+    let mut ret = Vec::new();
+    for i in 0..n {
+        let (u, v) = (ring[i], ring[(i + 1) % n]);
+        if u == v {
+            continue;
+        }
+        let edge = Segment::new(points[u], points[v]);
+        let d = edge.b - edge.a;
+        let mut on_edge: Vec<(f32, usize)> = (0..points.len())
+            .filter(|&k| k != u && k != v && edge.distance_to_point(points[k]) <= tolerance)
+            .map(|k| ((points[k] - edge.a).dot(d) / d.len2(), k))
+            .collect();
+        on_edge.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut from = u;
+        for to in on_edge.into_iter().map(|(_, k)| k).chain([v]) {
+            ret.push((from, to));
+            from = to;
+        }
+    }
+    ret
+}
+
+// the rings made of the edges, where every point has as many edges in as out
+// at a point with several ways out, the sharpest left turn keeps to the same polygon
+fn join_edges(points: &[Vec2], edges: &[(usize, usize)]) -> Result<Vec<Vec<usize>>, String> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (e, &(u, _)) in edges.iter().enumerate() {
+        outgoing.entry(u).or_default().push(e);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut ret = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let mut ring = Vec::new();
+        let mut e = start;
+        loop {
+            used[e] = true;
+            let (u, v) = edges[e];
+            ring.push(u);
+            if v == edges[start].0 {
+                break;
+            }
+            let dir = points[v] - points[u];
+            let turn = |&&e: &&usize| {
+                let d = points[edges[e].1] - points[v];
+                dir.cross(d).atan2(dir.dot(d))
+            };
+            let next = outgoing[&v]
+                .iter()
+                .filter(|&&e| !used[e])
+                .max_by(|a, b| turn(a).total_cmp(&turn(b)));
+            e = *next.ok_or("Bad polygon: the outlines can't be joined, are they simple?")?;
+        }
+        split_loops(ring, &mut ret);
+    }
+    Ok(ret)
+}
+
+// a ring going through the same point twice, split into simple rings
+fn split_loops(ring: Vec<usize>, out: &mut Vec<Vec<usize>>) {
+    for j in 1..ring.len() {
+        if let Some(i) = ring[..j].iter().position(|&p| p == ring[j]) {
+            let mut outer = ring[..i].to_vec();
+            outer.extend_from_slice(&ring[j..]);
+            split_loops(ring[i..j].to_vec(), out);
+            split_loops(outer, out);
+            return;
+        }
+    }
+    out.push(ring);
+}
+
+// the points where the outline goes straight on, ex: where edges were split
+fn remove_collinear(mut points: Vec<Vec2>, tolerance: f32) -> Vec<Vec2> {
+    let mut i = 0;
+    let mut kept = 0;
+    while points.len() >= 3 && kept < points.len() {
+        let n = points.len();
+        let (prev, p, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+        if Segment::new(prev, next).distance_to_point(p) <= tolerance {
+            points.remove(i);
+            kept = 0;
+            i %= points.len().max(1);
+        } else {
+            i = (i + 1) % n;
+            kept += 1;
+        }
+    }
+    points
+}
+
+/// the outlines of the pixels with an alpha above `threshold`, holes included
+/// in pixels, with the origin at the bottom left corner like textures
+/// the outlines follow the pixel edges, use `simplify` to smooth them
+/// pixels touching only by a corner get separate outlines, the largest outline comes first
+///
+/// ex: a collider for a sprite
+/// let image = image::open("deer.png")?.to_rgba8();
+/// let outline = polygon::simplify(&polygon::trace_outlines(&image, 0)[0], 1.0, true);
+/// let colliders = polygon::convex_decomposition(&outline);
+pub fn trace_outlines(image: &RgbaImage, threshold: u8) -> Vec<Vec<Vec2>> {
+    let (w, h) = (image.width() as i32, image.height() as i32);
+    let solid = |x: i32, y: i32| {
+        (0..w).contains(&x)
+            && (0..h).contains(&y)
+            && image.get_pixel(x as u32, (h - 1 - y) as u32)[3] > threshold
+    };
+
+    // the edges between solid and empty pixels, going counter clockwise around solid ones
+    let mut edges: BTreeMap<(i32, i32), Vec<(i32, i32)>> = BTreeMap::new();
+    let mut add = |from: (i32, i32), to: (i32, i32)| edges.entry(from).or_default().push(to);
+    for y in 0..h {
+        for x in 0..w {
+            if !solid(x, y) {
+                continue;
+            }
+            if !solid(x, y - 1) {
+                add((x, y), (x + 1, y));
+            }
+            if !solid(x + 1, y) {
+                add((x + 1, y), (x + 1, y + 1));
+            }
+            if !solid(x, y + 1) {
+                add((x + 1, y + 1), (x, y + 1));
+            }
+            if !solid(x - 1, y) {
+                add((x, y + 1), (x, y));
+            }
+        }
+    }
+
+    let mut ret = Vec::new();
+    // corners shared by diagonal pixels have two ways out, never start there
+    while let Some(start) = edges.iter().find(|e| e.1.len() == 1).map(|e| *e.0) {
+        let mut from = start;
+        let mut loop_points = vec![from];
+        loop {
+            let outgoing = edges.get_mut(&from).unwrap();
+            // at corners shared by diagonal pixels, turn left to stay around the same pixel
+            let dir = loop_points
+                .len()
+                .checked_sub(2)
+                .map(|i| sub(from, loop_points[i]));
+            let pick = (0..outgoing.len())
+                .max_by_key(|&i| dir.map_or(0, |d| cross(d, sub(outgoing[i], from))))
+                .unwrap();
+            let to = outgoing.swap_remove(pick);
+            if outgoing.is_empty() {
+                edges.remove(&from);
+            }
+
+            if to == start {
+                break;
+            }
+            loop_points.push(to);
+            from = to;
+        }
+
+        // only keep the corners
+        let n = loop_points.len();
+        let corners: Vec<Vec2> = (0..n)
+            .filter(|&i| {
+                let (prev, p, next) = (
+                    loop_points[(i + n - 1) % n],
+                    loop_points[i],
+                    loop_points[(i + 1) % n],
+                );
+                cross(sub(p, prev), sub(next, p)) != 0
+            })
+            .map(|i| Vec2::new(loop_points[i].0 as f32, loop_points[i].1 as f32))
+            .collect();
+        ret.push(corners);
+    }
+
+    ret.sort_by(|a, b| signed_area(b).abs().total_cmp(&signed_area(a).abs()));
+    ret
+}
+
+fn sub(a: (i32, i32), b: (i32, i32)) -> (i32, i32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn cross(a: (i32, i32), b: (i32, i32)) -> i32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<Vec2> {
+        vec![
+            Vec2::new(x0, y0),
+            Vec2::new(x1, y0),
+            Vec2::new(x1, y1),
+            Vec2::new(x0, y1),
+        ]
+    }
+
+    // holes count negatively
+    fn area(polygons: &[Vec<Vec2>]) -> f32 {
+        polygons.iter().map(|p| signed_area(p)).sum()
+    }
+
+    fn triangles_area(points: &[Vec2], triangles: &[[usize; 3]]) -> f32 {
+        let each = triangles.iter().map(|t| signed_area(&t.map(|i| points[i])));
+        each.inspect(|&a| assert!(a > 0.0)).sum()
+    }
+
+    // the same polygon, from any starting point
+    fn same_ring(a: &[Vec2], b: &[Vec2]) -> bool {
+        a.len() == b.len()
+            && (0..b.len()).any(|k| (0..a.len()).all(|i| a[i] == b[(i + k) % b.len()]))
+    }
+
+    #[test]
+    fn overlapping() {
+        let a = rect(0.0, 0.0, 2.0, 2.0);
+        let b = rect(1.0, 1.0, 3.0, 3.0);
+        let either = union(&a, &b).unwrap();
+        assert_eq!(either.len(), 1);
+        assert_eq!(either[0].len(), 8);
+        assert_eq!(area(&either), 7.0);
+        let both = intersection(&a, &b).unwrap();
+        assert!(same_ring(&both[0], &rect(1.0, 1.0, 2.0, 2.0)));
+        assert_eq!(area(&difference(&a, &b).unwrap()), 3.0);
+
+        // apart, and one inside the other
+        let far = rect(5.0, 5.0, 6.0, 6.0);
+        assert_eq!(union(&a, &far).unwrap().len(), 2);
+        assert!(intersection(&a, &far).unwrap().is_empty());
+        let inner = rect(0.5, 0.5, 1.5, 1.5);
+        let holed = difference(&a, &inner).unwrap();
+        assert_eq!(holed.len(), 2);
+        assert_eq!(winding(&holed[0]), Winding::CounterClockwise);
+        assert_eq!(winding(&holed[1]), Winding::Clockwise);
+        assert_eq!(area(&holed), 3.0);
+        assert!(difference(&inner, &a).unwrap().is_empty());
+    }
+
+    #[test]
+    fn shared_edges() {
+        // a corner cut out, with two edges along the outline
+        let big = rect(0.0, 0.0, 2.0, 2.0);
+        let corner = rect(0.0, 0.0, 1.0, 1.0);
+        let l = difference(&big, &corner).unwrap();
+        assert_eq!(l.len(), 1);
+        let expected = [
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+            (1.0, 1.0),
+        ];
+        assert!(same_ring(&l[0], &expected.map(|(x, y)| Vec2::new(x, y))));
+        assert!(same_ring(&intersection(&big, &corner).unwrap()[0], &corner));
+        assert!(same_ring(&union(&big, &corner).unwrap()[0], &big));
+
+        // side by side, merged into one
+        let left = rect(0.0, 0.0, 1.0, 1.0);
+        let right = rect(1.0, 0.0, 2.0, 1.0);
+        let merged = union(&left, &right).unwrap();
+        assert_eq!(merged.len(), 1);
+        assert!(same_ring(&merged[0], &rect(0.0, 0.0, 2.0, 1.0)));
+        assert!(intersection(&left, &right).unwrap().is_empty());
+        assert!(same_ring(&difference(&left, &right).unwrap()[0], &left));
+
+        // along part of an edge
+        let shifted = rect(1.0, 0.5, 2.0, 1.5);
+        let merged = union(&left, &shifted).unwrap();
+        assert_eq!((merged.len(), merged[0].len(), area(&merged)), (1, 8, 2.0));
+
+        // touching at a corner only
+        let diagonal = rect(1.0, 1.0, 2.0, 2.0);
+        let apart = union(&left, &diagonal).unwrap();
+        assert_eq!(apart.len(), 2);
+        assert!(apart.iter().all(|p| p.len() == 4 && signed_area(p) == 1.0));
+        assert!(intersection(&left, &diagonal).unwrap().is_empty());
+    }
+
+    #[test]
+    fn identical() {
+        let a = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 2.0),
+            Vec2::new(1.5, 0.5),
+            Vec2::new(0.0, 2.0),
+        ];
+        assert!(same_ring(&union(&a, &a).unwrap()[0], &a));
+        assert!(same_ring(&intersection(&a, &a).unwrap()[0], &a));
+        assert!(difference(&a, &a).unwrap().is_empty());
+
+        // with the other winding
+        let mut reversed = a.clone();
+        reversed.reverse();
+        assert!(same_ring(&intersection(&a, &reversed).unwrap()[0], &a));
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let a = rect(0.0, 0.0, 1.0, 1.0);
+        let line = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(1.0, 1.0),
+        ];
+        assert!(same_ring(&union(&a, &line).unwrap()[0], &a));
+        assert!(same_ring(&difference(&a, &line).unwrap()[0], &a));
+        assert!(intersection(&a, &line).unwrap().is_empty());
+        assert!(union(&[], &[Vec2::zero()]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn random_area_identities() {
+        let mut rng = Rng::new(42);
+        for i in 0..500 {
+            // on a small grid for many shared edges and vertices, or rotated
+            let (a, b) = if i % 2 == 0 {
+                let mut corner = || rng.range(0..4) as f32;
+                let (x, y) = (corner(), corner());
+                let a = rect(x, y, x + 1.0 + corner(), y + 1.0 + corner());
+                let (x, y) = (corner(), corner());
+                (a, rect(x, y, x + 1.0 + corner(), y + 1.0 + corner()))
+            } else {
+                let mut ngon = || {
+                    let center = Vec2::new(rng.range(0.0..2.0), rng.range(0.0..2.0));
+                    let (n, radius, start) = (
+                        rng.range(3..8),
+                        rng.range(0.5..2.0),
+                        rng.range(0.0..360.0f32),
+                    );
+                    (0..n)
+                        .map(|k| {
+                            let angle = (start + k as f32 * 360.0 / n as f32).to_radians();
+                            center + radius * Vec2::new(angle.cos(), angle.sin())
+                        })
+                        .collect::<Vec<_>>()
+                };
+                (ngon(), ngon())
+            };
+
+            let both = area(&intersection(&a, &b).unwrap());
+            let either = area(&union(&a, &b).unwrap());
+            let only_a = area(&difference(&a, &b).unwrap());
+            let (area_a, area_b) = (signed_area(&a), signed_area(&b));
+            assert!(
+                (either - (area_a + area_b - both)).abs() < 1e-3,
+                "{a:?} {b:?}"
+            );
+            assert!((only_a - (area_a - both)).abs() < 1e-3, "{a:?} {b:?}");
+            assert!(both <= area_a.min(area_b) + 1e-3);
+        }
+    }
+
+    #[test]
+    fn triangulation() {
+        // a U shape, both windings
+        let mut u = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 3.0),
+            Vec2::new(2.0, 3.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ];
+        for _ in 0..2 {
+            let triangles = triangulate(&u);
+            assert_eq!(triangles.len(), u.len() - 2);
+            assert_eq!(triangles_area(&u, &triangles), signed_area(&u).abs());
+            u.reverse();
+        }
+
+        let holes = vec![rect(1.0, 1.0, 2.0, 2.0), rect(2.5, 2.5, 3.0, 3.5)];
+        let (points, triangles) = triangulate_with_holes(&rect(0.0, 0.0, 4.0, 4.0), &holes);
+        assert_eq!(points.len(), 12);
+        assert_eq!(triangles_area(&points, &triangles), 16.0 - 1.0 - 0.5);
+    }
+
+    #[test]
+    fn decomposition() {
+        let star: Vec<Vec2> = (0..10)
+            .map(|k| {
+                let angle = (k as f32 * 36.0).to_radians();
+                let radius = if k % 2 == 0 { 2.0 } else { 0.8 };
+                radius * Vec2::new(angle.cos(), angle.sin())
+            })
+            .collect();
+        let pieces = convex_decomposition(&star);
+        assert!(pieces.len() >= 5 && pieces.len() < 8);
+        assert!(pieces.iter().all(|p| is_convex(&p.points)));
+        let total: f32 = pieces.iter().map(|p| signed_area(&p.points)).sum();
+        assert!((total - signed_area(&star)).abs() < 1e-4);
+
+        // already convex
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(convex_decomposition(&square).len(), 1);
+    }
+}
@@ -0,0 +1,403 @@
+use std::ops::Range;
+
+use super::Aabb;
+use super::Shape;
+use super::Vec2;
+
+/// A small, fast, seedable random number generator (PCG32, XSH RR variant)
+///
+/// the same seed gives the same sequence on every platform,
+/// everything is computed with integer math or exactly rounded float operations
+/// so recording the seed (or the state, see `to_bytes`) is enough to replay a game
+///
+/// ex: Rng::with_stream(42, 54).next_u32() is 0xa15c02b7, like the PCG reference implementation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rng {
+    state: u64,
+    // must be odd
+    inc: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    /// generators with the same seed but different streams give unrelated sequences
+    /// ex: one stream for the level generation and one for the gameplay
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    /// the full state, to save and restore the generator mid-sequence
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut ret = [0; 16];
+        ret[..8].copy_from_slice(&self.state.to_le_bytes());
+        ret[8..].copy_from_slice(&self.inc.to_le_bytes());
+        ret
+    }
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let (state, inc) = bytes.split_at(8);
+        Self {
+            state: u64::from_le_bytes(state.try_into().unwrap()),
+            inc: u64::from_le_bytes(inc.try_into().unwrap()) | 1,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let hi = self.next_u32() as u64;
+        let lo = self.next_u32() as u64;
+        (hi << 32) | lo
+    }
+
+    /// uniform in [0, 1)
+    pub fn float(&mut self) -> f32 {
+        // 24 bits, as many as an f32 can hold exactly
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// uniform in [0, 1)
+    pub fn double(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniform in [start, end), panics if the range is empty
+    /// ex: rng.range(1..7) rolls a die, rng.range(-1.0..1.0)
+    pub fn range<T: Uniform>(&mut self, range: Range<T>) -> T {
+        assert!(range.start < range.end, "Empty range");
+        T::sample(self, range.start, range.end)
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    /// true with the given probability, in [0, 1]
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.float() < probability
+    }
+
+    /// None if `items` is empty
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.range(0..items.len()))
+    }
+
+    /// picks an index with a probability proportional to its weight
+    /// None if there are no positive weights
+    /// ex: loot tables, rng.weighted(&[70.0, 25.0, 5.0]) gives 2 about 5% of the time
+    pub fn weighted(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().filter(|&&w| w > 0.0).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = self.float() * total;
+        let mut last = None;
+        for (i, &w) in weights.iter().enumerate().filter(|(_, &w)| w > 0.0) {
+            if pick < w {
+                return Some(i);
+            }
+            pick -= w;
+            last = Some(i);
+        }
+        // rounding left a sliver past the last weight
+        last
+    }
+
+    /// like `weighted`, with the weight of each item given by `weight`
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        let weights: Vec<f32> = items.iter().map(weight).collect();
+        self.weighted(&weights).map(|i| &items[i])
+    }
+
+    /// every permutation is equally likely
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..i + 1));
+        }
+    }
+
+    /// a random direction
+    pub fn unit_vec2(&mut self) -> Vec2 {
+        // rejection sampling instead of sin/cos, which aren't exact on every platform
+        loop {
+            let v = self.in_unit_square();
+            let len2 = v.len2();
+            if len2 > 1e-4 && len2 <= 1.0 {
+                return v / len2.sqrt();
+            }
+        }
+    }
+
+    /// uniformly distributed inside the circle
+    pub fn in_circle(&mut self, center: Vec2, radius: f32) -> Vec2 {
+        loop {
+            let v = self.in_unit_square();
+            if v.len2() <= 1.0 {
+                return center + radius * v;
+            }
+        }
+    }
+
+    /// uniformly distributed inside the rectangle
+    pub fn in_rect(&mut self, rect: &Aabb) -> Vec2 {
+        let t = Vec2::new(self.float(), self.float());
+        rect.min + t * rect.size()
+    }
+
+    /// random points in the rectangle, all at least `min_distance` apart,
+    /// and packed so that no more could be added (Bridson's algorithm)
+    /// ex: scattering trees or rocks without clumps
+    /// `attempts` is how hard to try to fit points around each one, 30 is usual
+    pub fn poisson_disk(&mut self, rect: &Aabb, min_distance: f32, attempts: u32) -> Vec<Vec2> {
+        let size = rect.size();
+        if min_distance <= 0.0 || size.x <= 0.0 || size.y <= 0.0 {
+            return Vec::new();
+        }
+
+        // a cell fits at most one point
+        let cell = min_distance / std::f32::consts::SQRT_2;
+        let cols = (size.x / cell).ceil() as usize;
+        let rows = (size.y / cell).ceil() as usize;
+        let mut grid: Vec<Option<usize>> = vec![None; cols * rows];
+        let cell_of = |p: Vec2| {
+            let c = ((p - rect.min) / cell).cast::<u32>();
+            ((c.x as usize).min(cols - 1), (c.y as usize).min(rows - 1))
+        };
+
+        let mut points = Vec::new();
+        let mut active = Vec::new();
+
+        let first = self.in_rect(rect);
+        let (x, y) = cell_of(first);
+        grid[y * cols + x] = Some(0);
+        points.push(first);
+        active.push(0);
+
+        while !active.is_empty() {
+            let idx = self.range(0..active.len());
+            let center = points[active[idx]];
+            let mut found = false;
+
+            for _ in 0..attempts {
+                // somewhere between 1 and 2 times the distance away
+                let distance = min_distance * (1.0 + self.float());
+                let p = center + distance * self.unit_vec2();
+                if !rect.contains_point(p) {
+                    continue;
+                }
+
+                let (x, y) = cell_of(p);
+                let near = (y.saturating_sub(2)..(y + 3).min(rows)).any(|ny| {
+                    (x.saturating_sub(2)..(x + 3).min(cols)).any(|nx| {
+                        grid[ny * cols + nx].is_some_and(|i| points[i].distance(p) < min_distance)
+                    })
+                });
+                if near {
+                    continue;
+                }
+
+                grid[y * cols + x] = Some(points.len());
+                active.push(points.len());
+                points.push(p);
+                found = true;
+                break;
+            }
+
+            if !found {
+                active.swap_remove(idx);
+            }
+        }
+
+        points
+    }
+
+    // uniform in [-1, 1) on both axes
+    fn in_unit_square(&mut self) -> Vec2 {
+        Vec2::new(2.0 * self.float() - 1.0, 2.0 * self.float() - 1.0)
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(self.inc);
+    }
+}
+
+/// A number that `Rng::range` can pick
+pub trait Uniform: PartialOrd + Sized {
+    /// uniform in [low, high), with low < high
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self;
+}
+
+// unbiased, using Lemire's multiply and reject method
+fn below_u32(rng: &mut Rng, span: u32) -> u32 {
+    let mut m = rng.next_u32() as u64 * span as u64;
+    if (m as u32) < span {
+        let threshold = span.wrapping_neg() % span;
+        while (m as u32) < threshold {
+            m = rng.next_u32() as u64 * span as u64;
+        }
+    }
+    (m >> 32) as u32
+}
+
+fn below_u64(rng: &mut Rng, span: u64) -> u64 {
+    let mut m = rng.next_u64() as u128 * span as u128;
+    if (m as u64) < span {
+        let threshold = span.wrapping_neg() % span;
+        while (m as u64) < threshold {
+            m = rng.next_u64() as u128 * span as u128;
+        }
+    }
+    (m >> 64) as u64
+}
+
+impl Uniform for u32 {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        low + below_u32(rng, high - low)
+    }
+}
+
+impl Uniform for i32 {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        let span = high.wrapping_sub(low) as u32;
+        low.wrapping_add(below_u32(rng, span) as i32)
+    }
+}
+
+impl Uniform for u64 {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        low + below_u64(rng, high - low)
+    }
+}
+
+// always goes through u64, so the sequence doesn't depend on the pointer size
+impl Uniform for usize {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        low + below_u64(rng, (high - low) as u64) as usize
+    }
+}
+
+impl Uniform for f32 {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        loop {
+            let v = low + rng.float() * (high - low);
+            // rounding may land exactly on `high`
+            if v < high {
+                return v;
+            }
+        }
+    }
+}
+
+impl Uniform for f64 {
+    fn sample(rng: &mut Rng, low: Self, high: Self) -> Self {
+        loop {
+            let v = low + rng.double() * (high - low);
+            if v < high {
+                return v;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // recorded values: a change here breaks every replay and generated level
+    // the float values are exact, the whole computation is rounded the same way everywhere
+
+    #[test]
+    fn reference_sequence() {
+        // from the PCG reference implementation, pcg32-demo
+        let mut rng = Rng::with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        assert_eq!(expected.map(|_| rng.next_u32()), expected);
+    }
+
+    #[test]
+    fn next_u64() {
+        let mut rng = Rng::new(39);
+        let expected = [
+            0xe14207ccc95bfe83,
+            0x4f685886fc72a24e,
+            0x670235681ee31acf,
+            0x5cca8e86cd682530,
+        ];
+        assert_eq!(expected.map(|_| rng.next_u64()), expected);
+    }
+
+    #[test]
+    fn range() {
+        let mut rng = Rng::new(39);
+        assert_eq!([0; 6].map(|_| rng.range(1..7)), [6, 5, 2, 6, 3, 1]);
+        assert_eq!(
+            [0; 3].map(|_| rng.range(-1.0f32..1.0)),
+            [-0.27506852, 0.60474074, -0.642434]
+        );
+        assert_eq!(
+            [0; 2].map(|_| rng.range(0.0f64..10.0)),
+            [9.194712430787266, 4.958119884661371]
+        );
+    }
+
+    #[test]
+    fn unit_vec2() {
+        let mut rng = Rng::new(39);
+        let expected = [
+            (1061970229, 1058678950),
+            (3196007530, 3212307476),
+            (3201563764, 1063847642),
+        ];
+        let actual = expected.map(|_| {
+            let v = rng.unit_vec2();
+            (v.x.to_bits(), v.y.to_bits())
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn shuffle() {
+        let mut rng = Rng::new(39);
+        let mut items: Vec<u32> = (0..10).collect();
+        rng.shuffle(&mut items);
+        assert_eq!(items, [6, 4, 0, 5, 7, 1, 9, 3, 2, 8]);
+    }
+
+    #[test]
+    fn saved_state() {
+        let mut rng = Rng::new(39);
+        rng.next_u32();
+        let mut restored = Rng::from_bytes(rng.to_bytes());
+        assert_eq!(restored.next_u64(), rng.next_u64());
+    }
+}
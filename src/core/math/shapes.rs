@@ -0,0 +1,1029 @@
+// geometric primitives and the queries between them
+// every shape is closed: points on the boundary are contained and touching shapes overlap
+use super::polygon::signed_area;
+use super::Vec2;
+
+const EPSILON: f32 = 1e-6;
+
+/// The result of a successful ray cast
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// distance along the ray, in units of the ray's direction
+    pub distance: f32,
+    pub point: Vec2,
+    /// the surface normal at the hit point, facing against the ray
+    /// if the ray starts inside the shape, the distance is 0 and the normal is the reverse of the ray
+    pub normal: Vec2,
+}
+
+/// Queries shared by every shape
+pub trait Shape {
+    fn area(&self) -> f32;
+    fn aabb(&self) -> Aabb;
+    fn contains_point(&self, point: Vec2) -> bool;
+    /// the point on or inside the shape that is closest to `point`
+    fn closest_point(&self, point: Vec2) -> Vec2;
+    /// finds the first hit along the ray within `max_distance`
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit>;
+
+    fn distance_to_point(&self, point: Vec2) -> f32 {
+        (point - self.closest_point(point)).len()
+    }
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+pub type Rect = Aabb;
+
+impl Aabb {
+    /// the corners may be given in any order
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn from_center(center: Vec2, half_extents: Vec2) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    /// the smallest box containing every point, None if there are no points
+    pub fn from_points(points: &[Vec2]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        Some(rest.iter().fold(Self::new(*first, *first), |acc, &p| {
+            acc.union(&Self::new(p, p))
+        }))
+    }
+
+    pub fn center(&self) -> Vec2 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn half_extents(&self) -> Vec2 {
+        0.5 * self.size()
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        let s = self.size();
+        2.0 * (s.x + s.y)
+    }
+
+    /// whether `other` lies completely inside
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// the overlapping area, None if the boxes don't overlap
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        self.overlaps(other).then(|| Aabb {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        })
+    }
+
+    /// the smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// grows the box by `margin` on every side
+    pub fn expand(&self, margin: f32) -> Aabb {
+        let m = Vec2::new(margin, margin);
+        Aabb {
+            min: self.min - m,
+            max: self.max + m,
+        }
+    }
+
+    /// the corners in counter clockwise order, starting at `min`
+    pub fn corners(&self) -> [Vec2; 4] {
+        [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            self.max,
+            Vec2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn to_polygon(&self) -> Polygon {
+        Polygon {
+            points: self.corners().to_vec(),
+        }
+    }
+}
+
+impl Shape for Aabb {
+    fn area(&self) -> f32 {
+        let s = self.size();
+        s.x * s.y
+    }
+
+    fn aabb(&self) -> Aabb {
+        *self
+    }
+
+    fn contains_point(&self, point: Vec2) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.max(self.min).min(self.max)
+    }
+
+    // slab test
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.contains_point(ray.origin) {
+            return Some(ray.inside_hit());
+        }
+
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut normal = Vec2::default();
+
+        for axis in 0..2 {
+            let (o, d) = (ray.origin[axis], ray.direction[axis]);
+            let (lo, hi) = (self.min[axis], self.max[axis]);
+
+            if d.abs() < EPSILON {
+                // parallel to the slab, must already be between its sides
+                if o < lo || o > hi {
+                    return None;
+                }
+                continue;
+            }
+
+            let (t0, t1) = ((lo - o) / d, (hi - o) / d);
+            let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if near > t_enter {
+                t_enter = near;
+                normal = Vec2::default();
+                normal[axis] = -d.signum();
+            }
+            t_exit = t_exit.min(far);
+        }
+
+        (t_enter <= t_exit && t_enter >= 0.0 && t_enter <= max_distance).then(|| RayHit {
+            distance: t_enter,
+            point: ray.at(t_enter),
+            normal,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn overlaps(&self, other: &Circle) -> bool {
+        let r = self.radius + other.radius;
+        (other.center - self.center).len2() <= r * r
+    }
+
+    pub fn overlaps_aabb(&self, aabb: &Aabb) -> bool {
+        (aabb.closest_point(self.center) - self.center).len2() <= self.radius * self.radius
+    }
+
+    /// the points where the two circles' outlines cross
+    /// empty if they don't touch, or if they are the same circle
+    pub fn intersection_points(&self, other: &Circle) -> Vec<Vec2> {
+        let d = other.center - self.center;
+        let dist = d.len();
+        let (r0, r1) = (self.radius, other.radius);
+        if dist < EPSILON || dist > r0 + r1 || dist < (r0 - r1).abs() {
+            return Vec::new();
+        }
+
+        // distance from self.center to the chord
+        let a = (r0 * r0 - r1 * r1 + dist * dist) / (2.0 * dist);
+        let h = (r0 * r0 - a * a).max(0.0).sqrt();
+        let dir = (1.0 / dist) * d;
+        let mid = self.center + a * dir;
+
+        if h < EPSILON {
+            vec![mid]
+        } else {
+            vec![mid + h * dir.perp(), mid - h * dir.perp()]
+        }
+    }
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f32 {
+        std::f32::consts::PI * self.radius * self.radius
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_center(self.center, Vec2::new(self.radius, self.radius))
+    }
+
+    fn contains_point(&self, point: Vec2) -> bool {
+        (point - self.center).len2() <= self.radius * self.radius
+    }
+
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        let d = point - self.center;
+        if d.len2() <= self.radius * self.radius {
+            point
+        } else {
+            self.center + (self.radius / d.len()) * d
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.contains_point(ray.origin) {
+            return Some(ray.inside_hit());
+        }
+
+        // |o + t*d - c|^2 = r^2, d is unit length
+        let m = ray.origin - self.center;
+        let b = m.dot(ray.direction);
+        let c = m.len2() - self.radius * self.radius;
+        let disc = b * b - c;
+        if b > 0.0 || disc < 0.0 {
+            return None;
+        }
+
+        let t = -b - disc.sqrt();
+        (t <= max_distance).then(|| {
+            let point = ray.at(t);
+            let normal = (point - self.center).normalize();
+            RayHit {
+                distance: t,
+                point,
+                // a circle of radius 0 has no outline to take the normal from
+                normal: if normal.is_zero() {
+                    -ray.direction
+                } else {
+                    normal
+                },
+            }
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Vec2,
+    pub b: Vec2,
+}
+
+impl Segment {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self { a, b }
+    }
+
+    pub fn len(&self) -> f32 {
+        (self.b - self.a).len()
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        (self.b - self.a).normalize()
+    }
+
+    /// the point's position along the segment, clamped to [0,1]
+    pub fn project(&self, point: Vec2) -> f32 {
+        let ab = self.b - self.a;
+        let l2 = ab.len2();
+        if l2 < EPSILON * EPSILON {
+            return 0.0;
+        }
+        ((point - self.a).dot(ab) / l2).clamp(0.0, 1.0)
+    }
+
+    pub fn at(&self, t: f32) -> Vec2 {
+        self.a + t * (self.b - self.a)
+    }
+
+    /// the point where the two segments cross
+    /// for overlapping collinear segments, the overlap's start is returned
+    pub fn intersection(&self, other: &Segment) -> Option<Vec2> {
+        let r = self.b - self.a;
+        let s = other.b - other.a;
+        let qp = other.a - self.a;
+        let denom = r.cross(s);
+
+        if denom.abs() < EPSILON {
+            // parallel
+            if qp.cross(r).abs() > EPSILON {
+                return None;
+            }
+
+            // collinear, find the overlap along r
+            let r2 = r.len2();
+            if r2 < EPSILON * EPSILON {
+                // self is a point
+                return (other.distance_to_point(self.a) < EPSILON).then_some(self.a);
+            }
+            let t0 = qp.dot(r) / r2;
+            let t1 = t0 + s.dot(r) / r2;
+            let (lo, hi) = (t0.min(t1).max(0.0), t0.max(t1).min(1.0));
+            return (lo <= hi).then(|| self.at(lo));
+        }
+
+        let t = qp.cross(s) / denom;
+        let u = qp.cross(r) / denom;
+        ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| self.at(t))
+    }
+
+    pub fn intersects(&self, other: &Segment) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// the points where the segment crosses the circle's outline
+    pub fn circle_intersections(&self, circle: &Circle) -> Vec<Vec2> {
+        let d = self.b - self.a;
+        let m = self.a - circle.center;
+        let a = d.len2();
+        if a < EPSILON * EPSILON {
+            return Vec::new();
+        }
+
+        let b = m.dot(d);
+        let c = m.len2() - circle.radius * circle.radius;
+        let disc = b * b - a * c;
+        if disc < 0.0 {
+            return Vec::new();
+        }
+
+        let sq = disc.sqrt();
+        let mut ret: Vec<Vec2> = [(-b - sq) / a, (-b + sq) / a]
+            .into_iter()
+            .filter(|t| (0.0..=1.0).contains(t))
+            .map(|t| self.at(t))
+            .collect();
+        ret.dedup_by(|p, q| (*p - *q).len2() < EPSILON * EPSILON);
+        ret
+    }
+
+    /// the closest points between the two segments (on self, on other)
+    pub fn closest_points(&self, other: &Segment) -> (Vec2, Vec2) {
+        if self.intersects(other) {
+            let p = self.intersection(other).unwrap();
+            return (p, p);
+        }
+
+        // without an intersection, one of the closest points is an endpoint
+        [
+            (self.a, other.closest_point(self.a)),
+            (self.b, other.closest_point(self.b)),
+            (self.closest_point(other.a), other.a),
+            (self.closest_point(other.b), other.b),
+        ]
+        .into_iter()
+        .min_by(|(a0, a1), (b0, b1)| (*a1 - *a0).len2().total_cmp(&(*b1 - *b0).len2()))
+        .unwrap()
+    }
+
+    pub fn distance_to_segment(&self, other: &Segment) -> f32 {
+        let (p, q) = self.closest_points(other);
+        (q - p).len()
+    }
+}
+
+impl Shape for Segment {
+    fn area(&self) -> f32 {
+        0.0
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::new(self.a, self.b)
+    }
+
+    fn contains_point(&self, point: Vec2) -> bool {
+        self.distance_to_point(point) < EPSILON
+    }
+
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        self.at(self.project(point))
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.contains_point(ray.origin) {
+            return Some(ray.inside_hit());
+        }
+
+        let s = self.b - self.a;
+        let denom = ray.direction.cross(s);
+        if denom.abs() < EPSILON {
+            // parallel rays graze the segment, count collinear ones as hitting the nearest end
+            if ray.direction.is_zero() || (self.a - ray.origin).cross(ray.direction).abs() > EPSILON
+            {
+                return None;
+            }
+            let ta = (self.a - ray.origin).dot(ray.direction);
+            let tb = (self.b - ray.origin).dot(ray.direction);
+            let t = ta.min(tb);
+            return (t >= 0.0 && t <= max_distance).then(|| ray.inside_hit_at(t));
+        }
+
+        let qp = self.a - ray.origin;
+        let t = qp.cross(s) / denom;
+        let u = qp.cross(ray.direction) / denom;
+        if t < 0.0 || t > max_distance || !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let n = s.perp().normalize();
+        Some(RayHit {
+            distance: t,
+            point: ray.at(t),
+            normal: if n.dot(ray.direction) > 0.0 { -n } else { n },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec2,
+    /// always unit length
+    pub direction: Vec2,
+}
+
+impl Ray {
+    /// `direction` is normalized
+    pub fn new(origin: Vec2, direction: Vec2) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vec2 {
+        self.origin + t * self.direction
+    }
+
+    fn inside_hit(&self) -> RayHit {
+        self.inside_hit_at(0.0)
+    }
+
+    fn inside_hit_at(&self, t: f32) -> RayHit {
+        RayHit {
+            distance: t,
+            point: self.at(t),
+            normal: -self.direction,
+        }
+    }
+}
+
+/// A segment with a radius, a.k.a. a stadium
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Capsule {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.a, self.b)
+    }
+
+    pub fn overlaps(&self, other: &Capsule) -> bool {
+        let r = self.radius + other.radius;
+        self.segment().distance_to_segment(&other.segment()) <= r
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        self.segment().distance_to_point(circle.center) <= self.radius + circle.radius
+    }
+}
+
+impl Shape for Capsule {
+    fn area(&self) -> f32 {
+        Circle::new(self.a, self.radius).area() + 2.0 * self.radius * self.segment().len()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::new(self.a, self.b).expand(self.radius)
+    }
+
+    fn contains_point(&self, point: Vec2) -> bool {
+        self.segment().distance_to_point(point) <= self.radius
+    }
+
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        Circle::new(self.segment().closest_point(point), self.radius).closest_point(point)
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.contains_point(ray.origin) {
+            return Some(ray.inside_hit());
+        }
+
+        // the two caps and the two sides
+        let offset = self.radius * self.segment().direction().perp();
+        let hits = [
+            Circle::new(self.a, self.radius).raycast(ray, max_distance),
+            Circle::new(self.b, self.radius).raycast(ray, max_distance),
+            Segment::new(self.a + offset, self.b + offset).raycast(ray, max_distance),
+            Segment::new(self.a - offset, self.b - offset).raycast(ray, max_distance),
+        ];
+
+        hits.into_iter()
+            .flatten()
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+/// A convex polygon, with points in counter clockwise order
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+impl Polygon {
+    /// the points are reordered counter clockwise if necessary
+    /// the points must form a convex polygon
+    pub fn new(mut points: Vec<Vec2>) -> Self {
+        if signed_area(&points) < 0.0 {
+            points.reverse();
+        }
+        Self { points }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        let n = self.points.len();
+        (0..n).map(move |i| Segment::new(self.points[i], self.points[(i + 1) % n]))
+    }
+
+    pub fn centroid(&self) -> Vec2 {
+        let a = signed_area(&self.points);
+        if a.abs() < EPSILON {
+            // degenerate, average the points instead
+            let n = self.points.len().max(1) as f32;
+            let sum = self.points.iter().fold(Vec2::default(), |acc, &p| acc + p);
+            return (1.0 / n) * sum;
+        }
+
+        let sum = self
+            .edges()
+            .fold(Vec2::default(), |acc, e| acc + e.a.cross(e.b) * (e.a + e.b));
+        (1.0 / (6.0 * a)) * sum
+    }
+
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        !self.has_separating_axis(other) && !other.has_separating_axis(self)
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        (self.closest_point(circle.center) - circle.center).len2() <= circle.radius * circle.radius
+    }
+
+    pub fn overlaps_aabb(&self, aabb: &Aabb) -> bool {
+        self.overlaps(&aabb.to_polygon())
+    }
+
+    /// the points where the segment crosses the polygon's outline
+    pub fn segment_intersections(&self, segment: &Segment) -> Vec<Vec2> {
+        self.edges()
+            .filter_map(|e| e.intersection(segment))
+            .collect()
+    }
+
+    // separating axis theorem, only this polygon's edge normals are checked
+    fn has_separating_axis(&self, other: &Polygon) -> bool {
+        self.edges().any(|e| {
+            let outward = -(e.b - e.a).perp();
+            other.points.iter().all(|&p| (p - e.a).dot(outward) > 0.0)
+        })
+    }
+}
+
+impl Shape for Polygon {
+    fn area(&self) -> f32 {
+        signed_area(&self.points).abs()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_points(&self.points).unwrap_or_default()
+    }
+
+    fn contains_point(&self, point: Vec2) -> bool {
+        match self.points.len() {
+            0 => false,
+            1 => (self.points[0] - point).len2() < EPSILON * EPSILON,
+            2 => self.edges().next().unwrap().contains_point(point),
+            _ => self
+                .edges()
+                .all(|e| (e.b - e.a).cross(point - e.a) >= -EPSILON),
+        }
+    }
+
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        if self.points.is_empty() || self.contains_point(point) {
+            return point;
+        }
+
+        self.edges()
+            .map(|e| e.closest_point(point))
+            .min_by(|a, b| (*a - point).len2().total_cmp(&(*b - point).len2()))
+            .unwrap_or(self.points[0])
+    }
+
+    // Cyrus-Beck clipping
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.points.len() < 3 {
+            return self
+                .edges()
+                .filter_map(|e| e.raycast(ray, max_distance))
+                .min_by(|a, b| a.distance.total_cmp(&b.distance));
+        }
+
+        if self.contains_point(ray.origin) {
+            return Some(ray.inside_hit());
+        }
+
+        let mut t_enter = 0.0;
+        let mut t_exit = max_distance;
+        let mut normal = None;
+
+        for e in self.edges() {
+            let outward = -(e.b - e.a).perp().normalize();
+            let num = (e.a - ray.origin).dot(outward);
+            let denom = ray.direction.dot(outward);
+
+            if denom.abs() < EPSILON {
+                // parallel to the edge, and outside of it
+                if num < 0.0 {
+                    return None;
+                }
+                continue;
+            }
+
+            let t = num / denom;
+            if denom < 0.0 {
+                if t > t_enter {
+                    t_enter = t;
+                    normal = Some(outward);
+                }
+            } else {
+                t_exit = t_exit.min(t);
+            }
+
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+
+        normal.map(|normal| RayHit {
+            distance: t_enter,
+            point: ray.at(t_enter),
+            normal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2::new(x, y)
+    }
+
+    fn hit_distance(shape: &impl Shape, ray: &Ray) -> Option<f32> {
+        shape.raycast(ray, 100.0).map(|hit| hit.distance)
+    }
+
+    fn assert_near(a: Option<f32>, b: f32) {
+        let a = a.expect("no hit");
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    // a ray that doesn't move
+    fn point_ray(origin: Vec2) -> Ray {
+        Ray::new(origin, Vec2::default())
+    }
+
+    #[test]
+    fn aabb() {
+        let b = Aabb::new(v(2.0, 2.0), v(0.0, 0.0));
+        assert_eq!(b.min, v(0.0, 0.0));
+        assert!(b.contains_point(v(1.0, 1.0)));
+        assert!(b.contains_point(v(2.0, 0.0)));
+        assert!(!b.contains_point(v(2.1, 1.0)));
+        assert!(b.contains(&Aabb::new(v(0.5, 0.5), v(2.0, 2.0))));
+
+        assert!(b.overlaps(&Aabb::new(v(2.0, 2.0), v(3.0, 3.0))));
+        assert!(!b.overlaps(&Aabb::new(v(2.1, 0.0), v(3.0, 3.0))));
+        assert_eq!(
+            b.intersection(&Aabb::new(v(1.0, 1.0), v(3.0, 3.0))),
+            Some(Aabb::new(v(1.0, 1.0), v(2.0, 2.0)))
+        );
+
+        let hit = b
+            .raycast(&Ray::new(v(-1.0, 1.0), v(1.0, 0.0)), 100.0)
+            .unwrap();
+        assert_eq!(
+            (hit.distance, hit.point, hit.normal),
+            (1.0, v(0.0, 1.0), v(-1.0, 0.0))
+        );
+        assert!(b
+            .raycast(&Ray::new(v(-1.0, 1.0), v(1.0, 0.0)), 0.5)
+            .is_none());
+        assert!(b
+            .raycast(&Ray::new(v(-1.0, 1.0), v(-1.0, 0.0)), 100.0)
+            .is_none());
+        assert!(b
+            .raycast(&Ray::new(v(-1.0, 3.0), v(1.0, 0.0)), 100.0)
+            .is_none());
+        assert_near(hit_distance(&b, &Ray::new(v(1.0, 1.0), v(1.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn degenerate_aabb() {
+        let point = Aabb::new(v(1.0, 1.0), v(1.0, 1.0));
+        assert!(point.contains_point(v(1.0, 1.0)));
+        assert!(!point.contains_point(v(1.0, 1.1)));
+        assert!(point.overlaps(&Aabb::new(v(0.0, 0.0), v(1.0, 1.0))));
+        assert_near(
+            hit_distance(&point, &Ray::new(v(1.0, -1.0), v(0.0, 1.0))),
+            2.0,
+        );
+        assert!(hit_distance(&point, &point_ray(v(0.0, 0.0))).is_none());
+        assert_near(hit_distance(&point, &point_ray(v(1.0, 1.0))), 0.0);
+    }
+
+    #[test]
+    fn circle() {
+        let c = Circle::new(v(0.0, 0.0), 2.0);
+        assert!(c.contains_point(v(2.0, 0.0)));
+        assert!(!c.contains_point(v(1.5, 1.5)));
+        assert!(c.overlaps(&Circle::new(v(3.0, 0.0), 1.0)));
+        assert!(!c.overlaps(&Circle::new(v(3.1, 0.0), 1.0)));
+        assert!(c.overlaps_aabb(&Aabb::new(v(1.0, 1.0), v(3.0, 3.0))));
+        assert!(!c.overlaps_aabb(&Aabb::new(v(1.5, 1.5), v(3.0, 3.0))));
+        assert_eq!(
+            c.intersection_points(&Circle::new(v(4.0, 0.0), 2.0)),
+            vec![v(2.0, 0.0)]
+        );
+        assert!(c.intersection_points(&c).is_empty());
+
+        let hit = c
+            .raycast(&Ray::new(v(-5.0, 0.0), v(1.0, 0.0)), 100.0)
+            .unwrap();
+        assert_eq!(
+            (hit.distance, hit.point, hit.normal),
+            (3.0, v(-2.0, 0.0), v(-1.0, 0.0))
+        );
+        assert!(hit_distance(&c, &Ray::new(v(-5.0, 0.0), v(-1.0, 0.0))).is_none());
+        assert!(hit_distance(&c, &Ray::new(v(-5.0, 2.1), v(1.0, 0.0))).is_none());
+        assert!(c
+            .raycast(&Ray::new(v(-5.0, 0.0), v(1.0, 0.0)), 2.9)
+            .is_none());
+        assert_near(hit_distance(&c, &Ray::new(v(1.0, 0.0), v(1.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn zero_radius_circle() {
+        let c = Circle::new(v(1.0, 0.0), 0.0);
+        assert!(c.contains_point(v(1.0, 0.0)));
+        assert!(!c.contains_point(v(1.0, 0.01)));
+        assert!(c.overlaps(&Circle::new(v(2.0, 0.0), 1.0)));
+        assert!(!c.overlaps(&Circle::new(v(2.1, 0.0), 1.0)));
+        assert!(c.overlaps_aabb(&Aabb::new(v(1.0, -1.0), v(2.0, 1.0))));
+        assert_eq!(c.area(), 0.0);
+
+        let hit = c
+            .raycast(&Ray::new(v(-1.0, 0.0), v(1.0, 0.0)), 100.0)
+            .unwrap();
+        assert_eq!((hit.distance, hit.normal), (2.0, v(-1.0, 0.0)));
+        assert!(hit_distance(&c, &Ray::new(v(-1.0, 0.1), v(1.0, 0.0))).is_none());
+        assert!(hit_distance(&c, &point_ray(v(0.0, 0.0))).is_none());
+        assert_near(hit_distance(&c, &point_ray(v(1.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn segment() {
+        let s = Segment::new(v(0.0, 0.0), v(4.0, 0.0));
+        assert!(s.contains_point(v(2.0, 0.0)));
+        assert!(!s.contains_point(v(2.0, 0.1)));
+        assert!(!s.contains_point(v(4.1, 0.0)));
+        assert_eq!(s.closest_point(v(2.0, 3.0)), v(2.0, 0.0));
+
+        let cross = Segment::new(v(2.0, -1.0), v(2.0, 1.0));
+        assert_eq!(s.intersection(&cross), Some(v(2.0, 0.0)));
+        assert!(!s.intersects(&Segment::new(v(5.0, -1.0), v(5.0, 1.0))));
+        assert!(!s.intersects(&Segment::new(v(0.0, 1.0), v(4.0, 1.0))));
+        // collinear and overlapping, the overlap starts at 3
+        assert_eq!(
+            s.intersection(&Segment::new(v(6.0, 0.0), v(3.0, 0.0))),
+            Some(v(3.0, 0.0))
+        );
+        assert!(
+            (s.distance_to_segment(&Segment::new(v(5.0, 1.0), v(6.0, 1.0))) - 2f32.sqrt()).abs()
+                < 1e-5
+        );
+
+        let hit = s
+            .raycast(&Ray::new(v(1.0, 2.0), v(0.0, -1.0)), 100.0)
+            .unwrap();
+        assert_eq!(
+            (hit.distance, hit.point, hit.normal),
+            (2.0, v(1.0, 0.0), v(0.0, 1.0))
+        );
+        assert!(hit_distance(&s, &Ray::new(v(5.0, 2.0), v(0.0, -1.0))).is_none());
+        assert!(hit_distance(&s, &Ray::new(v(1.0, 2.0), v(0.0, 1.0))).is_none());
+        assert!(hit_distance(&s, &Ray::new(v(0.0, 1.0), v(1.0, 0.0))).is_none());
+    }
+
+    #[test]
+    fn collinear_segment_raycast() {
+        let s = Segment::new(v(0.0, 0.0), v(4.0, 0.0));
+        // from before, after, and on the segment
+        assert_near(hit_distance(&s, &Ray::new(v(-2.0, 0.0), v(1.0, 0.0))), 2.0);
+        assert_near(hit_distance(&s, &Ray::new(v(6.0, 0.0), v(-1.0, 0.0))), 2.0);
+        assert!(hit_distance(&s, &Ray::new(v(6.0, 0.0), v(1.0, 0.0))).is_none());
+        assert_near(hit_distance(&s, &Ray::new(v(1.0, 0.0), v(1.0, 0.0))), 0.0);
+        assert_near(hit_distance(&s, &Ray::new(v(1.0, 0.0), v(-1.0, 0.0))), 0.0);
+        assert!(s
+            .raycast(&Ray::new(v(-2.0, 0.0), v(1.0, 0.0)), 1.0)
+            .is_none());
+    }
+
+    #[test]
+    fn zero_length_segment() {
+        let s = Segment::new(v(1.0, 1.0), v(1.0, 1.0));
+        assert_eq!(s.len(), 0.0);
+        assert!(s.contains_point(v(1.0, 1.0)));
+        assert!(!s.contains_point(v(1.0, 1.1)));
+        assert_eq!(s.closest_point(v(5.0, 5.0)), v(1.0, 1.0));
+        assert!(s.intersects(&Segment::new(v(0.0, 0.0), v(2.0, 2.0))));
+        assert!(!s.intersects(&Segment::new(v(0.0, 0.0), v(2.0, 0.0))));
+        assert!(s
+            .circle_intersections(&Circle::new(v(0.0, 0.0), 1.0))
+            .is_empty());
+
+        assert_near(hit_distance(&s, &Ray::new(v(1.0, -1.0), v(0.0, 1.0))), 2.0);
+        assert!(hit_distance(&s, &Ray::new(v(1.5, -1.0), v(0.0, 1.0))).is_none());
+        assert!(hit_distance(&s, &Ray::new(v(1.0, 3.0), v(0.0, 1.0))).is_none());
+    }
+
+    #[test]
+    fn zero_length_ray() {
+        let ray = point_ray(v(0.0, 0.0));
+        assert_eq!(ray.direction, Vec2::default());
+        assert_eq!(ray.at(10.0), v(0.0, 0.0));
+
+        // only what contains the origin is hit
+        let s = Segment::new(v(-1.0, 0.0), v(1.0, 0.0));
+        assert_near(hit_distance(&s, &ray), 0.0);
+        assert!(hit_distance(&Segment::new(v(1.0, 0.0), v(2.0, 0.0)), &ray).is_none());
+        assert!(hit_distance(&Segment::new(v(1.0, 1.0), v(2.0, 1.0)), &ray).is_none());
+        assert!(hit_distance(&Circle::new(v(2.0, 0.0), 1.0), &ray).is_none());
+        assert!(hit_distance(&Capsule::new(v(2.0, 0.0), v(3.0, 0.0), 1.0), &ray).is_none());
+        assert!(hit_distance(&Aabb::new(v(1.0, 1.0), v(2.0, 2.0)), &ray).is_none());
+        let triangle = Polygon::new(vec![v(1.0, 0.0), v(2.0, 0.0), v(1.0, 1.0)]);
+        assert!(hit_distance(&triangle, &ray).is_none());
+        assert_near(hit_distance(&Circle::new(v(0.5, 0.0), 1.0), &ray), 0.0);
+    }
+
+    #[test]
+    fn capsule() {
+        let c = Capsule::new(v(0.0, 0.0), v(4.0, 0.0), 1.0);
+        assert!(c.contains_point(v(2.0, 1.0)));
+        assert!(c.contains_point(v(5.0, 0.0)));
+        assert!(!c.contains_point(v(4.8, 0.8)));
+        assert!(c.overlaps(&Capsule::new(v(2.0, 2.0), v(2.0, 5.0), 1.0)));
+        assert!(!c.overlaps(&Capsule::new(v(2.0, 2.1), v(2.0, 5.0), 1.0)));
+        assert!(c.overlaps_circle(&Circle::new(v(6.0, 0.0), 1.0)));
+        assert!(!c.overlaps_circle(&Circle::new(v(6.1, 0.0), 1.0)));
+
+        let hit = c
+            .raycast(&Ray::new(v(2.0, 3.0), v(0.0, -1.0)), 100.0)
+            .unwrap();
+        assert_eq!((hit.distance, hit.point), (2.0, v(2.0, 1.0)));
+        assert_eq!(hit.normal, v(0.0, 1.0));
+        assert_near(hit_distance(&c, &Ray::new(v(7.0, 0.0), v(-1.0, 0.0))), 2.0);
+        assert!(hit_distance(&c, &Ray::new(v(7.0, 1.1), v(-1.0, 0.0))).is_none());
+        assert_near(hit_distance(&c, &Ray::new(v(1.0, 0.0), v(-1.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn degenerate_capsule() {
+        // a circle
+        let c = Capsule::new(v(1.0, 1.0), v(1.0, 1.0), 1.0);
+        assert!(c.contains_point(v(2.0, 1.0)));
+        assert!(!c.contains_point(v(2.1, 1.0)));
+        assert_near(hit_distance(&c, &Ray::new(v(4.0, 1.0), v(-1.0, 0.0))), 2.0);
+
+        // a segment
+        let c = Capsule::new(v(0.0, 0.0), v(4.0, 0.0), 0.0);
+        assert!(c.contains_point(v(2.0, 0.0)));
+        assert!(!c.contains_point(v(2.0, 0.1)));
+        assert_near(hit_distance(&c, &Ray::new(v(2.0, 2.0), v(0.0, -1.0))), 2.0);
+    }
+
+    #[test]
+    fn polygon() {
+        // clockwise points are reordered
+        let p = Polygon::new(vec![v(0.0, 0.0), v(0.0, 2.0), v(2.0, 2.0), v(2.0, 0.0)]);
+        assert_eq!(p.area(), 4.0);
+        assert_eq!(p.centroid(), v(1.0, 1.0));
+        assert!(p.contains_point(v(1.0, 1.0)));
+        assert!(p.contains_point(v(2.0, 1.0)));
+        assert!(!p.contains_point(v(2.1, 1.0)));
+
+        let triangle = Polygon::new(vec![v(2.0, 0.0), v(4.0, 0.0), v(3.0, 2.0)]);
+        assert!(p.overlaps(&triangle));
+        let triangle = Polygon::new(vec![v(2.1, 0.0), v(4.0, 0.0), v(3.0, 2.0)]);
+        assert!(!p.overlaps(&triangle));
+        assert!(p.overlaps_circle(&Circle::new(v(3.0, 1.0), 1.0)));
+        assert!(!p.overlaps_circle(&Circle::new(v(3.0, 3.0), 1.0)));
+        assert!(p.overlaps_aabb(&Aabb::new(v(1.0, 1.0), v(3.0, 3.0))));
+        assert!(!p.overlaps_aabb(&Aabb::new(v(2.5, 0.0), v(3.0, 3.0))));
+        assert_eq!(
+            p.segment_intersections(&Segment::new(v(-1.0, 1.0), v(1.0, 1.0))),
+            vec![v(0.0, 1.0)]
+        );
+
+        let hit = p
+            .raycast(&Ray::new(v(-1.0, 1.0), v(1.0, 0.0)), 100.0)
+            .unwrap();
+        assert_eq!(
+            (hit.distance, hit.point, hit.normal),
+            (1.0, v(0.0, 1.0), v(-1.0, 0.0))
+        );
+        assert!(hit_distance(&p, &Ray::new(v(-1.0, 1.0), v(-1.0, 0.0))).is_none());
+        assert!(hit_distance(&p, &Ray::new(v(-1.0, 3.0), v(1.0, 0.0))).is_none());
+        assert!(p
+            .raycast(&Ray::new(v(-1.0, 1.0), v(1.0, 0.0)), 0.5)
+            .is_none());
+        assert_near(hit_distance(&p, &Ray::new(v(1.0, 1.0), v(1.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        let empty = Polygon::new(Vec::new());
+        assert!(!empty.contains_point(v(0.0, 0.0)));
+        assert_eq!(empty.area(), 0.0);
+        assert_eq!(empty.closest_point(v(1.0, 1.0)), v(1.0, 1.0));
+        assert!(hit_distance(&empty, &Ray::new(v(0.0, 0.0), v(1.0, 0.0))).is_none());
+
+        let point = Polygon::new(vec![v(1.0, 1.0)]);
+        assert!(point.contains_point(v(1.0, 1.0)));
+        assert!(!point.contains_point(v(1.0, 1.1)));
+        assert_eq!(point.centroid(), v(1.0, 1.0));
+        assert_eq!(point.closest_point(v(3.0, 1.0)), v(1.0, 1.0));
+        assert!(point.overlaps_circle(&Circle::new(v(2.0, 1.0), 1.0)));
+        assert_near(
+            hit_distance(&point, &Ray::new(v(1.0, -1.0), v(0.0, 1.0))),
+            2.0,
+        );
+        assert!(hit_distance(&point, &Ray::new(v(0.0, -1.0), v(0.0, 1.0))).is_none());
+
+        let line = Polygon::new(vec![v(0.0, 0.0), v(4.0, 0.0)]);
+        assert!(line.contains_point(v(2.0, 0.0)));
+        assert!(!line.contains_point(v(2.0, 0.1)));
+        assert_eq!(line.area(), 0.0);
+        assert_eq!(line.closest_point(v(2.0, 3.0)), v(2.0, 0.0));
+        assert!(line.overlaps_circle(&Circle::new(v(2.0, 1.0), 1.0)));
+        assert!(!line.overlaps_circle(&Circle::new(v(2.0, 1.1), 1.0)));
+        assert_near(
+            hit_distance(&line, &Ray::new(v(2.0, 2.0), v(0.0, -1.0))),
+            2.0,
+        );
+        assert_near(
+            hit_distance(&line, &Ray::new(v(-2.0, 0.0), v(1.0, 0.0))),
+            2.0,
+        );
+        assert!(hit_distance(&line, &Ray::new(v(5.0, 2.0), v(0.0, -1.0))).is_none());
+    }
+}
@@ -0,0 +1,256 @@
+//! Accelerated matrix products, and batches of `Mat3` math
+//!
+//! x86_64 uses SSE2, which every x86_64 cpu has, so there is no runtime detection
+//! other targets fall back to the `scalar` implementation
+//!
+//! `Mat4` products use these, `Mat3` ones stay scalar: with columns of 3 floats, loading
+//! them into registers costs as much as the SSE2 version saves, see examples/math_bench.rs
+//!
+//! both implementations perform the same operations in the same order without fused
+//! multiply-add, so their results are bit-identical
+
+use super::Mat3;
+use super::Mat4;
+use super::Vec2;
+use super::Vec3;
+use super::Vec4;
+
+pub use imp::*;
+
+/// The portable implementation, also used as the reference for the SIMD one
+pub mod scalar {
+    use super::Mat3;
+    use super::Mat4;
+    use super::Vec2;
+    use super::Vec3;
+    use super::Vec4;
+
+    #[inline]
+    pub fn mat3_mul(a: Mat3, b: Mat3) -> Mat3 {
+        a.scalar_mul(b)
+    }
+
+    #[inline]
+    pub fn mat3_mul_vec(a: Mat3, v: Vec3) -> Vec3 {
+        a.scalar_mul_vec(v)
+    }
+
+    #[inline]
+    pub fn mat4_mul(a: Mat4, b: Mat4) -> Mat4 {
+        a.scalar_mul(b)
+    }
+
+    #[inline]
+    pub fn mat4_mul_vec(a: Mat4, v: Vec4) -> Vec4 {
+        a.scalar_mul_vec(v)
+    }
+
+    /// same as calling `Mat3::transform_point` on every point
+    #[inline]
+    pub fn transform_points(m: &Mat3, points: &mut [Vec2]) {
+        for p in points {
+            *p = m.transform_point(*p);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod imp {
+    pub use super::scalar::*;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod imp {
+    use std::arch::x86_64::*;
+
+    use super::Mat3;
+    use super::Mat4;
+    use super::Vec2;
+    use super::Vec3;
+    use super::Vec4;
+
+    // the sum of the columns scaled by the elements of `v`, starting from 0 like the scalar version
+    #[inline]
+    unsafe fn combine<const N: usize>(cols: &[__m128; N], v: &[f32]) -> __m128 {
+        let mut sum = _mm_setzero_ps();
+        for (col, &e) in cols.iter().zip(v) {
+            sum = _mm_add_ps(sum, _mm_mul_ps(*col, _mm_set1_ps(e)));
+        }
+        sum
+    }
+
+    // the columns of a Mat3, with a 0 after each
+    #[inline]
+    unsafe fn mat3_cols(m: &Mat3) -> [__m128; 3] {
+        let a = m.to_array();
+        [0, 3, 6].map(|i| _mm_set_ps(0.0, a[i + 2], a[i + 1], a[i]))
+    }
+
+    #[inline]
+    unsafe fn to_floats(v: __m128) -> [f32; 4] {
+        let mut ret = [0.0; 4];
+        _mm_storeu_ps(ret.as_mut_ptr(), v);
+        ret
+    }
+
+    #[inline]
+    pub fn mat3_mul(a: Mat3, b: Mat3) -> Mat3 {
+        let rhs = b.to_array();
+        let mut ret = [0.0; 9];
+        unsafe {
+            let cols = mat3_cols(&a);
+            for y in 0..3 {
+                let col = to_floats(combine(&cols, &rhs[3 * y..3 * y + 3]));
+                ret[3 * y..3 * y + 3].copy_from_slice(&col[..3]);
+            }
+        }
+        Mat3::from_array(ret)
+    }
+
+    #[inline]
+    pub fn mat3_mul_vec(a: Mat3, v: Vec3) -> Vec3 {
+        unsafe {
+            let [x, y, z, _] = to_floats(combine(&mat3_cols(&a), &[v.x, v.y, v.z]));
+            Vec3::new(x, y, z)
+        }
+    }
+
+    #[inline]
+    pub fn mat4_mul(a: Mat4, b: Mat4) -> Mat4 {
+        let (lhs, rhs) = (a.to_array(), b.to_array());
+        let mut ret = [0.0; 16];
+        unsafe {
+            let cols = [0, 4, 8, 12].map(|i| _mm_loadu_ps(lhs[i..].as_ptr()));
+            for y in 0..4 {
+                let col = combine(&cols, &rhs[4 * y..4 * y + 4]);
+                _mm_storeu_ps(ret[4 * y..].as_mut_ptr(), col);
+            }
+        }
+        Mat4::from_array(ret)
+    }
+
+    #[inline]
+    pub fn mat4_mul_vec(a: Mat4, v: Vec4) -> Vec4 {
+        let lhs = a.to_array();
+        unsafe {
+            let cols = [0, 4, 8, 12].map(|i| _mm_loadu_ps(lhs[i..].as_ptr()));
+            let [x, y, z, w] = to_floats(combine(&cols, &[v.x, v.y, v.z, v.w]));
+            Vec4::new(x, y, z, w)
+        }
+    }
+
+    /// same as calling `Mat3::transform_point` on every point
+    /// four points are transformed at a time, as two pairs
+    #[inline]
+    pub fn transform_points(m: &Mat3, points: &mut [Vec2]) {
+        let a = m.to_array();
+        let mut quads = points.chunks_exact_mut(4);
+
+        unsafe {
+            let col_x = _mm_set_ps(a[1], a[0], a[1], a[0]);
+            let col_y = _mm_set_ps(a[4], a[3], a[4], a[3]);
+            let translation = _mm_set_ps(a[7], a[6], a[7], a[6]);
+
+            // a pair of points: (x0, y0, x1, y1)
+            let transform = |v: __m128| {
+                // (x0, x0, x1, x1) and (y0, y0, y1, y1)
+                let xs = _mm_shuffle_ps::<0b10_10_00_00>(v, v);
+                let ys = _mm_shuffle_ps::<0b11_11_01_01>(v, v);
+                let xy = _mm_add_ps(_mm_mul_ps(col_x, xs), _mm_mul_ps(col_y, ys));
+                _mm_add_ps(xy, translation)
+            };
+
+            for quad in &mut quads {
+                // Vec2 is a transparent [f32; 2], so the points are 8 packed floats
+                let ptr = quad.as_mut_ptr() as *mut f32;
+                let lo = transform(_mm_loadu_ps(ptr));
+                let hi = transform(_mm_loadu_ps(ptr.add(4)));
+                _mm_storeu_ps(ptr, lo);
+                _mm_storeu_ps(ptr.add(4), hi);
+            }
+        }
+
+        for p in quads.into_remainder() {
+            *p = m.transform_point(*p);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    fn random_mat3(rng: &mut Rng) -> Mat3 {
+        Mat3::from_array(std::array::from_fn(|_| rng.range(-10.0..10.0)))
+    }
+
+    fn random_mat4(rng: &mut Rng) -> Mat4 {
+        Mat4::from_array(std::array::from_fn(|_| rng.range(-10.0..10.0)))
+    }
+
+    #[test]
+    fn products_match_scalar() {
+        let mut rng = Rng::new(35);
+        for _ in 0..1000 {
+            let (a, b) = (random_mat3(&mut rng), random_mat3(&mut rng));
+            let v = Vec3::new(
+                rng.range(-1e3..1e3),
+                rng.range(-1e3..1e3),
+                rng.range(-1e3..1e3),
+            );
+            assert_eq!(mat3_mul(a, b).to_array(), scalar::mat3_mul(a, b).to_array());
+            assert_eq!(mat3_mul_vec(a, v), scalar::mat3_mul_vec(a, v));
+            assert_eq!(a * b, a.scalar_mul(b));
+
+            let (a, b) = (random_mat4(&mut rng), random_mat4(&mut rng));
+            let v = Vec4::new(
+                rng.range(-1e3..1e3),
+                rng.range(-1e3..1e3),
+                rng.range(-1e3..1e3),
+                1.0,
+            );
+            assert_eq!(mat4_mul(a, b).to_array(), scalar::mat4_mul(a, b).to_array());
+            assert_eq!(mat4_mul_vec(a, v), scalar::mat4_mul_vec(a, v));
+            assert_eq!(a * b, a.scalar_mul(b));
+            assert_eq!(a * v, a.scalar_mul_vec(v));
+        }
+
+        // the sign of zeros, infinities and nans are kept too
+        let mut a = Mat4::identity().to_array();
+        a[1] = -0.0;
+        a[5] = f32::INFINITY;
+        let a = Mat4::from_array(a);
+        let v = Vec4::new(-0.0, 1.0, 0.0, f32::NAN);
+        let bits = |v: Vec4| [v.x, v.y, v.z, v.w].map(f32::to_bits);
+        assert_eq!(bits(mat4_mul_vec(a, v)), bits(scalar::mat4_mul_vec(a, v)));
+    }
+
+    #[test]
+    fn transform_points_matches_scalar() {
+        let mut rng = Rng::new(35);
+        // every remainder of the batches of 4
+        for len in [0, 1, 2, 3, 4, 5, 6, 7, 8, 100, 1001] {
+            let m = Mat3::from_trs(
+                Vec2::new(rng.range(-100.0..100.0), rng.range(-100.0..100.0)),
+                rng.range(-180.0..180.0),
+                Vec2::new(rng.range(-4.0..4.0), rng.range(-4.0..4.0)),
+            );
+            let points: Vec<Vec2> = (0..len)
+                .map(|_| Vec2::new(rng.range(-1e3..1e3), rng.range(-1e3..1e3)))
+                .collect();
+
+            let mut expected = points.clone();
+            scalar::transform_points(&m, &mut expected);
+            let mut actual = points.clone();
+            transform_points(&m, &mut actual);
+            assert_eq!(actual, expected);
+
+            for (p, e) in points.iter().zip(&expected) {
+                assert_eq!(m.transform_point(*p), *e);
+                let v = m * crate::math::Vec3::new(p.x, p.y, 1.0);
+                assert!((Vec2::new(v.x, v.y) - *e).len() <= 1e-3 * e.len().max(1.0));
+            }
+        }
+    }
+}
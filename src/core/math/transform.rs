@@ -0,0 +1,347 @@
+use std::cell::Cell;
+
+use super::Mat3;
+use super::Vec2;
+use crate::data::Handle;
+use crate::data::SlotMap;
+
+/// A 2D transform built from its components
+/// the matrix is rebuilt lazily, only after one of the components has changed
+///
+/// the order of operations is:
+/// move the origin to (0,0), scale, skew, rotate, then translate to `position`
+#[derive(Debug, Clone)]
+pub struct Transform2D {
+    position: Vec2,
+    // degrees, clockwise like `Mat3::rotate`
+    rotation: f32,
+    scale: Vec2,
+    // the pivot point for scaling and rotation, in local space
+    origin: Vec2,
+    // degrees, x shears along the y axis and y along the x axis
+    skew: Vec2,
+    // None when dirty
+    local: Cell<Option<Mat3>>,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::new(Vec2::default(), 0.0, Vec2::new(1.0, 1.0))
+    }
+}
+
+impl PartialEq for Transform2D {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+            && self.rotation == other.rotation
+            && self.scale == other.scale
+            && self.origin == other.origin
+            && self.skew == other.skew
+    }
+}
+
+impl Transform2D {
+    pub fn new(position: Vec2, rotation: f32, scale: Vec2) -> Self {
+        Self {
+            position,
+            rotation,
+            scale,
+            origin: Vec2::default(),
+            skew: Vec2::default(),
+            local: Cell::new(None),
+        }
+    }
+
+    pub fn from_position(position: Vec2) -> Self {
+        Self::new(position, 0.0, Vec2::new(1.0, 1.0))
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn scale(&self) -> Vec2 {
+        self.scale
+    }
+
+    pub fn origin(&self) -> Vec2 {
+        self.origin
+    }
+
+    pub fn skew(&self) -> Vec2 {
+        self.skew
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.position = position;
+        self.local.set(None);
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.local.set(None);
+    }
+
+    pub fn set_scale(&mut self, scale: Vec2) {
+        self.scale = scale;
+        self.local.set(None);
+    }
+
+    pub fn set_origin(&mut self, origin: Vec2) {
+        self.origin = origin;
+        self.local.set(None);
+    }
+
+    pub fn set_skew(&mut self, skew: Vec2) {
+        self.skew = skew;
+        self.local.set(None);
+    }
+
+    pub fn translate(&mut self, offset: Vec2) {
+        self.set_position(self.position + offset);
+    }
+
+    pub fn rotate(&mut self, angle: f32) {
+        self.set_rotation(self.rotation + angle);
+    }
+
+    /// the local transform matrix, to pass to `ISprite::draw`
+    pub fn matrix(&self) -> Mat3 {
+        if let Some(m) = self.local.get() {
+            return m;
+        }
+
+        let skew = Mat3::from_cols(
+            (1.0, self.skew.y.to_radians().tan(), 0.0).into(),
+            (self.skew.x.to_radians().tan(), 1.0, 0.0).into(),
+            (0.0, 0.0, 1.0).into(),
+        );
+        let m = Mat3::translate(self.position)
+            * Mat3::rotate(self.rotation)
+            * skew
+            * Mat3::scale(self.scale)
+            * Mat3::translate(-self.origin);
+
+        self.local.set(Some(m));
+        m
+    }
+
+    /// interpolates every component, `t` = 0 gives `self` and `t` = 1 gives `other`
+    /// the rotation takes the shortest way around
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let delta = (other.rotation - self.rotation + 180.0).rem_euclid(360.0) - 180.0;
+
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation + t * delta,
+            scale: self.scale.lerp(other.scale, t),
+            origin: self.origin.lerp(other.origin, t),
+            skew: self.skew.lerp(other.skew, t),
+            local: Cell::new(None),
+        }
+    }
+}
+
+/// Identifies a transform inside a `TransformTree`, stale once the transform is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransformId(Handle<Node>);
+
+struct Node {
+    transform: Transform2D,
+    parent: Option<TransformId>,
+    children: Vec<TransformId>,
+    // None when the node or one of its ancestors changed
+    world: Cell<Option<Mat3>>,
+}
+
+/// A hierarchy of transforms, where every child moves along with its parent
+///
+/// ex: a ship and its turret
+/// let ship = tree.insert(Transform2D::default(), None);
+/// let turret = tree.insert(Transform2D::from_position((0.0, 0.5).into()), Some(ship));
+/// tree.get_mut(ship).translate((1.0, 0.0).into());
+/// // the turret is now at (1.0, 0.5)
+/// let m = tree.world(turret);
+#[derive(Default)]
+pub struct TransformTree {
+    nodes: SlotMap<Node>,
+}
+
+impl TransformTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, transform: Transform2D, parent: Option<TransformId>) -> TransformId {
+        let node = Node {
+            transform,
+            parent: None,
+            children: Vec::new(),
+            world: Cell::new(None),
+        };
+
+        let id = TransformId(self.nodes.insert(node));
+        self.set_parent(id, parent);
+        id
+    }
+
+    /// removes the transform and all of its descendants
+    pub fn remove(&mut self, id: TransformId) -> Option<Transform2D> {
+        let parent = self.node(id)?.parent;
+        self.detach(id, parent);
+
+        let node = self.nodes.remove(id.0)?;
+        for child in node.children {
+            // the child's parent is already gone, skip detaching
+            self.node_mut(child).unwrap().parent = None;
+            self.remove(child);
+        }
+
+        Some(node.transform)
+    }
+
+    pub fn contains(&self, id: TransformId) -> bool {
+        self.node(id).is_some()
+    }
+
+    pub fn get(&self, id: TransformId) -> Option<&Transform2D> {
+        self.node(id).map(|n| &n.transform)
+    }
+
+    /// panics if `id` isn't part of the tree
+    /// descendants are marked as dirty since they may be moved through the returned reference
+    pub fn get_mut(&mut self, id: TransformId) -> &mut Transform2D {
+        self.invalidate(id);
+        &mut self.node_mut(id).expect("Bad transform id").transform
+    }
+
+    pub fn parent(&self, id: TransformId) -> Option<TransformId> {
+        self.node(id)?.parent
+    }
+
+    pub fn children(&self, id: TransformId) -> &[TransformId] {
+        self.node(id).map(|n| n.children.as_slice()).unwrap_or(&[])
+    }
+
+    /// moves the transform under a new parent, keeping its local transform
+    /// attaching a transform to one of its own descendants is ignored
+    pub fn set_parent(&mut self, id: TransformId, parent: Option<TransformId>) {
+        if let Some(p) = parent {
+            if !self.contains(p) || self.is_ancestor(id, p) {
+                return;
+            }
+        }
+
+        let old = match self.node(id) {
+            Some(n) => n.parent,
+            None => return,
+        };
+        self.detach(id, old);
+
+        if let Some(p) = parent {
+            self.node_mut(p).unwrap().children.push(id);
+        }
+        self.node_mut(id).unwrap().parent = parent;
+        self.invalidate(id);
+    }
+
+    /// the transform from the node's local space to the root's space
+    /// panics if `id` isn't part of the tree
+    pub fn world(&self, id: TransformId) -> Mat3 {
+        let node = self.node(id).expect("Bad transform id");
+        if let Some(m) = node.world.get() {
+            return m;
+        }
+
+        let local = node.transform.matrix();
+        let m = match node.parent {
+            Some(p) => self.world(p) * local,
+            None => local,
+        };
+        node.world.set(Some(m));
+        m
+    }
+
+    fn node(&self, id: TransformId) -> Option<&Node> {
+        self.nodes.get(id.0)
+    }
+
+    fn node_mut(&mut self, id: TransformId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0)
+    }
+
+    // whether `ancestor` is `id` or one of its parents
+    fn is_ancestor(&self, ancestor: TransformId, mut id: TransformId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.parent(id) {
+                Some(p) => id = p,
+                None => return false,
+            }
+        }
+    }
+
+    fn detach(&mut self, id: TransformId, parent: Option<TransformId>) {
+        if let Some(p) = parent.and_then(|p| self.node_mut(p)) {
+            p.children.retain(|&c| c != id);
+        }
+    }
+
+    fn invalidate(&self, id: TransformId) {
+        if let Some(node) = self.node(id) {
+            node.world.set(None);
+            for &child in &node.children {
+                self.invalidate(child);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_ids() {
+        let mut tree = TransformTree::new();
+        let parent = tree.insert(Transform2D::default(), None);
+        let old = tree.insert(
+            Transform2D::from_position(Vec2::new(1.0, 0.0)),
+            Some(parent),
+        );
+        assert!(tree.remove(old).is_some());
+
+        // takes the slot of the removed transform
+        let new = tree.insert(
+            Transform2D::from_position(Vec2::new(2.0, 0.0)),
+            Some(parent),
+        );
+        assert_ne!(old, new);
+        assert!(!tree.contains(old));
+        assert!(tree.get(old).is_none());
+        assert!(tree.parent(old).is_none());
+        assert!(tree.remove(old).is_none());
+        tree.set_parent(old, None);
+
+        assert_eq!(tree.children(parent), &[new]);
+        assert_eq!(tree.parent(new), Some(parent));
+        assert_eq!(tree.get(new).unwrap().position(), Vec2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn remove_descendants() {
+        let mut tree = TransformTree::new();
+        let a = tree.insert(Transform2D::default(), None);
+        let b = tree.insert(Transform2D::default(), Some(a));
+        let c = tree.insert(Transform2D::default(), Some(b));
+        tree.remove(b);
+        assert!(tree.contains(a) && !tree.contains(b) && !tree.contains(c));
+        assert!(tree.children(a).is_empty());
+    }
+}
@@ -0,0 +1,708 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::iter::Sum;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::ops::Div;
+use std::ops::DivAssign;
+use std::ops::Index;
+use std::ops::IndexMut;
+use std::ops::Mul;
+use std::ops::MulAssign;
+use std::ops::Neg;
+use std::ops::Rem;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+use super::Fixed;
+
+/// A number that can be stored in a vector
+pub trait Scalar:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    // conversions with the semantics of `as`
+    // every supported scalar fits in an f64 without loss,
+    // apart from the lowest fraction bits of `Fixed` values beyond 2^21
+    fn to_f64(self) -> f64;
+    fn from_f64(value: f64) -> Self;
+}
+
+/// A scalar that can be negated
+pub trait Signed: Scalar + Neg<Output = Self> {
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+}
+
+/// A real number
+pub trait Float: Signed {
+    /// tolerance used by approximate comparisons such as `is_unit`
+    const TOLERANCE: Self;
+
+    fn sqrt(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn to_degrees(self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),+) => {$(
+        impl Scalar for $t {
+            const ZERO: Self = 0 as _;
+            const ONE: Self = 1 as _;
+
+            fn to_f64(self) -> f64 {
+                self as _
+            }
+
+            fn from_f64(value: f64) -> Self {
+                value as _
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_signed {
+    ($($t:ty),+) => {$(
+        impl Signed for $t {
+            fn abs(self) -> Self {
+                self.abs()
+            }
+
+            fn signum(self) -> Self {
+                self.signum()
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_float {
+    ($($t:ty),+) => {$(
+        impl Float for $t {
+            const TOLERANCE: Self = 0.001;
+
+            fn sqrt(self) -> Self {
+                self.sqrt()
+            }
+
+            fn floor(self) -> Self {
+                self.floor()
+            }
+
+            fn ceil(self) -> Self {
+                self.ceil()
+            }
+
+            fn round(self) -> Self {
+                self.round()
+            }
+
+            fn acos(self) -> Self {
+                self.acos()
+            }
+
+            fn atan2(self, other: Self) -> Self {
+                self.atan2(other)
+            }
+
+            fn to_degrees(self) -> Self {
+                self.to_degrees()
+            }
+        }
+    )+};
+}
+
+impl_scalar!(f32, f64, i32, u32);
+impl_signed!(f32, f64, i32);
+impl_float!(f32, f64);
+
+/// A vector of `N` components of type `T`
+/// vectors of 2 to 4 components expose their components as named fields (x, y, z, w)
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Vector<T, const N: usize>(pub [T; N]);
+
+pub type Vec2 = Vector<f32, 2>;
+pub type Vec3 = Vector<f32, 3>;
+pub type Vec4 = Vector<f32, 4>;
+
+pub type DVec2 = Vector<f64, 2>;
+pub type DVec3 = Vector<f64, 3>;
+pub type DVec4 = Vector<f64, 4>;
+
+pub type IVec2 = Vector<i32, 2>;
+pub type IVec3 = Vector<i32, 3>;
+pub type IVec4 = Vector<i32, 4>;
+
+pub type UVec2 = Vector<u32, 2>;
+pub type UVec3 = Vector<u32, 3>;
+pub type UVec4 = Vector<u32, 4>;
+
+/// deterministic, see `Fixed`
+pub type FVec2 = Vector<Fixed, 2>;
+pub type FVec3 = Vector<Fixed, 3>;
+pub type FVec4 = Vector<Fixed, 4>;
+
+// named access to the components
+// these have the same layout as the matching array, so vectors can deref into them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct XY<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct XYZ<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct XYZW<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+macro_rules! impl_named {
+    ($n:literal => $named:ident { $($c:ident),+ }) => {
+        impl<T> Vector<T, $n> {
+            pub const fn new($($c: T),+) -> Self {
+                Self([$($c),+])
+            }
+        }
+
+        impl<T> Deref for Vector<T, $n> {
+            type Target = $named<T>;
+
+            fn deref(&self) -> &Self::Target {
+                // SAFETY: both types are N consecutive Ts
+                unsafe { &*(self as *const Self as *const Self::Target) }
+            }
+        }
+
+        impl<T> DerefMut for Vector<T, $n> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                // SAFETY: see above
+                unsafe { &mut *(self as *mut Self as *mut Self::Target) }
+            }
+        }
+
+        // tuple -> array -> vector
+        impl<T> From<($(impl_named!(@repeat $c T)),+)> for Vector<T, $n> {
+            fn from(($($c),+): ($(impl_named!(@repeat $c T)),+)) -> Self {
+                Self([$($c),+])
+            }
+        }
+
+        impl<T> From<Vector<T, $n>> for ($(impl_named!(@repeat $c T)),+) {
+            fn from(value: Vector<T, $n>) -> Self {
+                let [$($c),+] = value.0;
+                ($($c),+)
+            }
+        }
+    };
+    (@repeat $_c:ident $t:ident) => { $t };
+}
+
+impl_named!(2 => XY { x, y });
+impl_named!(3 => XYZ { x, y, z });
+impl_named!(4 => XYZW { x, y, z, w });
+
+impl<T: Scalar, const N: usize> Default for Vector<T, N> {
+    fn default() -> Self {
+        Self::splat(T::ZERO)
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T, N> {
+    fn from(value: [T; N]) -> Self {
+        Self(value)
+    }
+}
+
+impl<T, const N: usize> From<Vector<T, N>> for [T; N] {
+    fn from(value: Vector<T, N>) -> Self {
+        value.0
+    }
+}
+
+impl<T: Scalar, const N: usize> Vector<T, N> {
+    pub fn splat(value: T) -> Self {
+        Self([value; N])
+    }
+
+    pub fn zero() -> Self {
+        Self::splat(T::ZERO)
+    }
+
+    pub fn one() -> Self {
+        Self::splat(T::ONE)
+    }
+
+    /// applies `f` to every component
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Vector<U, N> {
+        Vector(self.0.map(f))
+    }
+
+    /// applies `f` to every pair of matching components
+    pub fn zip_map<U>(self, rhs: Self, mut f: impl FnMut(T, T) -> U) -> Vector<U, N> {
+        Vector(std::array::from_fn(|i| f(self.0[i], rhs.0[i])))
+    }
+
+    pub fn len2(self) -> T {
+        self.dot(self)
+    }
+
+    pub fn dot(self, rhs: Self) -> T {
+        let mut ret = T::ZERO;
+        for i in 0..N {
+            ret += self.0[i] * rhs.0[i];
+        }
+        ret
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        self.zip_map(rhs, |a, b| if b < a { b } else { a })
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        self.zip_map(rhs, |a, b| if b > a { b } else { a })
+    }
+
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    pub fn min_element(self) -> T {
+        self.0
+            .into_iter()
+            .reduce(|a, b| if b < a { b } else { a })
+            .unwrap()
+    }
+
+    pub fn max_element(self) -> T {
+        self.0
+            .into_iter()
+            .reduce(|a, b| if b > a { b } else { a })
+            .unwrap()
+    }
+
+    /// the sum of all components
+    pub fn element_sum(self) -> T {
+        self.0.into_iter().fold(T::ZERO, |a, b| a + b)
+    }
+
+    /// the product of all components
+    pub fn element_product(self) -> T {
+        self.0.into_iter().fold(T::ONE, |a, b| a * b)
+    }
+
+    /// converts every component with the semantics of `as`
+    /// ex: a Vec2 of (1.7, -2.0) cast to a UVec2 is (1, 0)
+    pub fn cast<U: Scalar>(self) -> Vector<U, N> {
+        self.map(|c| U::from_f64(c.to_f64()))
+    }
+
+    /// like `cast`, but returns None if any component doesn't convert exactly
+    /// ex: a Vec2 of (1.0, 2.0) converts to an IVec2, but (1.5, 2.0) doesn't
+    pub fn try_cast<U: Scalar>(self) -> Option<Vector<U, N>> {
+        let ret = self.cast::<U>();
+        let exact = (0..N).all(|i| ret.0[i].to_f64() == self.0[i].to_f64());
+        exact.then_some(ret)
+    }
+}
+
+impl<T: Signed, const N: usize> Vector<T, N> {
+    pub fn abs(self) -> Self {
+        self.map(T::abs)
+    }
+
+    pub fn signum(self) -> Self {
+        self.map(T::signum)
+    }
+}
+
+impl<T: Float, const N: usize> Vector<T, N> {
+    /// exactly, however short a vector is it still has a direction
+    pub fn is_zero(self) -> bool {
+        self.0.iter().all(|&c| c == T::ZERO)
+    }
+
+    pub fn is_unit(self) -> bool {
+        let l = self.len2() - T::ONE;
+        let e = T::TOLERANCE;
+        -e <= l && l < e
+    }
+
+    pub fn len(self) -> T {
+        self.len2().sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> T {
+        (rhs - self).len()
+    }
+
+    /// the zero vector for the zero vector
+    pub fn normalize(self) -> Self {
+        let len = self.len();
+        if len != T::ZERO {
+            return self / len;
+        }
+        // the squared length of a tiny vector can round to 0, scale it up first
+        let largest = self.0.iter().fold(T::ZERO, |m, &c| {
+            let c = c.abs();
+            if c > m {
+                c
+            } else {
+                m
+            }
+        });
+        if largest == T::ZERO {
+            return Self::default();
+        }
+        let scaled = self / largest;
+        scaled / scaled.len()
+    }
+
+    pub fn floor(self) -> Self {
+        self.map(T::floor)
+    }
+
+    pub fn ceil(self) -> Self {
+        self.map(T::ceil)
+    }
+
+    pub fn round(self) -> Self {
+        self.map(T::round)
+    }
+
+    /// `t` = 0 gives `self` and `t` = 1 gives `rhs`
+    pub fn lerp(self, rhs: Self, t: T) -> Self {
+        self + (rhs - self) * t
+    }
+
+    /// reflects the vector off a surface with the given unit normal
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (T::from_f64(2.0) * self.dot(normal))
+    }
+
+    /// the part of the vector that points along `onto`
+    pub fn project(self, onto: Self) -> Self {
+        let l2 = onto.len2();
+        if l2 == T::ZERO {
+            return Self::default();
+        }
+        onto * (self.dot(onto) / l2)
+    }
+
+    /// the part of the vector perpendicular to `onto`
+    pub fn reject(self, onto: Self) -> Self {
+        self - self.project(onto)
+    }
+}
+
+impl<T: Scalar> Vector<T, 2> {
+    /// the z component of the 3D cross product,
+    /// positive if `rhs` is counter clockwise from `self`
+    pub fn cross(self, rhs: Self) -> T {
+        self.0[0] * rhs.0[1] - self.0[1] * rhs.0[0]
+    }
+}
+
+impl<T: Signed> Vector<T, 2> {
+    /// rotated 90 degrees counter clockwise
+    pub fn perp(self) -> Self {
+        Self([-self.0[1], self.0[0]])
+    }
+}
+
+impl<T: Scalar> Vector<T, 3> {
+    pub fn cross(self, rhs: Self) -> Self {
+        let (a, b) = (self.0, rhs.0);
+        Self([
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ])
+    }
+}
+
+// growing and shrinking vectors
+
+impl<T: Scalar> Vector<T, 2> {
+    pub fn extend(self, z: T) -> Vector<T, 3> {
+        Vector([self.0[0], self.0[1], z])
+    }
+
+    /// a point, ready to be multiplied by a `Mat3`
+    pub fn xy1(self) -> Vector<T, 3> {
+        self.extend(T::ONE)
+    }
+
+    /// a direction, unaffected by the translation of a `Mat3`
+    pub fn xy0(self) -> Vector<T, 3> {
+        self.extend(T::ZERO)
+    }
+}
+
+impl<T: Scalar> Vector<T, 3> {
+    pub fn extend(self, w: T) -> Vector<T, 4> {
+        Vector([self.0[0], self.0[1], self.0[2], w])
+    }
+
+    pub fn truncate(self) -> Vector<T, 2> {
+        Vector([self.0[0], self.0[1]])
+    }
+
+    /// a point, ready to be multiplied by a `Mat4`
+    pub fn xyz1(self) -> Vector<T, 4> {
+        self.extend(T::ONE)
+    }
+
+    /// a direction, unaffected by the translation of a `Mat4`
+    pub fn xyz0(self) -> Vector<T, 4> {
+        self.extend(T::ZERO)
+    }
+}
+
+impl<T: Scalar> Vector<T, 4> {
+    pub fn truncate(self) -> Vector<T, 3> {
+        Vector([self.0[0], self.0[1], self.0[2]])
+    }
+}
+
+// swizzles, ex: v.xy(), v.yx(), v.zyx(), v.xxyy()
+// every combination of 2 to 4 components is generated by the build script
+include!(concat!(env!("OUT_DIR"), "/swizzles.rs"));
+
+impl Vec2 {
+    // gets the angle in degrees to the up vector
+    // note that the angle is in the range (-180,180)
+    pub fn angle(self) -> f32 {
+        let up = Vec2::new(0.0, 1.0);
+        // a.b = |a|*|b|*cos(th)
+        self.dot(up).acos().to_degrees()
+    }
+}
+
+impl<T, const N: usize> Index<usize> for Vector<T, N> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        self.0.get(idx).expect("Bad index")
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for Vector<T, N> {
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        self.0.get_mut(idx).expect("Bad index")
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for Vector<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl<T: Display, const N: usize> Display for Vector<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "( ")?;
+        for c in &self.0 {
+            write!(f, "{:.2} ", c)?;
+        }
+        write!(f, ")")
+    }
+}
+
+// component wise operations, both with another vector and with a scalar
+macro_rules! impl_op {
+    ($op:ident $fn:ident, $op_assign:ident $fn_assign:ident) => {
+        impl<T: Scalar, const N: usize> $op for Vector<T, N> {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                self.zip_map(rhs, $op::$fn)
+            }
+        }
+
+        impl<T: Scalar, const N: usize> $op<T> for Vector<T, N> {
+            type Output = Self;
+
+            fn $fn(self, rhs: T) -> Self {
+                self.map(|c| $op::$fn(c, rhs))
+            }
+        }
+
+        impl<T: Scalar, const N: usize> $op_assign for Vector<T, N> {
+            fn $fn_assign(&mut self, rhs: Self) {
+                *self = $op::$fn(*self, rhs);
+            }
+        }
+
+        impl<T: Scalar, const N: usize> $op_assign<T> for Vector<T, N> {
+            fn $fn_assign(&mut self, rhs: T) {
+                *self = $op::$fn(*self, rhs);
+            }
+        }
+    };
+}
+
+impl_op!(Add add, AddAssign add_assign);
+impl_op!(Sub sub, SubAssign sub_assign);
+impl_op!(Mul mul, MulAssign mul_assign);
+impl_op!(Div div, DivAssign div_assign);
+
+impl<T: Scalar, const N: usize> Rem for Vector<T, N> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        self.zip_map(rhs, Rem::rem)
+    }
+}
+
+impl<T: Scalar, const N: usize> Rem<T> for Vector<T, N> {
+    type Output = Self;
+
+    fn rem(self, rhs: T) -> Self {
+        self.map(|c| c % rhs)
+    }
+}
+
+impl<T: Signed, const N: usize> Neg for Vector<T, N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(Neg::neg)
+    }
+}
+
+impl<T: Scalar, const N: usize> Sum for Vector<T, N> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl<'a, T: Scalar, const N: usize> Sum<&'a Vector<T, N>> for Vector<T, N> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+// scalar on the left hand side
+// generic impls aren't allowed here, so every scalar type is listed
+macro_rules! impl_scalar_lhs {
+    ($($t:ty),+) => {$(
+        impl<const N: usize> Mul<Vector<$t, N>> for $t {
+            type Output = Vector<$t, N>;
+
+            fn mul(self, rhs: Vector<$t, N>) -> Self::Output {
+                rhs * self
+            }
+        }
+
+        impl<const N: usize> Div<Vector<$t, N>> for $t {
+            type Output = Vector<$t, N>;
+
+            fn div(self, rhs: Vector<$t, N>) -> Self::Output {
+                rhs.map(|c| self / c)
+            }
+        }
+    )+};
+}
+
+impl_scalar_lhs!(f32, f64, i32, u32, Fixed);
+
+// lossless conversions between element types
+macro_rules! impl_widen {
+    ($($from:ty => $to:ty),+) => {$(
+        impl<const N: usize> From<Vector<$from, N>> for Vector<$to, N> {
+            fn from(value: Vector<$from, N>) -> Self {
+                value.map(Into::into)
+            }
+        }
+    )+};
+}
+
+impl_widen!(f32 => f64, i32 => f64, u32 => f64);
+
+// window and image sizes, as reported by glfw (i32) and image (u32)
+macro_rules! impl_size {
+    ($($from:ty),+) => {$(
+        impl From<($from, $from)> for Vec2 {
+            fn from((x, y): ($from, $from)) -> Self {
+                Self([x as f32, y as f32])
+            }
+        }
+    )+};
+}
+
+impl_size!(i32, u32);
+
+// glfw takes and reports cursor positions as (f64, f64)
+// going the other way, use DVec2::from(pos).cast::<f32>()
+impl From<Vec2> for (f64, f64) {
+    fn from(value: Vec2) -> Self {
+        (value.x.into(), value.y.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Fixed;
+    use crate::math::Ray;
+
+    #[test]
+    fn normalize_short_vectors() {
+        assert_eq!(Vec2::new(0.02, 0.0).normalize(), Vec2::new(1.0, 0.0));
+        assert_eq!(Vec2::new(0.0, -1e-30).normalize(), Vec2::new(0.0, -1.0));
+        assert_eq!(
+            Vec2::new(f32::MIN_POSITIVE, 0.0).normalize(),
+            Vec2::new(1.0, 0.0)
+        );
+        let tiny = Vec3::new(3e-25, 0.0, 4e-25).normalize();
+        assert!((tiny - Vec3::new(0.6, 0.0, 0.8)).len() < 1e-6);
+        assert!(tiny.is_unit());
+
+        assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+        assert!(Vec2::zero().is_zero());
+        assert!(!Vec2::new(1e-30, 0.0).is_zero());
+        assert!(!Vec2::new(0.0, -0.001).is_zero());
+
+        let d = Vector([Fixed::from_f64(0.003), Fixed::ZERO]).normalize();
+        assert!(d.is_unit() && d.y == Fixed::ZERO);
+
+        // a short direction still has a direction
+        let ray = Ray::new(Vec2::zero(), Vec2::new(0.0, 0.01));
+        assert_eq!(ray.direction, Vec2::new(0.0, 1.0));
+    }
+}
@@ -1,6 +1,9 @@
 pub mod data;
+pub mod ecs;
 pub mod event;
 pub mod math;
+pub mod scene;
 pub mod socket;
 pub mod time;
+pub mod tween;
 pub mod window;
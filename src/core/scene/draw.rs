@@ -0,0 +1,31 @@
+use super::Scene;
+use crate::render::queue::RenderQueue;
+use crate::render::shader::IShader;
+use crate::render::sprite::ISprite;
+use crate::render::texture::ITexture;
+
+/// queues the sprite of every node that has one, where its world transform puts it
+/// `sprites` gives the sprite for the name of a `SpriteAttachment`, nodes without one aren't drawn
+/// the queue is drawn on `RenderQueue::flush`
+///
+/// ex: draw_sprites(&scene, &mut queue, |name| assets.get(name), &shader)
+pub fn draw_sprites<'q, 'a, Sh, Tx, S>(
+    scene: &Scene,
+    queue: &mut RenderQueue<'q>,
+    sprites: impl Fn(&str) -> Option<&'q S>,
+    shader: &'q Sh,
+) where
+    Sh: IShader,
+    Tx: ITexture<'a>,
+    S: ISprite<'a, Sh, Tx> + 'q,
+{
+    for (id, node) in scene.iter() {
+        let Some(attachment) = &node.sprite else {
+            continue;
+        };
+        if let Some(sprite) = sprites(&attachment.sprite) {
+            let matrix = scene.world_transform(id);
+            sprite.enqueue(queue, shader, matrix, attachment.params);
+        }
+    }
+}
@@ -0,0 +1,971 @@
+use std::collections::HashMap;
+
+use super::Node;
+use super::NodeId;
+use super::NodeShape;
+use super::Scene;
+use super::ShapeAttachment;
+use super::SpriteAttachment;
+use super::TextAttachment;
+use crate::data::Json;
+use crate::math::Aabb;
+use crate::math::Circle;
+use crate::math::Color;
+use crate::math::Transform2D;
+use crate::math::Vec2;
+use crate::render::queue::Blend;
+use crate::render::queue::DrawParams;
+
+// prefabs instancing prefabs, deeper than this is taken for a prefab instancing itself
+const MAX_DEPTH: usize = 32;
+
+/// the most nodes a scene or prefab expands to, so that prefabs each instancing several others
+/// can't make a small file take all the memory
+pub const MAX_NODES: usize = 100_000;
+
+const FIELDS: [&str; 12] = [
+    "name",
+    "prefab",
+    "overrides",
+    "position",
+    "rotation",
+    "scale",
+    "origin",
+    "skew",
+    "sprite",
+    "text",
+    "shape",
+    "children",
+];
+
+/// Reusable sub-scenes, by name, ex: an enemy with its weapon and health bar
+///
+/// a prefab is a node in the scene format, see `Scene::from_json`
+/// an instance of it is a node with a "prefab" field, any other field replaces the prefab's:
+///
+/// {
+///   "name": "boss",
+///   "prefab": "enemy",
+///   "scale": [2, 2],
+///   "overrides": {
+///     "weapon": {"sprite": {"name": "axe"}},
+///     "weapon/flash": null,
+///     "health_bar": {"children": [{"name": "crown", "sprite": {"name": "crown"}}]}
+///   },
+///   "children": [{"name": "aura", "shape": {"circle": [0, 0, 64], "color": "#ff000080"}}]
+/// }
+///
+/// overrides are by path inside the prefab, objects are merged field by field, null removes a field,
+/// or the node itself, and children are added after the prefab's
+#[derive(Debug, Clone, Default)]
+pub struct Prefabs {
+    prefabs: HashMap<String, Json>,
+}
+
+impl Prefabs {
+    pub fn new() -> Self {
+        Self {
+            prefabs: HashMap::new(),
+        }
+    }
+
+    /// adds or replaces a prefab, from the text of its file
+    pub fn insert(&mut self, name: &str, src: &str) -> Result<(), String> {
+        let json = Json::parse(src).map_err(|e| format!("Bad prefab: {name}: {e}"))?;
+        self.insert_json(name, json);
+        Ok(())
+    }
+
+    pub fn insert_json(&mut self, name: &str, json: Json) {
+        self.prefabs.insert(name.to_string(), json);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Json> {
+        self.prefabs.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        self.prefabs.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    // the node with every prefab instance under it replaced by the prefab and its overrides
+    // `nodes` counts the nodes expanded so far
+    fn expand(&self, node: &Json, depth: usize, nodes: &mut usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err("Bad prefab: nested too deep, does a prefab instance itself?".to_string());
+        }
+        *nodes += 1;
+        if *nodes > MAX_NODES {
+            return Err(format!(
+                "Bad scene: more than {MAX_NODES} nodes once the prefabs are expanded"
+            ));
+        }
+        let Json::Object(fields) = node else {
+            return Err("Bad scene: a node must be an object".to_string());
+        };
+        if let Some((key, _)) = fields.iter().find(|f| !FIELDS.contains(&f.0.as_str())) {
+            let name = node.get("name").and_then(Json::as_str).unwrap_or("");
+            return Err(format!(
+                "Bad scene: unknown field \"{key}\" in node \"{name}\""
+            ));
+        }
+
+        let Some(prefab) = node.get("prefab") else {
+            let mut ret = node.clone();
+            if let Some(children) = node.get("children") {
+                ret.insert("children", self.expand_children(children, depth, nodes)?);
+            }
+            return Ok(ret);
+        };
+
+        let name = prefab
+            .as_str()
+            .ok_or("Bad prefab: the name must be a string")?;
+        let base = self
+            .get(name)
+            .ok_or_else(|| format!("Bad prefab: {name} isn't defined"))?;
+        let mut ret = self.expand(base, depth + 1, nodes)?;
+        ret.remove("overrides");
+        self.patch(&mut ret, node, depth, nodes)?;
+        ret.insert("prefab", name.into());
+
+        if let Some(overrides) = node.get("overrides") {
+            let overrides = overrides
+                .as_object()
+                .ok_or_else(|| format!("Bad prefab: the overrides of {name} must be an object"))?;
+            for (path, patch) in overrides {
+                self.apply_override(&mut ret, path, patch, depth, nodes)
+                    .map_err(|e| format!("{e} in the overrides of {name}"))?;
+            }
+        }
+        Ok(ret)
+    }
+
+    fn expand_children(
+        &self,
+        children: &Json,
+        depth: usize,
+        nodes: &mut usize,
+    ) -> Result<Json, String> {
+        let children = children
+            .as_array()
+            .ok_or("Bad scene: children must be an array")?;
+        let expanded = children.iter().map(|c| self.expand(c, depth, nodes));
+        Ok(Json::Array(expanded.collect::<Result<_, _>>()?))
+    }
+
+    // merges the fields of `patch` into `node`, and adds its children after the node's
+    fn patch(
+        &self,
+        node: &mut Json,
+        patch: &Json,
+        depth: usize,
+        nodes: &mut usize,
+    ) -> Result<(), String> {
+        let Json::Object(fields) = patch else {
+            return Err("Bad prefab: an override must be an object or null".to_string());
+        };
+        for (key, value) in fields {
+            match key.as_str() {
+                "prefab" | "overrides" => {}
+                "children" => {
+                    let Json::Array(extra) = self.expand_children(value, depth, nodes)? else {
+                        unreachable!();
+                    };
+                    match node.get_mut("children") {
+                        Some(Json::Array(children)) => children.extend(extra),
+                        _ => node.insert("children", Json::Array(extra)),
+                    }
+                }
+                _ => merge(node, key, value),
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_override(
+        &self,
+        node: &mut Json,
+        path: &str,
+        patch: &Json,
+        depth: usize,
+        nodes: &mut usize,
+    ) -> Result<(), String> {
+        let names: Vec<&str> = path.split('/').filter(|n| !n.is_empty()).collect();
+        let missing = || format!("Bad prefab: no node at \"{path}\"");
+
+        let mut target = node;
+        if *patch == Json::Null {
+            let Some((last, parents)) = names.split_last() else {
+                return Err("Bad prefab: the root can't be removed".to_string());
+            };
+            for name in parents {
+                target = child_mut(target, name).ok_or_else(missing)?;
+            }
+            let Some(Json::Array(children)) = target.get_mut("children") else {
+                return Err(missing());
+            };
+            let i = children
+                .iter()
+                .position(|c| node_name(c) == *last)
+                .ok_or_else(missing)?;
+            children.remove(i);
+            return Ok(());
+        }
+
+        for name in &names {
+            target = child_mut(target, name).ok_or_else(missing)?;
+        }
+        self.patch(target, patch, depth, nodes)
+    }
+}
+
+impl Scene {
+    /// loads a scene saved by `to_json`, or written by hand
+    ///
+    /// the file is the root node, and every node is an object with only a name required:
+    ///
+    /// {
+    ///   "name": "level_1",
+    ///   "children": [
+    ///     {
+    ///       "name": "player",
+    ///       "position": [64, 32],
+    ///       "rotation": 90,
+    ///       "scale": [2, 2],
+    ///       "sprite": {"name": "hero", "layer": 1, "z": 0.5, "blend": "opaque"},
+    ///       "children": [{"name": "weapon", "position": [8, 0], "sprite": {"name": "sword"}}]
+    ///     },
+    ///     {"name": "title", "text": {"text": "Level 1", "size": 32, "color": "#ffcc00"}},
+    ///     {"name": "wall", "shape": {"rect": [0, 0, 640, 16], "color": "#808080"}},
+    ///     {"name": "goblin", "prefab": "enemy", "position": [300, 32]}
+    ///   ]
+    /// }
+    ///
+    /// rotations are in degrees, "origin" and "skew" complete the transform,
+    /// shapes are "rect": [min x, min y, max x, max y], "circle": [x, y, radius] or "polygon": [[x, y], ...]
+    /// see `Prefabs` for the instances
+    pub fn from_json(src: &str, prefabs: &Prefabs) -> Result<Self, String> {
+        let json = Json::parse(src)?;
+        let json = prefabs.expand(&json, 0, &mut 0)?;
+        let mut scene = Scene::from_root(node_from_json(&json)?);
+        scene.add_children(scene.root(), &json)?;
+        Ok(scene)
+    }
+
+    /// the scene in the format of `from_json`, with only the fields that aren't the default
+    /// the instances of prefabs only have what differs from their prefab,
+    /// unless the prefab is missing from `prefabs`, then they are saved in full,
+    /// as are instances with changes to nodes sharing their name with a sibling
+    /// colors are saved with 8 bits per channel
+    pub fn to_json(&self, prefabs: &Prefabs) -> String {
+        self.node_to_json(self.root(), prefabs).to_pretty_string()
+    }
+
+    /// adds an instance of a prefab as the last child of `parent`
+    pub fn instantiate(
+        &mut self,
+        prefabs: &Prefabs,
+        prefab: &str,
+        parent: NodeId,
+    ) -> Result<NodeId, String> {
+        let mut instance = Json::Object(Vec::new());
+        instance.insert("prefab", prefab.into());
+        let json = prefabs.expand(&instance, 0, &mut 0)?;
+        let id = self.add(parent, node_from_json(&json)?);
+        self.add_children(id, &json)?;
+        Ok(id)
+    }
+
+    // adds the children of an expanded node
+    fn add_children(&mut self, id: NodeId, json: &Json) -> Result<(), String> {
+        let Some(children) = json.get("children").and_then(Json::as_array) else {
+            return Ok(());
+        };
+        for child in children {
+            let child_id = self.add(id, node_from_json(child)?);
+            self.add_children(child_id, child)?;
+        }
+        Ok(())
+    }
+
+    fn node_to_json(&self, id: NodeId, prefabs: &Prefabs) -> Json {
+        let node = &self[id];
+        let prefab = node.prefab.as_deref().filter(|p| prefabs.contains(p));
+        let Some(prefab) = prefab else {
+            let mut ret = node_to_json(node);
+            if !self.children(id).is_empty() {
+                let children = self.children(id).iter();
+                let children = children.map(|&c| self.node_to_json(c, prefabs));
+                ret.insert("children", Json::Array(children.collect()));
+            }
+            return ret;
+        };
+
+        // the prefab as it is instantiated, to compare with
+        let mut base = Scene::new();
+        let Ok(base_id) = base.instantiate(prefabs, prefab, base.root()) else {
+            // a broken prefab, saved in full
+            let mut prefabs = prefabs.clone();
+            prefabs.remove(prefab);
+            return self.node_to_json(id, &prefabs);
+        };
+
+        let fields = diff(&node_to_json(&base[base_id]), &node_to_json(node));
+        let mut ret = prepend_name(fields.unwrap_or_default(), &node.name, Some(prefab));
+
+        let mut overrides = Json::Object(Vec::new());
+        let Some(extra) = self.diff_children(id, &base, base_id, "", &mut overrides, prefabs)
+        else {
+            // the overrides can't tell the nodes apart, saved in full
+            let mut prefabs = prefabs.clone();
+            prefabs.remove(prefab);
+            return self.node_to_json(id, &prefabs);
+        };
+        if overrides.as_object().is_some_and(|o| !o.is_empty()) {
+            ret.insert("overrides", overrides);
+        }
+        if !extra.is_empty() {
+            ret.insert("children", Json::Array(extra));
+        }
+        ret
+    }
+
+    // adds to `overrides` what differs between the children of `id` and those of `base_id`,
+    // returns the children that aren't in the base
+    // None if a node that differs has no path of its own, its name being shared or containing '/'
+    fn diff_children(
+        &self,
+        id: NodeId,
+        base: &Scene,
+        base_id: NodeId,
+        path: &str,
+        overrides: &mut Json,
+        prefabs: &Prefabs,
+    ) -> Option<Vec<Json>> {
+        let base_children = base.children(base_id);
+        let mut matched = vec![false; base_children.len()];
+        let mut extra = Vec::new();
+        let addressable = |name: &str| {
+            let shared = base_children.iter().filter(|&&c| base[c].name == name);
+            !name.is_empty() && !name.contains('/') && shared.count() == 1
+        };
+
+        for &child in self.children(id) {
+            let node = &self[child];
+            let found = (0..base_children.len()).find(|&i| {
+                let other = &base[base_children[i]];
+                !matched[i] && other.name == node.name && other.prefab == node.prefab
+            });
+            let Some(i) = found else {
+                extra.push(self.node_to_json(child, prefabs));
+                continue;
+            };
+            matched[i] = true;
+
+            let child_path = format!("{path}{}", node.name);
+            let base_child = base_children[i];
+            let fields = diff(&node_to_json(&base[base_child]), &node_to_json(node));
+            let nested_path = format!("{child_path}/");
+            let count = overrides.as_object().map_or(0, |o| o.len());
+            let nested =
+                self.diff_children(child, base, base_child, &nested_path, overrides, prefabs)?;
+
+            let mut patch = fields.unwrap_or(Json::Object(Vec::new()));
+            if !nested.is_empty() {
+                patch.insert("children", Json::Array(nested));
+            }
+            let changed = patch.as_object().is_some_and(|p| !p.is_empty());
+            let nested_changed = overrides.as_object().map_or(0, |o| o.len()) > count;
+            if (changed || nested_changed) && !addressable(&node.name) {
+                return None;
+            }
+            if changed {
+                overrides.insert(&child_path, patch);
+            }
+        }
+
+        for (i, &base_child) in base_children.iter().enumerate() {
+            if !matched[i] {
+                let name = &base[base_child].name;
+                if !addressable(name) {
+                    return None;
+                }
+                overrides.insert(&format!("{path}{name}"), Json::Null);
+            }
+        }
+        Some(extra)
+    }
+}
+
+// sets a field of a node, merging objects field by field, where null removes a field
+fn merge(node: &mut Json, key: &str, value: &Json) {
+    match (node.get_mut(key), value) {
+        (_, Json::Null) => {
+            node.remove(key);
+        }
+        (Some(old @ Json::Object(_)), Json::Object(fields)) => {
+            for (k, v) in fields {
+                merge(old, k, v);
+            }
+        }
+        _ => node.insert(key, value.clone()),
+    }
+}
+
+// what to merge into `base` to get `json`, None if they are the same
+fn diff(base: &Json, json: &Json) -> Option<Json> {
+    if base == json {
+        return None;
+    }
+    let (Json::Object(old), Json::Object(new)) = (base, json) else {
+        return Some(json.clone());
+    };
+
+    let mut ret = Json::Object(Vec::new());
+    for (key, value) in new {
+        let changed = match base.get(key) {
+            Some(old) => diff(old, value),
+            None => Some(value.clone()),
+        };
+        if let Some(changed) = changed {
+            ret.insert(key, changed);
+        }
+    }
+    for (key, _) in old {
+        if json.get(key).is_none() {
+            ret.insert(key, Json::Null);
+        }
+    }
+    Some(ret)
+}
+
+fn node_name(node: &Json) -> &str {
+    node.get("name").and_then(Json::as_str).unwrap_or("")
+}
+
+fn child_mut<'j>(node: &'j mut Json, name: &str) -> Option<&'j mut Json> {
+    let Some(Json::Array(children)) = node.get_mut("children") else {
+        return None;
+    };
+    children.iter_mut().find(|c| node_name(c) == name)
+}
+
+// the fields in the order they are written
+fn prepend_name(fields: Json, name: &str, prefab: Option<&str>) -> Json {
+    let mut ret = Json::Object(Vec::new());
+    ret.insert("name", name.into());
+    if let Some(prefab) = prefab {
+        ret.insert("prefab", prefab.into());
+    }
+    for (key, value) in fields.as_object().unwrap_or_default() {
+        if key != "name" {
+            ret.insert(key, value.clone());
+        }
+    }
+    ret
+}
+
+// the fields of a node, without its children, only those that aren't the default
+fn node_to_json(node: &Node) -> Json {
+    let mut ret = prepend_name(Json::Object(Vec::new()), &node.name, None);
+    let t = &node.transform;
+    let default = Transform2D::default();
+    if t.position() != default.position() {
+        ret.insert("position", vec2_to_json(t.position()));
+    }
+    if t.rotation() != default.rotation() {
+        ret.insert("rotation", t.rotation().into());
+    }
+    if t.scale() != default.scale() {
+        ret.insert("scale", vec2_to_json(t.scale()));
+    }
+    if t.origin() != default.origin() {
+        ret.insert("origin", vec2_to_json(t.origin()));
+    }
+    if t.skew() != default.skew() {
+        ret.insert("skew", vec2_to_json(t.skew()));
+    }
+
+    if let Some(sprite) = &node.sprite {
+        let mut json = Json::Object(Vec::new());
+        json.insert("name", sprite.sprite.as_str().into());
+        if sprite.params.layer != 0 {
+            json.insert("layer", sprite.params.layer.into());
+        }
+        if sprite.params.z != 0.0 {
+            json.insert("z", sprite.params.z.into());
+        }
+        if sprite.params.blend == Blend::Opaque {
+            json.insert("blend", "opaque".into());
+        }
+        ret.insert("sprite", json);
+    }
+
+    if let Some(text) = &node.text {
+        let mut json = Json::Object(Vec::new());
+        json.insert("text", text.text.as_str().into());
+        json.insert("size", text.size.into());
+        if text.color != Color::WHITE {
+            json.insert("color", text.color.to_hex().into());
+        }
+        ret.insert("text", json);
+    }
+
+    if let Some(shape) = &node.shape {
+        let mut json = Json::Object(Vec::new());
+        match &shape.shape {
+            NodeShape::Rect(r) => json.insert(
+                "rect",
+                floats_to_json(&[r.min.x, r.min.y, r.max.x, r.max.y]),
+            ),
+            NodeShape::Circle(c) => json.insert(
+                "circle",
+                floats_to_json(&[c.center.x, c.center.y, c.radius]),
+            ),
+            NodeShape::Polygon(points) => json.insert(
+                "polygon",
+                Json::Array(points.iter().map(|&p| vec2_to_json(p)).collect()),
+            ),
+        }
+        if shape.color != Color::WHITE {
+            json.insert("color", shape.color.to_hex().into());
+        }
+        ret.insert("shape", json);
+    }
+    ret
+}
+
+// a node from an expanded node, without its children
+fn node_from_json(json: &Json) -> Result<Node, String> {
+    let name = json
+        .get("name")
+        .ok_or("Bad scene: a node has no name")?
+        .as_str()
+        .ok_or("Bad scene: a name must be a string")?;
+    let error = |field: &str, expected: &str| {
+        format!("Bad scene: the {field} of \"{name}\" must be {expected}")
+    };
+
+    let mut node = Node::new(name);
+    if let Some(prefab) = json.get("prefab") {
+        node.prefab = prefab.as_str().map(str::to_string);
+    }
+
+    let t = &mut node.transform;
+    let vec2 = |field: &str| -> Result<Option<Vec2>, String> {
+        json.get(field)
+            .map(|v| {
+                floats(v, 2)
+                    .map(|f| Vec2::new(f[0], f[1]))
+                    .ok_or_else(|| error(field, "[x, y]"))
+            })
+            .transpose()
+    };
+    if let Some(position) = vec2("position")? {
+        t.set_position(position);
+    }
+    if let Some(rotation) = json.get("rotation") {
+        t.set_rotation(
+            rotation
+                .as_f64()
+                .ok_or_else(|| error("rotation", "a number"))? as f32,
+        );
+    }
+    if let Some(scale) = vec2("scale")? {
+        t.set_scale(scale);
+    }
+    if let Some(origin) = vec2("origin")? {
+        t.set_origin(origin);
+    }
+    if let Some(skew) = vec2("skew")? {
+        t.set_skew(skew);
+    }
+
+    let color = |json: &Json, field: &str| -> Result<Color, String> {
+        match json.get("color") {
+            Some(c) => {
+                let hex = c
+                    .as_str()
+                    .ok_or_else(|| error(field, "a color like \"#ff8000\""))?;
+                Color::from_hex(hex)
+            }
+            None => Ok(Color::WHITE),
+        }
+    };
+
+    if let Some(sprite) = json.get("sprite") {
+        let expected = || {
+            error(
+                "sprite",
+                "{\"name\": ..., \"layer\": ..., \"z\": ..., \"blend\": ...}",
+            )
+        };
+        let sprite_name = sprite
+            .get("name")
+            .and_then(Json::as_str)
+            .ok_or_else(expected)?;
+        let mut params = DrawParams::default();
+        if let Some(layer) = sprite.get("layer") {
+            params.layer = layer.as_f64().ok_or_else(expected)? as i32;
+        }
+        if let Some(z) = sprite.get("z") {
+            params.z = z.as_f64().ok_or_else(expected)? as f32;
+        }
+        if let Some(blend) = sprite.get("blend") {
+            params.blend = match blend.as_str() {
+                Some("opaque") => Blend::Opaque,
+                Some("transparent") => Blend::Transparent,
+                _ => return Err(error("blend", "\"opaque\" or \"transparent\"")),
+            };
+        }
+        node.sprite = Some(SpriteAttachment::new(sprite_name).with_params(params));
+    }
+
+    if let Some(text) = json.get("text") {
+        let expected = || error("text", "{\"text\": ..., \"size\": ..., \"color\": ...}");
+        let content = text
+            .get("text")
+            .and_then(Json::as_str)
+            .ok_or_else(expected)?;
+        let size = text
+            .get("size")
+            .and_then(Json::as_f64)
+            .ok_or_else(expected)?;
+        let attachment = TextAttachment::new(content, size as f32).with_color(color(text, "text")?);
+        node.text = Some(attachment);
+    }
+
+    if let Some(shape) = json.get("shape") {
+        let expected = || {
+            error(
+                "shape",
+                "{\"rect\" or \"circle\" or \"polygon\": ..., \"color\": ...}",
+            )
+        };
+        let outline = if let Some(rect) = shape.get("rect") {
+            let f = floats(rect, 4).ok_or_else(expected)?;
+            NodeShape::Rect(Aabb::new(Vec2::new(f[0], f[1]), Vec2::new(f[2], f[3])))
+        } else if let Some(circle) = shape.get("circle") {
+            let f = floats(circle, 3).ok_or_else(expected)?;
+            NodeShape::Circle(Circle::new(Vec2::new(f[0], f[1]), f[2]))
+        } else if let Some(polygon) = shape.get("polygon") {
+            let points = polygon.as_array().ok_or_else(expected)?;
+            let points = points
+                .iter()
+                .map(|p| floats(p, 2).map(|f| Vec2::new(f[0], f[1])));
+            NodeShape::Polygon(points.collect::<Option<_>>().ok_or_else(expected)?)
+        } else {
+            return Err(expected());
+        };
+        node.shape = Some(ShapeAttachment::new(outline).with_color(color(shape, "shape")?));
+    }
+    Ok(node)
+}
+
+// an array of exactly `n` numbers
+fn floats(json: &Json, n: usize) -> Option<Vec<f32>> {
+    let items = json.as_array().filter(|a| a.len() == n)?;
+    items.iter().map(|v| v.as_f64().map(|f| f as f32)).collect()
+}
+
+fn floats_to_json(floats: &[f32]) -> Json {
+    Json::Array(floats.iter().map(|&f| f.into()).collect())
+}
+
+fn vec2_to_json(v: Vec2) -> Json {
+    floats_to_json(&[v.x, v.y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENEMY: &str = r##"{
+      "name": "enemy",
+      "sprite": {"name": "goblin", "layer": 1},
+      "children": [
+        {
+          "name": "weapon",
+          "position": [8, 0],
+          "sprite": {"name": "sword", "z": 0.5},
+          "children": [{"name": "flash", "shape": {"circle": [0, 0, 4], "color": "#ffff00"}}]
+        },
+        {"name": "health_bar", "position": [0, -12], "shape": {"rect": [-8, -1, 8, 1]}}
+      ]
+    }"##;
+
+    fn prefabs() -> Prefabs {
+        let mut prefabs = Prefabs::new();
+        prefabs.insert("enemy", ENEMY).unwrap();
+        prefabs
+    }
+
+    // a level with a single node, the instance
+    fn load(instance: &str, prefabs: &Prefabs) -> Result<Scene, String> {
+        Scene::from_json(
+            &format!(r#"{{"name": "level", "children": [{instance}]}}"#),
+            prefabs,
+        )
+    }
+
+    fn paths(scene: &Scene) -> Vec<String> {
+        let nodes = scene.iter().skip(1);
+        nodes.map(|(id, _)| scene.path_of(id)).collect()
+    }
+
+    fn sprite(scene: &Scene, path: &str) -> Option<SpriteAttachment> {
+        scene[scene.find(path).unwrap()].sprite.clone()
+    }
+
+    #[test]
+    fn round_trip() {
+        let src = r##"{
+          "name": "level",
+          "children": [
+            {
+              "name": "player",
+              "position": [64, 32],
+              "rotation": 90,
+              "scale": [2, 0.5],
+              "origin": [4, 4],
+              "skew": [10, 0],
+              "sprite": {"name": "hero", "layer": -2, "z": 0.25, "blend": "opaque"},
+              "children": [{"name": "weapon", "position": [8, 0], "sprite": {"name": "sword"}}]
+            },
+            {"name": "title", "text": {"text": "Level 1", "size": 32, "color": "#ffcc00"}},
+            {"name": "zone", "shape": {"rect": [0, 0, 640, 16], "color": "#ff000080"}},
+            {"name": "floor", "shape": {"polygon": [[0, 0], [640, 0], [320, 16]]}}
+          ]
+        }"##;
+        let scene = Scene::from_json(src, &Prefabs::new()).unwrap();
+        assert_eq!(scene.len(), 6);
+        let saved = scene.to_json(&Prefabs::new());
+        assert_eq!(Json::parse(&saved).unwrap(), Json::parse(src).unwrap());
+
+        let player = scene.find("player").unwrap();
+        let t = &scene[player].transform;
+        assert_eq!(t.rotation(), 90.0);
+        assert_eq!(t.skew(), Vec2::new(10.0, 0.0));
+        let params = scene[player].sprite.as_ref().unwrap().params;
+        assert_eq!(
+            (params.layer, params.z, params.blend),
+            (-2, 0.25, Blend::Opaque)
+        );
+        let title = scene[scene.find("title").unwrap()].text.clone().unwrap();
+        assert_eq!(title.color.to_hex(), "#ffcc00");
+    }
+
+    #[test]
+    fn instances() {
+        let scene = load(r#"{"name": "goblin", "prefab": "enemy"}"#, &prefabs()).unwrap();
+        let expected = [
+            "goblin",
+            "goblin/weapon",
+            "goblin/weapon/flash",
+            "goblin/health_bar",
+        ];
+        assert_eq!(paths(&scene), expected);
+        let goblin = scene.find("goblin").unwrap();
+        assert_eq!(scene[goblin].prefab.as_deref(), Some("enemy"));
+        assert_eq!(sprite(&scene, "goblin/weapon").unwrap().params.z, 0.5);
+
+        // saved as the instance only, and without the prefabs in full
+        let saved = scene.to_json(&prefabs());
+        let level = Json::parse(&saved).unwrap();
+        let instance = &level.get("children").unwrap().as_array().unwrap()[0];
+        assert_eq!(
+            *instance,
+            Json::parse(r#"{"name": "goblin", "prefab": "enemy"}"#).unwrap()
+        );
+        let full = Scene::from_json(&scene.to_json(&Prefabs::new()), &Prefabs::new()).unwrap();
+        assert_eq!(paths(&full), expected);
+    }
+
+    #[test]
+    fn null_removes() {
+        let instance = r#"{
+          "name": "goblin",
+          "prefab": "enemy",
+          "sprite": null,
+          "overrides": {
+            "weapon": {"sprite": {"z": null}},
+            "weapon/flash": null,
+            "health_bar": {"position": null}
+          }
+        }"#;
+        let scene = load(instance, &prefabs()).unwrap();
+        assert_eq!(
+            paths(&scene),
+            ["goblin", "goblin/weapon", "goblin/health_bar"]
+        );
+        assert!(sprite(&scene, "goblin").is_none());
+        let weapon = sprite(&scene, "goblin/weapon").unwrap();
+        assert_eq!((weapon.sprite.as_str(), weapon.params.z), ("sword", 0.0));
+        let bar = scene.find("goblin/health_bar").unwrap();
+        assert_eq!(scene[bar].transform.position(), Vec2::zero());
+
+        // saved back as the same overrides
+        let saved = scene.to_json(&prefabs());
+        let loaded = Scene::from_json(&saved, &prefabs()).unwrap();
+        assert_eq!(loaded.to_json(&prefabs()), saved);
+        assert_eq!(paths(&loaded), paths(&scene));
+        assert!(sprite(&loaded, "goblin").is_none());
+
+        // the root isn't removed by a null override
+        let root = r#"{"name": "goblin", "prefab": "enemy", "overrides": {"": null}}"#;
+        assert!(load(root, &prefabs()).is_err());
+    }
+
+    #[test]
+    fn children_merge() {
+        let instance = r#"{
+          "name": "goblin",
+          "prefab": "enemy",
+          "overrides": {"health_bar": {"children": [{"name": "crown", "sprite": {"name": "crown"}}]}},
+          "children": [{"name": "aura", "shape": {"circle": [0, 0, 64]}}]
+        }"#;
+        let scene = load(instance, &prefabs()).unwrap();
+        let expected = [
+            "goblin",
+            "goblin/weapon",
+            "goblin/weapon/flash",
+            "goblin/health_bar",
+            "goblin/health_bar/crown",
+            "goblin/aura",
+        ];
+        assert_eq!(paths(&scene), expected);
+
+        // edits are saved as overrides, added nodes as children
+        let mut scene = scene;
+        scene.remove(scene.find("goblin/weapon").unwrap());
+        let bar = scene.find("goblin/health_bar").unwrap();
+        scene.add(bar, Node::new("skull"));
+        let saved = scene.to_json(&prefabs());
+        let level = Json::parse(&saved).unwrap();
+        let instance = &level.get("children").unwrap().as_array().unwrap()[0];
+        let overrides = instance.get("overrides").unwrap();
+        assert_eq!(overrides.get("weapon"), Some(&Json::Null));
+        let added = overrides
+            .get("health_bar")
+            .unwrap()
+            .get("children")
+            .unwrap();
+        assert_eq!(added.as_array().unwrap().len(), 2);
+
+        let loaded = Scene::from_json(&saved, &prefabs()).unwrap();
+        assert_eq!(paths(&loaded), paths(&scene));
+    }
+
+    #[test]
+    fn stale_prefabs() {
+        let error = load(r#"{"name": "ghost", "prefab": "ghost"}"#, &prefabs()).unwrap_err();
+        assert_eq!(error, "Bad prefab: ghost isn't defined");
+
+        // an override of a node the prefab no longer has
+        let instance = r#"{"name": "goblin", "prefab": "enemy", "overrides": {"shield": {"position": [1, 1]}}}"#;
+        let error = load(instance, &prefabs()).unwrap_err();
+        assert_eq!(
+            error,
+            "Bad prefab: no node at \"shield\" in the overrides of enemy"
+        );
+        let instance =
+            r#"{"name": "goblin", "prefab": "enemy", "overrides": {"weapon/shield": null}}"#;
+        assert!(load(instance, &prefabs()).is_err());
+
+        // a prefab instancing itself
+        let mut looping = prefabs();
+        looping
+            .insert("loop", r#"{"name": "loop", "prefab": "loop"}"#)
+            .unwrap();
+        let error = load(r#"{"name": "a", "prefab": "loop"}"#, &looping).unwrap_err();
+        assert!(error.contains("nested too deep"));
+
+        // once the prefab is gone, the instances are saved in full, as plain nodes
+        let mut scene = load(r#"{"name": "goblin", "prefab": "enemy"}"#, &prefabs()).unwrap();
+        assert!(scene
+            .instantiate(&prefabs(), "ghost", scene.root())
+            .is_err());
+        let saved = scene.to_json(&Prefabs::new());
+        let level = Json::parse(&saved).unwrap();
+        let instance = &level.get("children").unwrap().as_array().unwrap()[0];
+        assert!(instance.get("prefab").is_none());
+        assert_eq!(
+            instance.get("children").unwrap().as_array().unwrap().len(),
+            2
+        );
+        let loaded = Scene::from_json(&saved, &Prefabs::new()).unwrap();
+        assert_eq!(paths(&loaded), paths(&scene));
+    }
+
+    #[test]
+    fn fan_out() {
+        // each prefab with 10 instances of the one before
+        let mut prefabs = Prefabs::new();
+        prefabs.insert("p0", r#"{"name": "leaf"}"#).unwrap();
+        for i in 1..8 {
+            let child = format!(r#"{{"name": "c", "prefab": "p{}"}}"#, i - 1);
+            let children = vec![child; 10].join(", ");
+            let src = format!(r#"{{"name": "p{i}", "children": [{children}]}}"#);
+            prefabs.insert(&format!("p{i}"), &src).unwrap();
+        }
+
+        let scene = load(r#"{"name": "a", "prefab": "p3"}"#, &prefabs).unwrap();
+        assert_eq!(scene.len(), 2 + 10 + 100 + 1000);
+        let error = load(r#"{"name": "a", "prefab": "p7"}"#, &prefabs).unwrap_err();
+        assert_eq!(
+            error,
+            format!("Bad scene: more than {MAX_NODES} nodes once the prefabs are expanded")
+        );
+    }
+
+    #[test]
+    fn shared_names() {
+        let mut prefabs = prefabs();
+        let src =
+            r#"{"name": "face", "children": [{"name": "eye"}, {"name": "eye"}, {"name": "a/b"}]}"#;
+        prefabs.insert("face", src).unwrap();
+        let mut scene = load(r#"{"name": "face", "prefab": "face"}"#, &prefabs).unwrap();
+
+        // unchanged, saved as the instance only
+        let saved = scene.to_json(&prefabs);
+        assert!(!saved.contains("eye"));
+
+        // a change to the second eye can't be an override, the instance is saved in full
+        let face = scene.find("face").unwrap();
+        let second = scene.children(face)[1];
+        scene[second].transform.set_position(Vec2::new(4.0, 0.0));
+        let loaded = Scene::from_json(&scene.to_json(&prefabs), &prefabs).unwrap();
+        let face = loaded.find("face").unwrap();
+        let eyes: Vec<Vec2> = loaded.children(face)[..2]
+            .iter()
+            .map(|&c| loaded[c].transform.position())
+            .collect();
+        assert_eq!(eyes, [Vec2::zero(), Vec2::new(4.0, 0.0)]);
+
+        // as is removing one of them, or changing a node with a '/' in its name
+        for removed in [1, 2] {
+            let mut scene = load(r#"{"name": "face", "prefab": "face"}"#, &prefabs).unwrap();
+            let face = scene.find("face").unwrap();
+            let child = scene.children(face)[removed];
+            if removed == 1 {
+                scene.remove(child);
+            } else {
+                scene[child].transform.set_rotation(45.0);
+            }
+            let loaded = Scene::from_json(&scene.to_json(&prefabs), &prefabs).unwrap();
+            assert_eq!(
+                loaded.to_json(&Prefabs::new()),
+                scene.to_json(&Prefabs::new())
+            );
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// a scene tree: named nodes with transforms relative to their parents and things to draw,
+// loaded and saved as JSON, with prefabs for the parts that repeat
+
+mod draw;
+pub use draw::*;
+
+mod format;
+pub use format::*;
+
+mod node;
+pub use node::*;
+
+mod tree;
+pub use tree::*;
@@ -0,0 +1,134 @@
+use crate::math::Circle;
+use crate::math::Color;
+use crate::math::Rect;
+use crate::math::Transform2D;
+use crate::math::Vec2;
+use crate::render::queue::DrawParams;
+
+/// A node of a `Scene`: a name, a transform relative to its parent, and what to draw there
+///
+/// ex: Node::new("weapon")
+///     .with_transform(Transform2D::from_position(Vec2::new(8.0, 0.0)))
+///     .with_sprite(SpriteAttachment::new("sword"))
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    /// looked up by `Scene::find`, so unique among its siblings
+    pub name: String,
+    pub transform: Transform2D,
+    pub sprite: Option<SpriteAttachment>,
+    pub text: Option<TextAttachment>,
+    pub shape: Option<ShapeAttachment>,
+    /// the prefab the node was instantiated from, its children are part of the instance
+    /// saving writes the node as an instance of it, with only what differs
+    pub prefab: Option<String>,
+}
+
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform2D) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.transform.set_position(position);
+        self
+    }
+
+    pub fn with_sprite(mut self, sprite: SpriteAttachment) -> Self {
+        self.sprite = Some(sprite);
+        self
+    }
+
+    pub fn with_text(mut self, text: TextAttachment) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    pub fn with_shape(mut self, shape: ShapeAttachment) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+}
+
+/// A sprite drawn at its node, see `draw_sprites`
+#[derive(Debug, Clone, Default)]
+pub struct SpriteAttachment {
+    /// resolved to a sprite when drawing, ex: the file name of its texture
+    pub sprite: String,
+    pub params: DrawParams,
+}
+
+impl SpriteAttachment {
+    pub fn new(sprite: &str) -> Self {
+        Self {
+            sprite: sprite.to_string(),
+            params: DrawParams::default(),
+        }
+    }
+
+    pub fn with_params(mut self, params: DrawParams) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+/// A label drawn at its node, by the game's own text rendering
+#[derive(Debug, Clone)]
+pub struct TextAttachment {
+    pub text: String,
+    /// in pixels
+    pub size: f32,
+    pub color: Color,
+}
+
+impl TextAttachment {
+    pub fn new(text: &str, size: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            size,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// The outline of a `ShapeAttachment`, in the node's local space
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeShape {
+    Rect(Rect),
+    Circle(Circle),
+    /// the points in order, the polygon doesn't have to be convex
+    Polygon(Vec<Vec2>),
+}
+
+/// A colored shape at its node, ex: a trigger zone or a debug outline
+#[derive(Debug, Clone)]
+pub struct ShapeAttachment {
+    pub shape: NodeShape,
+    pub color: Color,
+}
+
+impl ShapeAttachment {
+    pub fn new(shape: NodeShape) -> Self {
+        Self {
+            shape,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
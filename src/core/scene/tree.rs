@@ -0,0 +1,232 @@
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use super::Node;
+use crate::data::Handle;
+use crate::data::SecondaryMap;
+use crate::data::SlotMap;
+use crate::math::Mat3;
+
+/// Identifies a node of a `Scene`, stale once the node is removed
+pub type NodeId = Handle<Node>;
+
+// where a node is in the tree, apart from the node so that replacing a node keeps it in place
+#[derive(Debug, Clone, Default)]
+struct Links {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// A tree of named nodes, each with a transform relative to its parent
+///
+/// nodes are found by their path from the root, ex: `scene.find("player/weapon")`
+/// scenes are loaded and saved as JSON, see `Scene::from_json`
+///
+/// ex:
+/// let mut scene = Scene::new();
+/// let player = scene.add(scene.root(), Node::new("player").with_sprite(SpriteAttachment::new("hero")));
+/// scene.add(player, Node::new("weapon").with_position(Vec2::new(8.0, 0.0)));
+/// let weapon = scene.find("player/weapon").unwrap();
+/// let m = scene.world_transform(weapon);
+#[derive(Debug, Clone)]
+pub struct Scene {
+    nodes: SlotMap<Node>,
+    links: SecondaryMap<Node, Links>,
+    root: NodeId,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene {
+    /// an empty scene, with only a root node named "root"
+    pub fn new() -> Self {
+        Self::from_root(Node::new("root"))
+    }
+
+    pub fn from_root(root: Node) -> Self {
+        let mut nodes = SlotMap::new();
+        let mut links = SecondaryMap::new();
+        let root = nodes.insert(root);
+        links.insert(root, Links::default());
+        Self { nodes, links, root }
+    }
+
+    /// the root is never removed, and isn't part of the paths
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// how many nodes there are, the root included
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// always false, there is always the root
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// adds a node as the last child of `parent`
+    /// panics if `parent` was removed
+    pub fn add(&mut self, parent: NodeId, node: Node) -> NodeId {
+        assert!(self.contains(parent), "Bad node: {parent:?}");
+        let id = self.nodes.insert(node);
+        self.links.insert(
+            id,
+            Links {
+                parent: Some(parent),
+                children: Vec::new(),
+            },
+        );
+        self.links[parent].children.push(id);
+        id
+    }
+
+    /// removes the node and all of its descendants, returns the node
+    /// None if it was already removed, or if it is the root
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        if id == self.root || !self.contains(id) {
+            return None;
+        }
+        let parent = self.links[id].parent.unwrap();
+        self.links[parent].children.retain(|&c| c != id);
+
+        let mut stack = self.links[id].children.clone();
+        while let Some(child) = stack.pop() {
+            stack.extend_from_slice(&self.links[child].children);
+            self.links.remove(child);
+            self.nodes.remove(child);
+        }
+        self.links.remove(id);
+        self.nodes.remove(id)
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains(id)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id)
+    }
+
+    /// None for the root
+    /// panics if the node was removed
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.links(id).parent
+    }
+
+    /// in order, the later ones drawn on top when on the same layer and depth
+    /// panics if the node was removed
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.links(id).children
+    }
+
+    /// moves the node to the end of the children of `parent`, keeping its local transform
+    /// moving the root, or moving a node under one of its own descendants, is ignored
+    pub fn set_parent(&mut self, id: NodeId, parent: NodeId) {
+        if id == self.root || !self.contains(id) || !self.contains(parent) {
+            return;
+        }
+        if self.ancestors(parent).any(|a| a == id) {
+            return;
+        }
+
+        let old = self.links[id].parent.unwrap();
+        self.links[old].children.retain(|&c| c != id);
+        self.links[parent].children.push(id);
+        self.links[id].parent = Some(parent);
+    }
+
+    /// the node, then its parent, up to the root
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let first = Some(id).filter(|&id| self.contains(id));
+        std::iter::successors(first, |&id| self.links[id].parent)
+    }
+
+    /// the node and everything under it, parents before their children, in order
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut stack = vec![id];
+        std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.children(id).iter().rev());
+            Some(id)
+        })
+    }
+
+    /// every node, parents before their children, in order
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.descendants(self.root).map(|id| (id, &self.nodes[id]))
+    }
+
+    /// the node at `path` from the root, ex: "player/weapon"
+    pub fn find(&self, path: &str) -> Option<NodeId> {
+        self.find_from(self.root, path)
+    }
+
+    /// the node at `path` from `id`, where ".." is the parent, ex: "../enemy/weapon"
+    /// with several siblings of the same name, the first one is found
+    pub fn find_from(&self, id: NodeId, path: &str) -> Option<NodeId> {
+        let mut id = id;
+        for name in path.split('/').filter(|n| !n.is_empty() && *n != ".") {
+            id = if name == ".." {
+                self.links.get(id)?.parent?
+            } else {
+                let children = &self.links.get(id)?.children;
+                *children.iter().find(|&&c| self.nodes[c].name == name)?
+            };
+        }
+        Some(id)
+    }
+
+    /// the path that finds the node from the root, "" for the root
+    /// panics if the node was removed
+    pub fn path_of(&self, id: NodeId) -> String {
+        assert!(self.contains(id), "Bad node: {id:?}");
+        let mut names: Vec<&str> = self
+            .ancestors(id)
+            .take_while(|&a| a != self.root)
+            .map(|a| self.nodes[a].name.as_str())
+            .collect();
+        names.reverse();
+        names.join("/")
+    }
+
+    /// the transform from the node's local space to the scene's space
+    /// panics if the node was removed
+    pub fn world_transform(&self, id: NodeId) -> Mat3 {
+        assert!(self.contains(id), "Bad node: {id:?}");
+        self.ancestors(id).fold(Mat3::identity(), |m, a| {
+            self.nodes[a].transform.matrix() * m
+        })
+    }
+
+    fn links(&self, id: NodeId) -> &Links {
+        self.links
+            .get(id)
+            .unwrap_or_else(|| panic!("Bad node: {id:?}"))
+    }
+}
+
+/// panics if the node was removed
+impl Index<NodeId> for Scene {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        self.get(id).unwrap_or_else(|| panic!("Bad node: {id:?}"))
+    }
+}
+
+impl IndexMut<NodeId> for Scene {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        self.get_mut(id)
+            .unwrap_or_else(|| panic!("Bad node: {id:?}"))
+    }
+}
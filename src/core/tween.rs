@@ -0,0 +1,708 @@
+use std::f32::consts::PI;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use crate::math::Color;
+use crate::math::Transform2D;
+use crate::math::Vector;
+use crate::time::Tickable;
+
+/// The shape of an easing curve, as its "in" version
+/// (slow at the start, fast at the end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    /// overshoots backwards before moving
+    Back,
+    /// wobbles like a spring
+    Elastic,
+    /// bounces like a dropped ball
+    Bounce,
+}
+
+impl Curve {
+    pub fn ease_in(self, t: f32) -> f32 {
+        match self {
+            Curve::Quad => t * t,
+            Curve::Cubic => t * t * t,
+            Curve::Quart => t * t * t * t,
+            Curve::Quint => t * t * t * t * t,
+            Curve::Sine => 1.0 - (t * PI / 2.0).cos(),
+            Curve::Expo if t <= 0.0 => 0.0,
+            Curve::Expo => 2f32.powf(10.0 * t - 10.0),
+            Curve::Circ => 1.0 - (1.0 - t * t).max(0.0).sqrt(),
+            Curve::Back => {
+                const C1: f32 = 1.70158;
+                (C1 + 1.0) * t * t * t - C1 * t * t
+            }
+            Curve::Elastic if t <= 0.0 || t >= 1.0 => t,
+            Curve::Elastic => {
+                -(2f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * (2.0 * PI / 3.0)).sin()
+            }
+            Curve::Bounce => 1.0 - bounce_out(1.0 - t),
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// The standard (Penner) easing functions
+/// ex: Ease::Out(Curve::Bounce), Ease::InOut(Curve::Cubic)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    /// slow start
+    In(Curve),
+    /// slow end
+    Out(Curve),
+    /// slow start and end
+    InOut(Curve),
+}
+
+impl Ease {
+    /// maps the progress `t` in [0, 1] onto the curve
+    /// the result starts at 0 and ends at 1, but may leave that range in between
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::In(c) => c.ease_in(t),
+            Ease::Out(c) => 1.0 - c.ease_in(1.0 - t),
+            Ease::InOut(c) if t < 0.5 => c.ease_in(2.0 * t) / 2.0,
+            Ease::InOut(c) => 1.0 - c.ease_in(2.0 - 2.0 * t) / 2.0,
+        }
+    }
+}
+
+/// A value that can be interpolated
+pub trait Lerp {
+    /// `t` = 0 gives `self` and `t` = 1 gives `other`
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + t * (other - self)
+    }
+}
+
+impl<const N: usize> Lerp for Vector<f32, N> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Vector::lerp(*self, *other, t)
+    }
+}
+
+// in linear space, use a `Gradient` for a perceptual transition
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::lerp(*self, *other, t)
+    }
+}
+
+impl Lerp for Transform2D {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Transform2D::lerp(self, other, t)
+    }
+}
+
+/// Something that plays out over time, and can be combined with `Sequence` and `Group`
+pub trait Animation {
+    /// moves the animation forward
+    /// returns the part of `dt` left over after the animation finished, zero otherwise
+    fn advance(&mut self, dt: Duration) -> Duration;
+
+    fn is_finished(&self) -> bool;
+
+    /// rewinds back to the start
+    fn reset(&mut self);
+}
+
+// called with the new value of a tween
+type UpdateFn<T> = Box<dyn FnMut(&T)>;
+
+// called once an animation finishes
+#[derive(Default)]
+struct Completion(Vec<Box<dyn FnMut()>>);
+
+impl Completion {
+    fn add(&mut self, f: impl FnMut() + 'static) {
+        self.0.push(Box::new(f));
+    }
+
+    // sends a copy of `data` through an `EventManager` notifier
+    fn add_notifier<D: Clone + 'static>(&mut self, notifier: Sender<D>, data: D) {
+        // the manager may be gone, nobody is listening then
+        self.add(move || {
+            let _ = notifier.send(data.clone());
+        });
+    }
+
+    fn fire(&mut self) {
+        for f in &mut self.0 {
+            f();
+        }
+    }
+}
+
+/// Interpolates a value over time
+///
+/// ex: fade in a sprite after a second, then notify the game
+/// let done = events.make_notifier(Event::FadedIn);
+/// let mut fade = Tween::new(Color::TRANSPARENT, Color::WHITE, Duration::from_millis(500))
+///     .ease(Ease::Out(Curve::Quad))
+///     .delay(Duration::from_secs(1))
+///     .notify(done, ());
+/// // every frame
+/// fade.tick(dt);
+/// instance.tint = fade.value();
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: Duration,
+    ease: Ease,
+    delay: Duration,
+    // extra plays after the first one, None to repeat forever
+    repeat: Option<u32>,
+    yoyo: bool,
+
+    elapsed: Duration,
+    delay_left: Duration,
+    repeats_left: Option<u32>,
+    // the number of completed plays, odd plays go backwards when yoyoing
+    cycle: u32,
+    finished: bool,
+
+    on_update: Vec<UpdateFn<T>>,
+    on_complete: Completion,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            ease: Ease::Linear,
+            delay: Duration::ZERO,
+            repeat: Some(0),
+            yoyo: false,
+            elapsed: Duration::ZERO,
+            delay_left: Duration::ZERO,
+            repeats_left: Some(0),
+            cycle: 0,
+            finished: false,
+            on_update: Vec::new(),
+            on_complete: Completion::default(),
+        }
+    }
+
+    pub fn ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    /// waits before starting, only once (not between repeats)
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self.delay_left = delay;
+        self
+    }
+
+    /// plays `count` more times after the first play
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.repeat = Some(count);
+        self.repeats_left = Some(count);
+        self
+    }
+
+    /// never finishes
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = None;
+        self.repeats_left = None;
+        self
+    }
+
+    /// every other play goes from `to` back to `from`
+    pub fn yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    /// called with the new value on every tick, ex: to move a sprite stored elsewhere
+    pub fn on_update(mut self, f: impl FnMut(&T) + 'static) -> Self {
+        self.on_update.push(Box::new(f));
+        self
+    }
+
+    pub fn on_complete(mut self, f: impl FnMut() + 'static) -> Self {
+        self.on_complete.add(f);
+        self
+    }
+
+    /// sends `data` through a notifier from `EventManager::make_notifier` on completion
+    pub fn notify<D: Clone + 'static>(mut self, notifier: Sender<D>, data: D) -> Self {
+        self.on_complete.add_notifier(notifier, data);
+        self
+    }
+
+    /// the eased progress of the current play, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        };
+
+        if self.yoyo && self.cycle % 2 == 1 {
+            self.ease.apply(1.0 - t)
+        } else {
+            self.ease.apply(t)
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.from.lerp(&self.to, self.progress())
+    }
+
+    fn update(&mut self) {
+        if self.on_update.is_empty() {
+            return;
+        }
+
+        let value = self.value();
+        for f in &mut self.on_update {
+            f(&value);
+        }
+    }
+}
+
+impl<T: Lerp> Animation for Tween<T> {
+    fn advance(&mut self, mut dt: Duration) -> Duration {
+        if self.finished {
+            return dt;
+        }
+
+        let wait = dt.min(self.delay_left);
+        self.delay_left -= wait;
+        dt -= wait;
+        if !self.delay_left.is_zero() {
+            return Duration::ZERO;
+        }
+
+        loop {
+            let left = self.duration - self.elapsed;
+            if dt < left {
+                self.elapsed += dt;
+                self.update();
+                return Duration::ZERO;
+            }
+
+            // the current play is over
+            dt -= left;
+            self.elapsed = self.duration;
+            match self.repeats_left {
+                Some(0) => break,
+                Some(ref mut n) => *n -= 1,
+                // an endless instant tween would never leave this loop
+                None if self.duration.is_zero() => {
+                    self.update();
+                    return Duration::ZERO;
+                }
+                None => (),
+            }
+            self.cycle += 1;
+            self.elapsed = Duration::ZERO;
+        }
+
+        self.finished = true;
+        self.update();
+        self.on_complete.fire();
+        dt
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.delay_left = self.delay;
+        self.repeats_left = self.repeat;
+        self.cycle = 0;
+        self.finished = false;
+    }
+}
+
+impl<T: Lerp> Tickable for Tween<T> {
+    fn tick(&mut self, dt: Duration) {
+        self.advance(dt);
+    }
+}
+
+/// Waits, ex: between two parts of a `Sequence`
+pub struct Delay {
+    duration: Duration,
+    left: Duration,
+}
+
+impl Delay {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            left: duration,
+        }
+    }
+}
+
+impl Animation for Delay {
+    fn advance(&mut self, dt: Duration) -> Duration {
+        let wait = dt.min(self.left);
+        self.left -= wait;
+        dt - wait
+    }
+
+    fn is_finished(&self) -> bool {
+        self.left.is_zero()
+    }
+
+    fn reset(&mut self) {
+        self.left = self.duration;
+    }
+}
+
+impl Tickable for Delay {
+    fn tick(&mut self, dt: Duration) {
+        self.advance(dt);
+    }
+}
+
+/// Plays animations one after the other
+///
+/// ex: move right, wait, then move back
+/// let mut seq = Sequence::new()
+///     .then(Tween::new(a, b, secs).on_update(move_player))
+///     .wait(Duration::from_millis(250))
+///     .then(Tween::new(b, a, secs).on_update(move_player));
+#[derive(Default)]
+pub struct Sequence {
+    steps: Vec<Box<dyn Animation>>,
+    current: usize,
+    on_complete: Completion,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn then(mut self, animation: impl Animation + 'static) -> Self {
+        self.steps.push(Box::new(animation));
+        self
+    }
+
+    pub fn wait(self, duration: Duration) -> Self {
+        self.then(Delay::new(duration))
+    }
+
+    pub fn on_complete(mut self, f: impl FnMut() + 'static) -> Self {
+        self.on_complete.add(f);
+        self
+    }
+
+    /// sends `data` through a notifier from `EventManager::make_notifier` on completion
+    pub fn notify<D: Clone + 'static>(mut self, notifier: Sender<D>, data: D) -> Self {
+        self.on_complete.add_notifier(notifier, data);
+        self
+    }
+}
+
+impl Animation for Sequence {
+    fn advance(&mut self, mut dt: Duration) -> Duration {
+        if self.is_finished() {
+            return dt;
+        }
+
+        while let Some(step) = self.steps.get_mut(self.current) {
+            dt = step.advance(dt);
+            if !step.is_finished() {
+                return Duration::ZERO;
+            }
+            self.current += 1;
+        }
+
+        self.on_complete.fire();
+        dt
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current == self.steps.len()
+    }
+
+    fn reset(&mut self) {
+        self.steps.iter_mut().for_each(|s| s.reset());
+        self.current = 0;
+    }
+}
+
+impl Tickable for Sequence {
+    fn tick(&mut self, dt: Duration) {
+        self.advance(dt);
+    }
+}
+
+/// Plays animations at the same time, finishing with the longest one
+#[derive(Default)]
+pub struct Group {
+    members: Vec<Box<dyn Animation>>,
+    finished: bool,
+    on_complete: Completion,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, animation: impl Animation + 'static) -> Self {
+        self.members.push(Box::new(animation));
+        self
+    }
+
+    pub fn on_complete(mut self, f: impl FnMut() + 'static) -> Self {
+        self.on_complete.add(f);
+        self
+    }
+
+    /// sends `data` through a notifier from `EventManager::make_notifier` on completion
+    pub fn notify<D: Clone + 'static>(mut self, notifier: Sender<D>, data: D) -> Self {
+        self.on_complete.add_notifier(notifier, data);
+        self
+    }
+}
+
+impl Animation for Group {
+    fn advance(&mut self, dt: Duration) -> Duration {
+        if self.finished {
+            return dt;
+        }
+
+        // the time left after the longest member finished
+        let left = self
+            .members
+            .iter_mut()
+            .map(|m| m.advance(dt))
+            .min()
+            .unwrap_or(dt);
+
+        if !self.members.iter().all(|m| m.is_finished()) {
+            return Duration::ZERO;
+        }
+
+        self.finished = true;
+        self.on_complete.fire();
+        left
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.members.iter_mut().for_each(|m| m.reset());
+        self.finished = false;
+    }
+}
+
+impl Tickable for Group {
+    fn tick(&mut self, dt: Duration) {
+        self.advance(dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    const CURVES: [Curve; 10] = [
+        Curve::Quad,
+        Curve::Cubic,
+        Curve::Quart,
+        Curve::Quint,
+        Curve::Sine,
+        Curve::Expo,
+        Curve::Circ,
+        Curve::Back,
+        Curve::Elastic,
+        Curve::Bounce,
+    ];
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    // counts the calls of on_complete
+    fn counter() -> (Rc<Cell<u32>>, impl FnMut() + 'static) {
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+        (count, move || c.set(c.get() + 1))
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        let eases = CURVES
+            .iter()
+            .flat_map(|&c| [Ease::In(c), Ease::Out(c), Ease::InOut(c)])
+            .chain([Ease::Linear]);
+        for ease in eases {
+            assert!(ease.apply(0.0).abs() < 1e-6, "{ease:?}");
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-6, "{ease:?}");
+            // clamped outside of [0, 1]
+            assert_eq!(ease.apply(-1.0), ease.apply(0.0), "{ease:?}");
+            assert_eq!(ease.apply(2.0), ease.apply(1.0), "{ease:?}");
+            if let Ease::InOut(_) = ease {
+                assert!((ease.apply(0.5) - 0.5).abs() < 1e-6, "{ease:?}");
+            }
+        }
+
+        // the curves that don't overshoot only move forward
+        for c in &CURVES[..7] {
+            let values: Vec<f32> = (0..=100).map(|i| c.ease_in(i as f32 / 100.0)).collect();
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "{c:?}");
+        }
+        assert!(Ease::In(Curve::Back).apply(0.2) < 0.0);
+        assert!(Ease::Out(Curve::Back).apply(0.8) > 1.0);
+        assert_eq!(Ease::In(Curve::Quad).apply(0.5), 0.25);
+        assert_eq!(Ease::Out(Curve::Quad).apply(0.5), 0.75);
+    }
+
+    #[test]
+    fn repeat_and_yoyo() {
+        let (done, on_complete) = counter();
+        let mut tween = Tween::new(0.0, 10.0, ms(1000))
+            .repeat(2)
+            .yoyo(true)
+            .on_complete(on_complete);
+
+        assert_eq!(tween.advance(ms(250)), Duration::ZERO);
+        assert_eq!(tween.value(), 2.5);
+        // the second play goes back
+        assert_eq!(tween.advance(ms(1000)), Duration::ZERO);
+        assert_eq!(tween.value(), 7.5);
+        // the third goes forward again
+        assert_eq!(tween.advance(ms(1000)), Duration::ZERO);
+        assert_eq!(tween.value(), 2.5);
+        assert!(!tween.is_finished());
+
+        // the time past the end is handed back
+        assert_eq!(tween.advance(ms(1000)), ms(250));
+        assert!(tween.is_finished());
+        assert_eq!(tween.value(), 10.0);
+        assert_eq!(tween.advance(ms(100)), ms(100));
+        assert_eq!(done.get(), 1);
+
+        // several plays in one step
+        tween.reset();
+        assert_eq!(tween.value(), 0.0);
+        assert_eq!(tween.advance(ms(2500)), Duration::ZERO);
+        assert_eq!(tween.value(), 5.0);
+        assert_eq!(tween.advance(ms(600)), ms(100));
+        assert_eq!(done.get(), 2);
+
+        // an even number of plays ends back at the start
+        let mut tween = Tween::new(0.0, 10.0, ms(1000)).repeat(1).yoyo(true);
+        assert_eq!(tween.advance(ms(3000)), ms(1000));
+        assert_eq!(tween.value(), 0.0);
+    }
+
+    #[test]
+    fn delay() {
+        let mut tween = Tween::new(0.0, 10.0, ms(1000)).delay(ms(500)).repeat(1);
+        assert_eq!(tween.advance(ms(400)), Duration::ZERO);
+        assert_eq!(tween.value(), 0.0);
+        assert_eq!(tween.advance(ms(350)), Duration::ZERO);
+        assert_eq!(tween.value(), 2.5);
+        // not again between the plays
+        assert_eq!(tween.advance(ms(1000)), Duration::ZERO);
+        assert_eq!(tween.value(), 2.5);
+        assert_eq!(tween.advance(ms(1000)), ms(250));
+
+        tween.reset();
+        assert_eq!(tween.advance(ms(750)), Duration::ZERO);
+        assert_eq!(tween.value(), 2.5);
+    }
+
+    #[test]
+    fn endless_and_instant() {
+        let mut tween = Tween::new(0.0, 10.0, ms(1000)).repeat_forever();
+        assert_eq!(
+            tween.advance(Duration::from_secs(1_000_000)),
+            Duration::ZERO
+        );
+        assert!(!tween.is_finished());
+
+        let mut tween = Tween::new(0.0, 10.0, Duration::ZERO).repeat_forever();
+        assert_eq!(tween.advance(ms(100)), Duration::ZERO);
+        assert_eq!(tween.value(), 10.0);
+
+        let mut tween = Tween::new(0.0, 10.0, Duration::ZERO);
+        assert_eq!(tween.advance(ms(100)), ms(100));
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn sequence_and_group() {
+        let (done, on_complete) = counter();
+        let mut seq = Sequence::new()
+            .then(Tween::new(0.0, 1.0, ms(1000)))
+            .wait(ms(500))
+            .then(Tween::new(0.0, 1.0, ms(1000)))
+            .on_complete(on_complete);
+        // the time left by a step goes to the next one
+        assert_eq!(seq.advance(ms(2250)), Duration::ZERO);
+        assert_eq!(seq.current, 2);
+        assert_eq!(seq.advance(ms(1000)), ms(750));
+        assert!(seq.is_finished());
+        assert_eq!(done.get(), 1);
+        seq.reset();
+        assert_eq!(seq.advance(ms(2500)), Duration::ZERO);
+        assert!(seq.is_finished());
+        assert_eq!(done.get(), 2);
+
+        let (done, on_complete) = counter();
+        let mut group = Group::new()
+            .with(Tween::new(0.0, 1.0, ms(1000)))
+            .with(Delay::new(ms(1500)))
+            .on_complete(on_complete);
+        assert_eq!(group.advance(ms(1200)), Duration::ZERO);
+        assert!(!group.is_finished());
+        assert_eq!(group.advance(ms(500)), ms(200));
+        assert!(group.is_finished());
+        assert_eq!(done.get(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use std::ffi::c_void;
 
+use crate::math::Color;
 use crate::window::DrawContext as Context;
 
 use self::gl::types::GLenum;
@@ -73,11 +74,15 @@ pub fn init(f: impl FnMut(&'static str) -> *const c_void) {
     }
 }
 
+// the color is linear, it gets encoded to sRGB along with everything else
+pub fn set_clear_color(color: Color) {
+    verify! { gl::ClearColor(color.r, color.g, color.b, color.a) };
+}
+
 pub fn clear() {
     verify! {
-        // set by default to this value anyway
-        // gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT);
+        // transparent black unless changed by set_clear_color
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
     }
 }
 
@@ -163,3 +168,37 @@ impl<'a> Drop for Texture<'a> {
         verify! { gl::DeleteTextures(1, &self.0) }
     }
 }
+
+#[allow(dead_code)]
+pub struct Framebuffer<'a>(pub GLuint, &'a Context);
+
+impl<'a> Framebuffer<'a> {
+    pub fn new(ctx: &'a Context) -> Self {
+        let mut n = 0;
+        verify! { gl::CreateFramebuffers(1, &mut n) };
+        Self(n, ctx)
+    }
+}
+
+impl<'a> Drop for Framebuffer<'a> {
+    fn drop(&mut self) {
+        verify! { gl::DeleteFramebuffers(1, &self.0) }
+    }
+}
+
+#[allow(dead_code)]
+pub struct Renderbuffer<'a>(pub GLuint, &'a Context);
+
+impl<'a> Renderbuffer<'a> {
+    pub fn new(ctx: &'a Context) -> Self {
+        let mut n = 0;
+        verify! { gl::CreateRenderbuffers(1, &mut n) };
+        Self(n, ctx)
+    }
+}
+
+impl<'a> Drop for Renderbuffer<'a> {
+    fn drop(&mut self) {
+        verify! { gl::DeleteRenderbuffers(1, &self.0) }
+    }
+}
@@ -0,0 +1,557 @@
+// 2D lighting
+// lights are accumulated into a light map which is then multiplied over the scene
+// a frame with lighting looks like:
+//
+// lighting.begin_normals();
+// // draw normal mapped sprites using `NORMAL_FRAGMENT_SHADER`
+// lighting.end_normals();
+// // draw the scene as usual
+// lighting.render(&lights, &occluders);
+// lighting.composite();
+//
+// all positions and distances are in clip space, like the rest of the renderer
+
+// export implementation
+pub use imp::*;
+
+use crate::math::UVec2;
+use crate::math::Vec2;
+use crate::math::Vec3;
+use crate::window::DrawContext as Context;
+
+#[derive(Debug, Clone, Copy)]
+pub enum LightShape {
+    /// shines in every direction
+    Point,
+    /// shines in a cone around `direction`
+    /// angles are measured in degrees from `direction` to the edge of the cone
+    /// the light fades out between `inner_angle` and `outer_angle`
+    Spot {
+        direction: Vec2,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shadow {
+    /// the light passes through occluders
+    None,
+    /// sharp shadows, cast from the light's center
+    Hard,
+    /// shadows with a penumbra, approximated by casting
+    /// `samples` hard shadows from points spread over a disk of `source_radius`
+    Soft { source_radius: f32, samples: u32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub position: Vec2,
+    pub color: Vec3,
+    pub intensity: f32,
+    /// the distance at which the light reaches zero
+    pub radius: f32,
+    /// the exponent of the attenuation curve, 1 is linear
+    pub falloff: f32,
+    /// how far the light is above the scene, used for normal mapping
+    /// smaller values give more pronounced normal maps
+    pub height: f32,
+    pub shape: LightShape,
+    pub shadow: Shadow,
+}
+
+impl Light {
+    pub fn point(position: Vec2, color: Vec3, radius: f32) -> Self {
+        Self {
+            position,
+            color,
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            height: 0.1,
+            shape: LightShape::Point,
+            shadow: Shadow::Hard,
+        }
+    }
+
+    pub fn spot(position: Vec2, color: Vec3, radius: f32, direction: Vec2, angle: f32) -> Self {
+        Self {
+            shape: LightShape::Spot {
+                direction: direction.normalize(),
+                inner_angle: 0.8 * angle,
+                outer_angle: angle,
+            },
+            ..Self::point(position, color, radius)
+        }
+    }
+
+    // the points that the shadows are cast from
+    fn shadow_origins(&self) -> Vec<Vec2> {
+        match self.shadow {
+            Shadow::None => Vec::new(),
+            Shadow::Hard => vec![self.position],
+            Shadow::Soft {
+                source_radius,
+                samples,
+            } => {
+                // spread evenly over the disk using the golden angle
+                const GOLDEN_ANGLE: f32 = 2.399_963;
+                let n = samples.max(1);
+                (0..n)
+                    .map(|i| {
+                        let r = source_radius * ((i as f32 + 0.5) / n as f32).sqrt();
+                        let th = i as f32 * GOLDEN_ANGLE;
+                        self.position + Vec2::new(r * th.cos(), r * th.sin())
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A closed polygon that blocks light
+#[derive(Debug, Default, Clone)]
+pub struct Occluder {
+    pub points: Vec<Vec2>,
+}
+
+impl Occluder {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self { points }
+    }
+
+    /// the triangles of the area shadowed by this occluder, as seen from `origin`
+    /// in homogeneous coordinates, every edge is extruded to infinity (w = 0) away from the origin
+    /// so the shadow has no far end whatever the angle
+    pub fn shadow_volume(&self, origin: Vec2, out: &mut Vec<Vec3>) {
+        let n = self.points.len();
+        if n < 2 {
+            return;
+        }
+
+        let point = |p: Vec2| Vec3::new(p.x, p.y, 1.0);
+        let extrude = |p: Vec2| {
+            let d = p - origin;
+            Vec3::new(d.x, d.y, 0.0)
+        };
+        for i in 0..n {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            let (pa, pb) = (point(a), point(b));
+            let (ea, eb) = (extrude(a), extrude(b));
+            out.extend_from_slice(&[pa, pb, eb, pa, eb, ea]);
+        }
+    }
+}
+
+pub trait ILighting<'a>: Sized {
+    /// `size` is the size of the framebuffer in pixels
+    fn new<'c: 'a>(ctx: &'c Context, size: UVec2) -> Self;
+    fn resize(&mut self, size: UVec2);
+
+    /// the light every pixel receives regardless of lights
+    fn set_ambient(&mut self, ambient: Vec3);
+
+    /// redirects drawing into the normal buffer
+    fn begin_normals(&self);
+    /// restores drawing to the screen
+    fn end_normals(&self);
+
+    /// accumulates `lights` into the light map
+    fn render(&mut self, lights: &[Light], occluders: &[Occluder]);
+
+    /// multiplies the light map over what has been drawn to the screen
+    fn composite(&self);
+}
+
+/// Fragment shader for drawing normal mapped sprites into the normal buffer
+/// pair with the regular sprite vertex shader
+pub const NORMAL_FRAGMENT_SHADER: &str = r#"
+#version 450 core
+
+uniform sampler2D uTexture;
+uniform sampler2D uNormalMap;
+
+in vec2 texUV;
+
+out vec4 FragColor;
+
+void main() {
+    float alpha = texture(uTexture, texUV).a;
+    FragColor = vec4(texture(uNormalMap, texUV).rgb, alpha);
+}
+"#;
+
+#[cfg(feature = "gl45")]
+mod imp {
+    use std::ptr::null;
+
+    use super::Context;
+    use super::ILighting;
+    use super::Light;
+    use super::LightShape;
+    use super::Occluder;
+    use crate::math::UVec2;
+    use crate::math::Vec2;
+    use crate::math::Vec3;
+    use crate::render::api as gl;
+    use crate::render::api::types::GLenum;
+    use crate::render::api::Framebuffer;
+    use crate::render::api::Renderbuffer;
+    use crate::render::mesh::AttribType;
+    use crate::render::mesh::IMesh;
+    use crate::render::mesh::Mesh;
+    use crate::render::mesh::Topology;
+    use crate::render::mesh::Usage;
+    use crate::render::mesh::VertexLayout;
+    use crate::render::shader::IShader;
+    use crate::render::shader::IShaderBuilder;
+    use crate::render::shader::PartType;
+    use crate::render::shader::Shader;
+    use crate::render::shader::ShaderBuilder;
+    use crate::render::shader::ShaderPart;
+
+    const QUAD_VERTEX_SHADER: &str = r#"
+    #version 450 core
+
+    layout(location = 0)
+    in vec2 aPos;
+
+    out vec2 texUV;
+
+    void main() {
+        texUV = 0.5 * aPos + 0.5;
+        gl_Position = vec4(aPos, 0.0, 1.0);
+    }
+    "#;
+
+    const COMPOSITE_FRAGMENT_SHADER: &str = r#"
+    #version 450 core
+
+    uniform sampler2D uLightMap;
+
+    in vec2 texUV;
+
+    out vec4 FragColor;
+
+    void main() {
+        FragColor = vec4(texture(uLightMap, texUV).rgb, 1.0);
+    }
+    "#;
+
+    const LIGHT_VERTEX_SHADER: &str = r#"
+    #version 450 core
+
+    uniform vec2 uLightPos;
+    uniform float uRadius;
+
+    layout(location = 0)
+    in vec2 aPos;
+
+    out vec2 fragPos;
+
+    void main() {
+        fragPos = uLightPos + uRadius * aPos;
+        gl_Position = vec4(fragPos, 0.0, 1.0);
+    }
+    "#;
+
+    const LIGHT_FRAGMENT_SHADER: &str = r#"
+    #version 450 core
+
+    uniform sampler2D uNormals;
+    uniform vec2 uLightPos;
+    uniform vec3 uColor;
+    uniform float uRadius;
+    uniform float uFalloff;
+    uniform float uHeight;
+    uniform vec2 uDirection;
+    uniform float uCosInner;
+    uniform float uCosOuter;
+
+    in vec2 fragPos;
+
+    out vec4 FragColor;
+
+    void main() {
+        vec2 d = fragPos - uLightPos;
+        float dist = length(d);
+        if (dist > uRadius) {
+            discard;
+        }
+
+        float attenuation = pow(1.0 - dist / uRadius, uFalloff);
+
+        vec2 dir = dist > 0.0 ? d / dist : uDirection;
+        float cone = smoothstep(uCosOuter, uCosInner, dot(dir, uDirection));
+
+        vec2 uv = gl_FragCoord.xy / vec2(textureSize(uNormals, 0));
+        vec3 n = normalize(2.0 * texture(uNormals, uv).xyz - 1.0);
+        vec3 l = normalize(vec3(-d, uHeight));
+        float diffuse = max(dot(n, l), 0.0);
+
+        FragColor = vec4(uColor * attenuation * cone * diffuse, 1.0);
+    }
+    "#;
+
+    const SHADOW_VERTEX_SHADER: &str = r#"
+    #version 450 core
+
+    // w = 0 for the points extruded to infinity
+    layout(location = 0)
+    in vec3 aPos;
+
+    void main() {
+        gl_Position = vec4(aPos.xy, 0.0, aPos.z);
+    }
+    "#;
+
+    const SHADOW_FRAGMENT_SHADER: &str = r#"
+    #version 450 core
+
+    out vec4 FragColor;
+
+    void main() {
+        FragColor = vec4(0.0);
+    }
+    "#;
+
+    fn make_shader<'c>(ctx: &'c Context, vert: &str, frag: &str) -> Shader<'c> {
+        ShaderBuilder::new(ctx)
+            .add_part(ShaderPart {
+                type_: PartType::Vertex,
+                source_code: vert,
+            })
+            .and_then(|b| {
+                b.add_part(ShaderPart {
+                    type_: PartType::Fragment,
+                    source_code: frag,
+                })
+            })
+            .and_then(|b| b.verify())
+            .unwrap()
+    }
+
+    // a framebuffer with a single color texture
+    struct Target<'a> {
+        fbo: Framebuffer<'a>,
+        tex: gl::Texture<'a>,
+        // only the light map uses the stencil buffer
+        #[allow(dead_code)]
+        stencil: Option<Renderbuffer<'a>>,
+    }
+
+    impl<'a> Target<'a> {
+        fn new(ctx: &'a Context, size: UVec2, format: GLenum, stencil: bool) -> Self {
+            let fbo = Framebuffer::new(ctx);
+            let tex = gl::Texture::new(ctx);
+            gl::verify! {
+                gl::BindTexture(gl::TEXTURE_2D, tex.0);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, format as _, size.x as _, size.y as _, 0, gl::RGBA, gl::UNSIGNED_BYTE, null());
+                gl::NamedFramebufferTexture(fbo.0, gl::COLOR_ATTACHMENT0, tex.0, 0);
+            }
+
+            let stencil = stencil.then(|| {
+                let rb = Renderbuffer::new(ctx);
+                gl::verify! {
+                    gl::NamedRenderbufferStorage(rb.0, gl::STENCIL_INDEX8, size.x as _, size.y as _);
+                    gl::NamedFramebufferRenderbuffer(fbo.0, gl::STENCIL_ATTACHMENT, gl::RENDERBUFFER, rb.0);
+                }
+                rb
+            });
+
+            Self { fbo, tex, stencil }
+        }
+
+        // leaves the clear color set through `set_clear_color` alone
+        fn clear(&self, color: [f32; 4]) {
+            gl::verify! { gl::ClearNamedFramebufferfv(self.fbo.0, gl::COLOR, 0, color.as_ptr()) };
+        }
+
+        fn bind(&self, size: UVec2) {
+            gl::verify! {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo.0);
+                gl::Viewport(0, 0, size.x as _, size.y as _);
+            }
+        }
+    }
+
+    pub struct Lighting<'a> {
+        ctx: &'a Context,
+        size: UVec2,
+        ambient: Vec3,
+        light_map: Target<'a>,
+        normals: Target<'a>,
+        quad: Mesh<'a>,
+        shadow_mesh: Mesh<'a>,
+        shadow_vertices: Vec<Vec3>,
+        light_shader: Shader<'a>,
+        shadow_shader: Shader<'a>,
+        composite_shader: Shader<'a>,
+    }
+
+    impl<'a> Lighting<'a> {
+        fn unbind(&self) {
+            gl::verify! {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                gl::Viewport(0, 0, self.size.x as _, self.size.y as _);
+            }
+        }
+
+        fn set_light_parameters(&self, light: &Light) {
+            let s = &self.light_shader;
+            let (direction, cos_inner, cos_outer) = match light.shape {
+                // the cone covers every direction
+                LightShape::Point => (Vec2::new(0.0, 1.0), -1.0, -2.0),
+                LightShape::Spot {
+                    direction,
+                    inner_angle,
+                    outer_angle,
+                } => (
+                    direction.normalize(),
+                    inner_angle.to_radians().cos(),
+                    outer_angle.to_radians().cos(),
+                ),
+            };
+
+            s.set_parameter("uNormals", &0);
+            s.set_parameter("uLightPos", &light.position);
+            s.set_parameter("uColor", &(light.intensity * light.color));
+            s.set_parameter("uRadius", &light.radius);
+            s.set_parameter("uFalloff", &light.falloff);
+            s.set_parameter("uHeight", &light.height);
+            s.set_parameter("uDirection", &direction);
+            s.set_parameter("uCosInner", &cos_inner);
+            s.set_parameter("uCosOuter", &cos_outer);
+        }
+
+        // marks the shadowed area in the stencil buffer
+        fn draw_shadows(&mut self, origin: Vec2, occluders: &[Occluder]) {
+            self.shadow_vertices.clear();
+            for o in occluders {
+                o.shadow_volume(origin, &mut self.shadow_vertices);
+            }
+
+            gl::verify! {
+                gl::Clear(gl::STENCIL_BUFFER_BIT);
+                gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                gl::StencilFunc(gl::ALWAYS, 1, 0xFF);
+                gl::StencilOp(gl::KEEP, gl::KEEP, gl::REPLACE);
+            }
+
+            if !self.shadow_vertices.is_empty() {
+                self.shadow_shader.bind();
+                self.shadow_mesh.set_vertices(&self.shadow_vertices);
+                self.shadow_mesh.draw();
+            }
+
+            gl::verify! {
+                gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                gl::StencilFunc(gl::EQUAL, 0, 0xFF);
+                gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+            }
+        }
+    }
+
+    impl<'a> ILighting<'a> for Lighting<'a> {
+        fn new<'c: 'a>(ctx: &'c Context, size: UVec2) -> Self {
+            let layout = VertexLayout::new().attrib(2, AttribType::F32); // aPos
+            let mut quad = Mesh::new(ctx, layout, Topology::TriangleFan, Usage::Static);
+            let vertex_data: [f32; 8] = [-1.0, 1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0];
+            quad.set_vertices(&vertex_data);
+
+            Self {
+                ctx,
+                size,
+                ambient: Vec3::new(0.1, 0.1, 0.1),
+                light_map: Target::new(ctx, size, gl::RGBA16F, true),
+                normals: Target::new(ctx, size, gl::RGBA8, false),
+                quad,
+                shadow_mesh: Mesh::new(
+                    ctx,
+                    VertexLayout::new().attrib(3, AttribType::F32), // aPos
+                    Topology::Triangles,
+                    Usage::Stream,
+                ),
+                shadow_vertices: Vec::new(),
+                light_shader: make_shader(ctx, LIGHT_VERTEX_SHADER, LIGHT_FRAGMENT_SHADER),
+                shadow_shader: make_shader(ctx, SHADOW_VERTEX_SHADER, SHADOW_FRAGMENT_SHADER),
+                composite_shader: make_shader(ctx, QUAD_VERTEX_SHADER, COMPOSITE_FRAGMENT_SHADER),
+            }
+        }
+
+        fn resize(&mut self, size: UVec2) {
+            self.size = size;
+            self.light_map = Target::new(self.ctx, size, gl::RGBA16F, true);
+            self.normals = Target::new(self.ctx, size, gl::RGBA8, false);
+        }
+
+        fn set_ambient(&mut self, ambient: Vec3) {
+            self.ambient = ambient;
+        }
+
+        fn begin_normals(&self) {
+            self.normals.bind(self.size);
+            // facing the viewer
+            self.normals.clear([0.5, 0.5, 1.0, 1.0]);
+        }
+
+        fn end_normals(&self) {
+            self.unbind();
+        }
+
+        fn render(&mut self, lights: &[Light], occluders: &[Occluder]) {
+            self.light_map.bind(self.size);
+            let a = self.ambient;
+            self.light_map.clear([a.x, a.y, a.z, 1.0]);
+            gl::verify! {
+                gl::Enable(gl::STENCIL_TEST);
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::ONE, gl::ONE);
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, self.normals.tex.0);
+            }
+
+            for light in lights {
+                let origins = light.shadow_origins();
+                if origins.is_empty() || occluders.is_empty() {
+                    gl::verify! { gl::StencilFunc(gl::ALWAYS, 0, 0xFF) };
+                    self.set_light_parameters(light);
+                    self.quad.draw();
+                    continue;
+                }
+
+                // every shadow sample contributes an equal part of the light
+                let mut sample = *light;
+                sample.intensity /= origins.len() as f32;
+                for origin in origins {
+                    self.draw_shadows(origin, occluders);
+                    self.set_light_parameters(&sample);
+                    self.quad.draw();
+                }
+            }
+
+            gl::verify! {
+                gl::Disable(gl::STENCIL_TEST);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            }
+            self.unbind();
+        }
+
+        fn composite(&self) {
+            gl::verify! {
+                gl::Enable(gl::BLEND);
+                // scene * light
+                gl::BlendFunc(gl::DST_COLOR, gl::ZERO);
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, self.light_map.tex.0);
+            }
+            self.composite_shader.set_parameter("uLightMap", &0);
+            self.quad.draw();
+            gl::verify! { gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) };
+        }
+    }
+}
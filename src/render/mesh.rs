@@ -0,0 +1,408 @@
+// export implementation
+pub use imp::*;
+
+use crate::window::DrawContext as Context;
+
+/// The type of a single vertex attribute component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttribType {
+    F32,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+}
+
+impl AttribType {
+    pub fn size(self) -> usize {
+        match self {
+            AttribType::I8 | AttribType::U8 => 1,
+            AttribType::I16 | AttribType::U16 => 2,
+            AttribType::F32 | AttribType::I32 | AttribType::U32 => 4,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        self != AttribType::F32
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Attrib {
+    pub location: u32,
+    pub components: u32,
+    pub type_: AttribType,
+    /// integer attributes are remapped to [0,1] (or [-1,1] if signed) floats
+    /// non-normalized integer attributes reach the shader as integers
+    pub normalized: bool,
+    /// byte offset from the start of the vertex
+    pub offset: usize,
+}
+
+/// Describes how vertices are laid out in memory
+/// attributes are tightly packed in the order they are added
+/// and are assigned consecutive shader locations starting at 0
+///
+/// ex: the sprite layout
+/// VertexLayout::new()
+///     .attrib(2, AttribType::F32) // aPos
+///     .attrib(2, AttribType::F32) // aUV
+#[derive(Debug, Default, Clone)]
+pub struct VertexLayout {
+    attribs: Vec<Attrib>,
+    stride: usize,
+    first_location: u32,
+}
+
+impl VertexLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a layout whose first attribute is bound to `location`
+    /// used for per-instance data that follows the per-vertex attributes
+    pub fn starting_at(location: u32) -> Self {
+        Self {
+            first_location: location,
+            ..Self::default()
+        }
+    }
+
+    pub fn attrib(self, components: u32, type_: AttribType) -> Self {
+        self.push(components, type_, false)
+    }
+
+    pub fn normalized_attrib(self, components: u32, type_: AttribType) -> Self {
+        self.push(components, type_, true)
+    }
+
+    /// skips `bytes` bytes of each vertex, for data the shader doesn't read
+    pub fn padding(mut self, bytes: usize) -> Self {
+        self.stride += bytes;
+        self
+    }
+
+    pub fn attribs(&self) -> &[Attrib] {
+        &self.attribs
+    }
+
+    /// the size of a single vertex in bytes
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    fn push(mut self, components: u32, type_: AttribType, normalized: bool) -> Self {
+        assert!((1..=4).contains(&components), "Bad component count");
+        self.attribs.push(Attrib {
+            location: self.first_location + self.attribs.len() as u32,
+            components,
+            type_,
+            normalized,
+            offset: self.stride,
+        });
+        self.stride += components as usize * type_.size();
+        self
+    }
+}
+
+/// How the vertices of a mesh are assembled into primitives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+/// Hints how often the mesh data is going to change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Usage {
+    /// set once, drawn many times
+    Static,
+    /// changed occasionally, drawn many times
+    Dynamic,
+    /// changed every frame
+    Stream,
+}
+
+pub trait IMesh<'a>: Sized {
+    fn new<'c: 'a>(
+        ctx: &'c Context,
+        layout: VertexLayout,
+        topology: Topology,
+        usage: Usage,
+    ) -> Self;
+
+    /// uploads the vertex data, replacing the old data
+    /// the size of `data` in bytes must be a multiple of the layout's stride
+    fn set_vertices<V: Copy>(&mut self, data: &[V]);
+
+    /// uploads an index buffer, the mesh is then drawn indexed
+    fn set_indices(&mut self, indices: &[u32]);
+
+    /// goes back to non-indexed drawing
+    fn clear_indices(&mut self);
+
+    fn layout(&self) -> &VertexLayout;
+    fn vertex_count(&self) -> usize;
+
+    /// issues the draw call, the caller is responsible for binding a shader
+    fn draw(&self);
+
+    /// sets the layout of the per-instance data passed to `draw_instanced`
+    /// its locations must not overlap with the per-vertex layout
+    fn set_instance_layout(&mut self, layout: VertexLayout);
+
+    /// uploads `instances` and draws the mesh once for every instance
+    /// panics if no instance layout was set
+    fn draw_instanced<I: Copy>(&self, instances: &[I]);
+}
+
+#[cfg(feature = "gl45")]
+mod imp {
+    use std::cell::Cell;
+    use std::ffi::c_void;
+    use std::mem::size_of_val;
+    use std::ptr::null;
+
+    use super::AttribType;
+    use super::Context;
+    use super::IMesh;
+    use super::Topology;
+    use super::Usage;
+    use super::VertexLayout;
+    use crate::render::api as gl;
+    use crate::render::api::types::GLenum;
+    use crate::render::api::Buf;
+    use crate::render::api::Vao;
+
+    impl AttribType {
+        fn api(self) -> GLenum {
+            match self {
+                AttribType::F32 => gl::FLOAT,
+                AttribType::I8 => gl::BYTE,
+                AttribType::U8 => gl::UNSIGNED_BYTE,
+                AttribType::I16 => gl::SHORT,
+                AttribType::U16 => gl::UNSIGNED_SHORT,
+                AttribType::I32 => gl::INT,
+                AttribType::U32 => gl::UNSIGNED_INT,
+            }
+        }
+    }
+
+    impl Topology {
+        fn api(self) -> GLenum {
+            match self {
+                Topology::Points => gl::POINTS,
+                Topology::Lines => gl::LINES,
+                Topology::LineStrip => gl::LINE_STRIP,
+                Topology::LineLoop => gl::LINE_LOOP,
+                Topology::Triangles => gl::TRIANGLES,
+                Topology::TriangleStrip => gl::TRIANGLE_STRIP,
+                Topology::TriangleFan => gl::TRIANGLE_FAN,
+            }
+        }
+    }
+
+    impl Usage {
+        fn api(self) -> GLenum {
+            match self {
+                Usage::Static => gl::STATIC_DRAW,
+                Usage::Dynamic => gl::DYNAMIC_DRAW,
+                Usage::Stream => gl::STREAM_DRAW,
+            }
+        }
+    }
+
+    // a buffer along with the amount of bytes allocated for it
+    struct SizedBuf<'a> {
+        buf: Buf<'a>,
+        capacity: Cell<usize>,
+    }
+
+    impl<'a> SizedBuf<'a> {
+        fn new(ctx: &'a Context) -> Self {
+            Self {
+                buf: Buf::new(ctx),
+                capacity: Cell::new(0),
+            }
+        }
+
+        // reuses the existing storage when it is large enough
+        fn upload(&self, target: GLenum, size: usize, data: *const c_void, usage: Usage) {
+            gl::verify! { gl::BindBuffer(target, self.buf.0) };
+            if usage != Usage::Static && size <= self.capacity.get() {
+                gl::verify! { gl::BufferSubData(target, 0, size as _, data) };
+            } else {
+                gl::verify! { gl::BufferData(target, size as _, data, usage.api()) };
+                self.capacity.set(size);
+            }
+        }
+    }
+
+    // describes `layout` to the currently bound vao
+    // the attributes source their data from the currently bound array buffer
+    fn enable_attribs(layout: &VertexLayout, divisor: u32) {
+        let stride = layout.stride() as i32;
+        for a in layout.attribs() {
+            let offset = a.offset as *const c_void;
+            gl::verify! {
+                gl::EnableVertexAttribArray(a.location);
+                gl::VertexAttribDivisor(a.location, divisor);
+            }
+            if a.type_.is_integer() && !a.normalized {
+                gl::verify! { gl::VertexAttribIPointer(a.location, a.components as _, a.type_.api(), stride, offset) };
+            } else {
+                let normalized = if a.normalized { gl::TRUE } else { gl::FALSE };
+                gl::verify! { gl::VertexAttribPointer(a.location, a.components as _, a.type_.api(), normalized, stride, offset) };
+            }
+        }
+    }
+
+    pub struct Mesh<'a> {
+        ctx: &'a Context,
+        vao: Vao<'a>,
+        vbo: SizedBuf<'a>,
+        ibo: Option<SizedBuf<'a>>,
+        instances: Option<(SizedBuf<'a>, VertexLayout)>,
+        layout: VertexLayout,
+        topology: Topology,
+        usage: Usage,
+        vertex_count: usize,
+        index_count: usize,
+    }
+
+    impl<'a> Mesh<'a> {
+        pub(crate) fn bind(&self) {
+            gl::verify! { gl::BindVertexArray(self.vao.0) };
+        }
+    }
+
+    impl<'a> IMesh<'a> for Mesh<'a> {
+        fn new<'c: 'a>(
+            ctx: &'c Context,
+            layout: VertexLayout,
+            topology: Topology,
+            usage: Usage,
+        ) -> Self {
+            let vao = Vao::new(ctx);
+            let vbo = SizedBuf::new(ctx);
+
+            gl::verify! {
+                gl::BindVertexArray(vao.0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vbo.buf.0);
+            }
+            enable_attribs(&layout, 0);
+
+            Self {
+                ctx,
+                vao,
+                vbo,
+                ibo: None,
+                instances: None,
+                layout,
+                topology,
+                usage,
+                vertex_count: 0,
+                index_count: 0,
+            }
+        }
+
+        fn set_vertices<V: Copy>(&mut self, data: &[V]) {
+            let size = size_of_val(data);
+            let stride = self.layout.stride();
+            assert!(
+                stride > 0 && size.is_multiple_of(stride),
+                "Vertex data doesn't match the layout"
+            );
+
+            self.vbo
+                .upload(gl::ARRAY_BUFFER, size, data.as_ptr() as _, self.usage);
+            self.vertex_count = size / stride;
+        }
+
+        fn set_indices(&mut self, indices: &[u32]) {
+            let ctx = self.ctx;
+            let ibo = self.ibo.get_or_insert_with(|| SizedBuf::new(ctx));
+            // the element buffer binding is part of the vao's state
+            gl::verify! { gl::BindVertexArray(self.vao.0) };
+            ibo.upload(
+                gl::ELEMENT_ARRAY_BUFFER,
+                size_of_val(indices),
+                indices.as_ptr() as _,
+                self.usage,
+            );
+            self.index_count = indices.len();
+        }
+
+        fn clear_indices(&mut self) {
+            if self.ibo.take().is_some() {
+                gl::verify! {
+                    gl::BindVertexArray(self.vao.0);
+                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+                }
+            }
+            self.index_count = 0;
+        }
+
+        fn layout(&self) -> &VertexLayout {
+            &self.layout
+        }
+
+        fn vertex_count(&self) -> usize {
+            self.vertex_count
+        }
+
+        fn draw(&self) {
+            self.bind();
+            if self.ibo.is_some() {
+                gl::verify! { gl::DrawElements(self.topology.api(), self.index_count as _, gl::UNSIGNED_INT, null()) };
+            } else {
+                gl::verify! { gl::DrawArrays(self.topology.api(), 0, self.vertex_count as _) };
+            }
+        }
+
+        fn set_instance_layout(&mut self, layout: VertexLayout) {
+            let buf = SizedBuf::new(self.ctx);
+            gl::verify! {
+                gl::BindVertexArray(self.vao.0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, buf.buf.0);
+            }
+            enable_attribs(&layout, 1);
+            self.instances = Some((buf, layout));
+        }
+
+        fn draw_instanced<I: Copy>(&self, instances: &[I]) {
+            let (buf, layout) = self.instances.as_ref().expect("No instance layout set");
+            let size = size_of_val(instances);
+            let stride = layout.stride();
+            assert!(
+                stride > 0 && size.is_multiple_of(stride),
+                "Instance data doesn't match the layout"
+            );
+            let count = (size / stride) as i32;
+            if count == 0 {
+                return;
+            }
+
+            // instance data changes every frame
+            buf.upload(
+                gl::ARRAY_BUFFER,
+                size,
+                instances.as_ptr() as _,
+                Usage::Stream,
+            );
+            self.bind();
+            if self.ibo.is_some() {
+                gl::verify! { gl::DrawElementsInstanced(self.topology.api(), self.index_count as _, gl::UNSIGNED_INT, null(), count) };
+            } else {
+                gl::verify! { gl::DrawArraysInstanced(self.topology.api(), 0, self.vertex_count as _, count) };
+            }
+        }
+    }
+}
@@ -5,7 +5,11 @@
 mod api;
 pub use api::clear;
 pub use api::init;
+pub use api::set_clear_color;
 
+pub mod light;
+pub mod mesh;
+pub mod queue;
 pub mod shader;
 pub mod sprite;
 pub mod texture;
@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+
+use crate::math::Mat3;
+
+// api specific render state
+use imp::*;
+
+/// How a queued draw interacts with whatever is already on screen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Blend {
+    /// fully covers what is below it, blending is disabled
+    Opaque,
+    /// alpha blended over what is below it
+    #[default]
+    Transparent,
+}
+
+/// Where a queued draw ends up in the final image
+/// higher layers are drawn on top of lower layers
+/// within a layer, higher `z` values are drawn on top of lower ones
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DrawParams {
+    pub layer: i32,
+    pub z: f32,
+    pub blend: Blend,
+}
+
+impl DrawParams {
+    pub fn new(layer: i32, z: f32, blend: Blend) -> Self {
+        Self { layer, z, blend }
+    }
+}
+
+struct DrawCommand<'q> {
+    params: DrawParams,
+    // the sprite's position, used for y-sorting
+    y: f32,
+    draw: Box<dyn Fn() + 'q>,
+}
+
+/// Collects draws during a frame and submits them sorted by layer and depth
+///
+/// the sort is stable, so draws with equal keys keep their submission order
+/// opaque draws are submitted front to back with depth writes enabled,
+/// transparent draws are then submitted back to front on top of them
+#[derive(Default)]
+pub struct RenderQueue<'q> {
+    commands: Vec<DrawCommand<'q>>,
+    y_sort: bool,
+}
+
+impl<'q> RenderQueue<'q> {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            y_sort: false,
+        }
+    }
+
+    /// top-down mode: within the same layer and z,
+    /// draws lower on the screen (smaller y) are drawn on top
+    pub fn with_y_sort(mut self, y_sort: bool) -> Self {
+        self.y_sort = y_sort;
+        self
+    }
+
+    pub fn set_y_sort(&mut self, y_sort: bool) {
+        self.y_sort = y_sort;
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// queues an arbitrary draw call
+    /// `sprite_matrix` is only used to determine the y-sort position
+    pub fn submit(&mut self, params: DrawParams, sprite_matrix: Mat3, draw: impl Fn() + 'q) {
+        self.commands.push(DrawCommand {
+            params,
+            y: sprite_matrix.translation().y,
+            draw: Box::new(draw),
+        });
+    }
+
+    /// sorts and draws everything in the queue, leaving it empty
+    pub fn flush(&mut self) {
+        let y_sort = self.y_sort;
+        // back to front
+        self.commands.sort_by(|a, b| Self::compare(a, b, y_sort));
+
+        let n = self.commands.len();
+        // front-most draw gets the smallest depth
+        let depth_of = |idx: usize| 1.0 - (idx + 1) as f32 / (n + 1) as f32;
+
+        begin_opaque_pass();
+        for (idx, cmd) in self.commands.iter().enumerate().rev() {
+            if cmd.params.blend == Blend::Opaque {
+                set_depth(depth_of(idx));
+                (cmd.draw)();
+            }
+        }
+
+        begin_transparent_pass();
+        for (idx, cmd) in self.commands.iter().enumerate() {
+            if cmd.params.blend == Blend::Transparent {
+                set_depth(depth_of(idx));
+                (cmd.draw)();
+            }
+        }
+
+        end_passes();
+        self.commands.clear();
+    }
+
+    fn compare(a: &DrawCommand, b: &DrawCommand, y_sort: bool) -> Ordering {
+        let ret = a
+            .params
+            .layer
+            .cmp(&b.params.layer)
+            .then(a.params.z.total_cmp(&b.params.z));
+
+        if y_sort {
+            // higher up means further back
+            ret.then(b.y.total_cmp(&a.y))
+        } else {
+            ret
+        }
+    }
+}
+
+#[cfg(feature = "gl45")]
+mod imp {
+    use crate::render::api as gl;
+
+    pub(super) fn begin_opaque_pass() {
+        gl::verify! {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+    }
+
+    pub(super) fn begin_transparent_pass() {
+        gl::verify! {
+            gl::DepthMask(gl::FALSE);
+            gl::Enable(gl::BLEND);
+        }
+    }
+
+    pub(super) fn end_passes() {
+        gl::verify! {
+            gl::DepthRangef(0.0, 1.0);
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+    }
+
+    // sprites are drawn at z = 0 in clip space
+    // collapsing the depth range places every fragment at `depth`
+    // so existing shaders work without modification
+    pub(super) fn set_depth(depth: f32) {
+        gl::verify! { gl::DepthRangef(depth, depth) };
+    }
+}
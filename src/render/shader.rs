@@ -43,6 +43,7 @@ pub trait IShaderBuilder<'c>: Sized {
 
 #[cfg(feature = "gl45")]
 mod imp {
+    use std::ffi::CString;
     use std::ptr::null_mut;
 
     use super::Context;
@@ -51,15 +52,19 @@ mod imp {
     use super::IShaderBuilder;
     use super::PartType;
     use super::ShaderPart;
+    use crate::math::Color;
+    use crate::math::Mat2;
     use crate::math::Mat3;
+    use crate::math::Mat4;
+    use crate::math::Vec2;
+    use crate::math::Vec3;
+    use crate::math::Vec4;
     use crate::render::api as gl;
     use crate::render::api::types::GLenum;
 
     impl<'a> IParameter<Shader<'a>> for f32 {
         fn location(&self, shader: &Shader, name: &str) -> usize {
-            let l =
-                gl::verify! { gl::GetUniformLocation(shader.0.0, name.as_bytes().as_ptr() as _) };
-            l as _
+            shader.uniform_location(name)
         }
 
         fn set(&self, location: usize) {
@@ -69,9 +74,7 @@ mod imp {
 
     impl<'a> IParameter<Shader<'a>> for Mat3 {
         fn location(&self, shader: &Shader, name: &str) -> usize {
-            let l =
-                gl::verify! { gl::GetUniformLocation(shader.0.0, name.as_bytes().as_ptr() as _) };
-            l as _
+            shader.uniform_location(name)
         }
 
         fn set(&self, location: usize) {
@@ -79,11 +82,69 @@ mod imp {
         }
     }
 
+    impl<'a> IParameter<Shader<'a>> for Mat2 {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::UniformMatrix2fv(location as _, 1, gl::FALSE, self.as_ptr()) };
+        }
+    }
+
+    impl<'a> IParameter<Shader<'a>> for Mat4 {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::UniformMatrix4fv(location as _, 1, gl::FALSE, self.as_ptr()) };
+        }
+    }
+
+    impl<'a> IParameter<Shader<'a>> for Vec2 {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::Uniform2f(location as _, self.x, self.y) };
+        }
+    }
+
+    impl<'a> IParameter<Shader<'a>> for Vec3 {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::Uniform3f(location as _, self.x, self.y, self.z) };
+        }
+    }
+
+    impl<'a> IParameter<Shader<'a>> for Vec4 {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::Uniform4f(location as _, self.x, self.y, self.z, self.w) };
+        }
+    }
+
+    impl<'a> IParameter<Shader<'a>> for Color {
+        fn location(&self, shader: &Shader, name: &str) -> usize {
+            shader.uniform_location(name)
+        }
+
+        fn set(&self, location: usize) {
+            gl::verify! { gl::Uniform4f(location as _, self.r, self.g, self.b, self.a) };
+        }
+    }
+
     impl<'a> IParameter<Shader<'a>> for i32 {
         fn location(&self, shader: &Shader, name: &str) -> usize {
-            let l =
-                gl::verify! { gl::GetUniformLocation(shader.0.0, name.as_bytes().as_ptr() as _) };
-            l as _
+            shader.uniform_location(name)
         }
 
         fn set(&self, location: usize) {
@@ -97,6 +158,13 @@ mod imp {
         pub(crate) fn bind(&self) {
             gl::verify! { gl::UseProgram(self.0.0) };
         }
+
+        // OpenGL expects a nul terminated name
+        fn uniform_location(&self, name: &str) -> usize {
+            let name = CString::new(name).unwrap();
+            let l = gl::verify! { gl::GetUniformLocation(self.0.0, name.as_ptr()) };
+            l as _
+        }
     }
 
     impl<'a> IShader for Shader<'a> {
@@ -1,5 +1,9 @@
+use crate::math::Color;
 use crate::math::Mat3;
+use crate::math::Vec4;
 
+use super::queue::DrawParams;
+use super::queue::RenderQueue;
 use super::shader::IShader;
 use super::texture::ITexture;
 use crate::window::DrawContext as Context;
@@ -7,72 +11,190 @@ use crate::window::DrawContext as Context;
 // export implementation
 pub use imp::*;
 
+/// Per-instance data for `ISprite::draw_instanced`
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Instance {
+    pub transform: Mat3,
+    /// the part of the texture to sample: (x, y, width, height) in uv space
+    pub uv_rect: Vec4,
+    /// multiplied with the sampled color
+    pub tint: Color,
+}
+
+impl Instance {
+    pub fn new(transform: Mat3) -> Self {
+        Self {
+            transform,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self {
+            transform: Mat3::identity(),
+            uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            tint: Color::WHITE,
+        }
+    }
+}
+
+/// Shader sources compatible with `ISprite::draw_instanced`
+/// the per-instance attributes are:
+/// location 2-4: aTransform (mat3)
+/// location 5: aUVRect
+/// location 6: aTint
+pub const INSTANCED_VERTEX_SHADER: &str = r#"
+#version 450 core
+
+layout(location = 0)
+in vec2 aPos;
+
+layout(location = 1)
+in vec2 aUV;
+
+layout(location = 2)
+in mat3 aTransform;
+
+layout(location = 5)
+in vec4 aUVRect;
+
+layout(location = 6)
+in vec4 aTint;
+
+out vec2 texUV;
+out vec4 tint;
+
+void main() {
+    texUV = aUVRect.xy + aUV * aUVRect.zw;
+    tint = aTint;
+
+    gl_Position = vec4(aTransform * vec3(aPos, 1.0), 1.0);
+}
+"#;
+
+pub const INSTANCED_FRAGMENT_SHADER: &str = r#"
+#version 450 core
+
+uniform sampler2D uTexture;
+
+in vec2 texUV;
+in vec4 tint;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = tint * texture(uTexture, texUV);
+}
+"#;
+
 pub trait ISprite<'a, Shader: IShader, Texture: ITexture<'a>>: Sized {
     fn new<'c: 'a>(ctx: &'c Context, texture: Texture) -> Self;
+
+    /// a tangent space normal map, sampled in the shader as `uNormalMap`
+    /// should be loaded with `ColorSpace::Linear`
+    fn set_normal_map(&mut self, normal_map: Texture);
     fn draw(&self, shader: &Shader, sprite_matrix: Mat3);
+
+    /// draws the sprite once per instance in a single draw call
+    /// `shader` must consume the per-instance attributes described in `INSTANCED_VERTEX_SHADER`
+    fn draw_instanced(&self, shader: &Shader, instances: &[Instance]);
+
+    /// like `draw`, but deferred until the queue is flushed
+    /// so that the draw is ordered according to `params`
+    fn enqueue<'q>(
+        &'q self,
+        queue: &mut RenderQueue<'q>,
+        shader: &'q Shader,
+        sprite_matrix: Mat3,
+        params: DrawParams,
+    ) {
+        queue.submit(params, sprite_matrix, move || {
+            self.draw(shader, sprite_matrix)
+        });
+    }
 }
 
 #[cfg(feature = "gl45")]
 mod imp {
-    use std::ffi::c_void;
-    use std::mem::size_of;
-    use std::mem::size_of_val;
-
     use super::Context;
     use crate::math::Mat3;
-    use crate::render::api as gl;
-    use crate::render::api::Buf;
-    use crate::render::api::Vao;
+    use crate::render::mesh::AttribType;
+    use crate::render::mesh::IMesh;
+    use crate::render::mesh::Mesh;
+    use crate::render::mesh::Topology;
+    use crate::render::mesh::Usage;
+    use crate::render::mesh::VertexLayout;
     use crate::render::shader::IShader;
     use crate::render::shader::Shader;
     use crate::render::texture::Texture;
 
     use super::ISprite;
+    use super::Instance;
 
     pub struct Sprite<'a> {
-        vao: gl::Vao<'a>,
-        /// vbo will never be read from
-        /// it is merely used to track the lifetime
-        /// of the vertex data
-        #[allow(dead_code)]
-        vbo: gl::Buf<'a>,
+        mesh: Mesh<'a>,
         tex: Texture<'a>,
+        normal_map: Option<Texture<'a>>,
+    }
+
+    impl<'a> Sprite<'a> {
+        fn bind_textures(&self, shader: &Shader) {
+            shader.bind();
+            let slot = 0;
+            self.tex.bind(slot);
+            shader.set_parameter("uTexture", &(slot as i32));
+
+            if let Some(normal_map) = &self.normal_map {
+                let slot = 1;
+                normal_map.bind(slot);
+                shader.set_parameter("uNormalMap", &(slot as i32));
+            }
+        }
     }
 
     impl<'a> ISprite<'a, Shader<'a>, Texture<'a>> for Sprite<'a> {
         fn new<'c: 'a>(ctx: &'c Context, tex: Texture<'a>) -> Self {
-            let vao = Vao::new(ctx);
-            let vbo = Buf::new(ctx);
+            let layout = VertexLayout::new()
+                .attrib(2, AttribType::F32) // aPos
+                .attrib(2, AttribType::F32); // aUV
+            let mut mesh = Mesh::new(ctx, layout, Topology::TriangleFan, Usage::Static);
+            let instance_layout = VertexLayout::starting_at(2)
+                .attrib(3, AttribType::F32) // aTransform
+                .attrib(3, AttribType::F32)
+                .attrib(3, AttribType::F32)
+                .attrib(4, AttribType::F32) // aUVRect
+                .attrib(4, AttribType::F32); // aTint
+            mesh.set_instance_layout(instance_layout);
             let vertex_data: [f32; 16] = [
                 // aPos     aUV
                 -1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -1.0, 1.0, 0.0, -1.0, -1.0, 0.0, 0.0,
             ];
-            gl::verify! {
-                gl::BindVertexArray(vao.0);
-                gl::BindBuffer(gl::ARRAY_BUFFER, vbo.0);
-                gl::BufferData(gl::ARRAY_BUFFER, size_of_val(&vertex_data) as _, vertex_data.as_ptr() as _, gl::STATIC_DRAW);
-                gl::EnableVertexAttribArray(0);
-                #[allow(clippy::erasing_op)]
-                gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 4 * size_of::<f32>() as i32, (0 * size_of::<f32>()) as *const c_void);
-                gl::EnableVertexAttribArray(1);
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, 4 * size_of::<f32>() as i32, (2 * size_of::<f32>()) as *const c_void);
+            mesh.set_vertices(&vertex_data);
+
+            Self {
+                mesh,
+                tex,
+                normal_map: None,
             }
+        }
 
-            Self { vao, vbo, tex }
+        fn set_normal_map(&mut self, normal_map: Texture<'a>) {
+            self.normal_map = Some(normal_map);
         }
 
         fn draw(&self, shader: &Shader, sprite_matrix: Mat3) {
-            shader.bind();
-            let slot = 0;
-            self.tex.bind(slot);
-            shader.set_parameter("uTexture", &(slot as i32));
-
+            self.bind_textures(shader);
             shader.set_parameter("uSprite", &sprite_matrix);
 
-            gl::verify! {
-                gl::BindVertexArray(self.vao.0);
-                gl::DrawArrays(gl::TRIANGLE_FAN, 0, 4);
-            }
+            self.mesh.draw();
+        }
+
+        fn draw_instanced(&self, shader: &Shader, instances: &[Instance]) {
+            self.bind_textures(shader);
+            self.mesh.draw_instanced(instances);
         }
     }
 }
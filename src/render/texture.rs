@@ -16,21 +16,53 @@ pub enum Filtering {
     Nearest,
 }
 
+/// How the stored pixel values are interpreted when sampled
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// color textures, converted to linear when sampled
+    #[default]
+    Srgb,
+    /// data textures such as normal maps, sampled as is
+    Linear,
+}
+
 pub trait ITexture<'a> {
     #[allow(clippy::result_unit_err)]
     fn from_file<'c: 'a>(ctx: &'c Context, path: &Path) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        Self::from_file_in(ctx, path, ColorSpace::Srgb)
+    }
+
+    #[allow(clippy::result_unit_err)]
+    fn from_file_in<'c: 'a>(ctx: &'c Context, path: &Path, space: ColorSpace) -> Result<Self, ()>
     where
         Self: Sized,
     {
         let img = image::open(path).map_err(|_| ())?;
         let img = img.flipv();
         let size = img.dimensions().into();
-        let pixel_data = img.as_bytes().as_ptr();
+        // pixel data is always uploaded as rgba8
+        let img = img.to_rgba8();
+        let pixel_data = img.as_ptr();
 
-        Ok(Self::from_memory(ctx, size, pixel_data as _))
+        Ok(Self::from_memory_in(ctx, size, pixel_data as _, space))
     }
 
-    fn from_memory<'c: 'a>(ctx: &'c Context, size: UVec2, pixel_data: *const c_void) -> Self;
+    fn from_memory<'c: 'a>(ctx: &'c Context, size: UVec2, pixel_data: *const c_void) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_memory_in(ctx, size, pixel_data, ColorSpace::Srgb)
+    }
+
+    fn from_memory_in<'c: 'a>(
+        ctx: &'c Context,
+        size: UVec2,
+        pixel_data: *const c_void,
+        space: ColorSpace,
+    ) -> Self;
     fn set_filtering(&mut self, option: Filtering);
     fn size(&self) -> UVec2;
 }
@@ -44,9 +76,19 @@ mod imp {
     use crate::render::api as gl;
     use crate::render::api::types::GLenum;
 
+    use super::ColorSpace;
     use super::Filtering;
     use super::ITexture;
 
+    impl ColorSpace {
+        fn api(self) -> GLenum {
+            match self {
+                ColorSpace::Srgb => gl::SRGB8_ALPHA8,
+                ColorSpace::Linear => gl::RGBA8,
+            }
+        }
+    }
+
     impl Filtering {
         fn api(self) -> GLenum {
             match self {
@@ -72,7 +114,12 @@ mod imp {
 
     impl<'a> ITexture<'a> for Texture<'a> {
         #[allow(clippy::not_unsafe_ptr_arg_deref)]
-        fn from_memory<'c: 'a>(ctx: &'c Context, size: UVec2, pixel_data: *const c_void) -> Self {
+        fn from_memory_in<'c: 'a>(
+            ctx: &'c Context,
+            size: UVec2,
+            pixel_data: *const c_void,
+            space: ColorSpace,
+        ) -> Self {
             let o = gl::Texture::new(ctx);
             gl::verify! {
                 gl::BindTexture(gl::TEXTURE_2D, o.0);
@@ -80,7 +127,7 @@ mod imp {
                 gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as _);
                 gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
                 gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
-                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::SRGB_ALPHA as _, size.x as _, size.y as _, 0, gl::RGBA, gl::UNSIGNED_BYTE, pixel_data);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, space.api() as _, size.x as _, size.y as _, 0, gl::RGBA, gl::UNSIGNED_BYTE, pixel_data);
             }
 
             Self { o, size }